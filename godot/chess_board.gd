@@ -20,6 +20,7 @@ var chess_game: ChessGame
 var selected_square: Vector2i = Vector2i(-1, -1)
 var legal_moves: Array = []
 var ai_plays_black: bool = false
+var ai_difficulty: String = "random"
 var pending_promotion_move: Vector2i = Vector2i(-1, -1)
 
 # Network state
@@ -46,6 +47,7 @@ var white_clock_label: Label
 var black_clock_label: Label
 var clock_timer: Timer
 var ai_toggle: CheckBox
+var ai_difficulty_dropdown: OptionButton
 var clock_preset_dropdown: OptionButton
 
 # Network UI nodes
@@ -220,6 +222,16 @@ func setup_ai_toggle():
 	ai_toggle.toggled.connect(_on_ai_toggle_changed)
 	add_child(ai_toggle)
 
+	# AI difficulty dropdown, next to the toggle
+	ai_difficulty_dropdown = OptionButton.new()
+	ai_difficulty_dropdown.position = Vector2(BOARD_SIZE + 200, 200)
+	ai_difficulty_dropdown.custom_minimum_size = Vector2(120, 30)
+	ai_difficulty_dropdown.add_theme_font_size_override("font_size", 14)
+	for difficulty in ["Random", "Easy", "Medium", "Hard"]:
+		ai_difficulty_dropdown.add_item(difficulty)
+	ai_difficulty_dropdown.item_selected.connect(_on_ai_difficulty_changed)
+	add_child(ai_difficulty_dropdown)
+
 func setup_network_ui():
 	# Network section label
 	var network_label = Label.new()
@@ -472,6 +484,18 @@ func update_status():
 			status_label.text = "Stalemate! Draw."
 		"draw":
 			status_label.text = "Draw by insufficient material."
+		"draw_repetition":
+			status_label.text = "Draw by fivefold repetition."
+		"draw_fifty_move":
+			status_label.text = "Draw by the fifty-move rule."
+		"draw_timeout":
+			status_label.text = "Both players out of time! Draw."
+		"king_captured_white":
+			status_label.text = "King captured! White wins!"
+		"king_captured_black":
+			status_label.text = "King captured! Black wins!"
+		"invalid":
+			status_label.text = "Invalid position: no kings on the board."
 		"check":
 			status_label.text = "Check! %s to move" % turn.capitalize()
 		_:
@@ -714,6 +738,9 @@ func _on_ai_toggle_changed(is_checked: bool):
 	ai_plays_black = is_checked
 	check_ai_turn()
 
+func _on_ai_difficulty_changed(index: int):
+	ai_difficulty = ai_difficulty_dropdown.get_item_text(index).to_lower()
+
 func _on_clock_preset_changed(index: int):
 	apply_clock_preset(index)
 
@@ -766,7 +793,7 @@ func make_ai_move():
 	# Small delay to make the AI move visible
 	await get_tree().create_timer(0.3).timeout
 
-	if chess_game.make_ai_move():
+	if chess_game.make_ai_move_with_difficulty(ai_difficulty):
 		update_board()
 		update_status()
 		update_clock_display()
@@ -951,16 +978,14 @@ func _apply_server_board_state(board_state: Array):
 		var piece_id = piece_state.get("id", -1)
 		var position = piece_state.get("position", "")
 		var piece_type = piece_state.get("piece_type", "")
+		# Promoted pieces have IDs from 32 up, so the color comes with the piece
+		var color = piece_state.get("color", "").to_lower()
 
-		if piece_id < 0 or position == "" or piece_type == "":
+		if piece_id < 0 or position == "" or piece_type == "" or color == "":
 			continue
 
 		seen_piece_ids[piece_id] = true
 
-		# Determine color from ID
-		var is_white = piece_id < 16
-		var color = "white" if is_white else "black"
-
 		# Convert algebraic position to board coordinates
 		var pos_vec = _algebraic_to_position(position)
 		if pos_vec != null:
@@ -15,11 +15,21 @@ enum NetworkState {
 # Signals
 signal connection_established
 signal connection_failed(reason: String)
+signal authenticated(player_id: String)
 signal matchmaking_joined
+signal matchmaking_left
+signal matchmaking_status(queue_position: int)
+signal room_created(code: String)
 signal match_found(game_id: String, opponent_id: String, your_color: String)
 signal game_state_updated(state: Dictionary)
+signal state_delta_received(delta: Dictionary)
 signal opponent_action_received(action: Dictionary)
+signal draw_offered
+signal draw_declined
 signal game_over(winner: String, reason: String)
+signal chat_received(from: String, text: String)
+signal rematch_offered(game_id: String)
+signal rematch_started(previous_game_id: String, game_id: String)
 signal invalid_action(reason: String)
 signal error_received(message: String)
 
@@ -35,6 +45,8 @@ var player_id: String = ""
 var current_game_id: String = ""
 var your_color: String = ""
 var opponent_id: String = ""
+var game_seed: int = 0  # Seed of the game's RNG, for reproducing random card effects
+var last_game_id: String = ""  # Most recently finished game, for rematches
 
 func _ready():
 	websocket = WebSocketPeer.new()
@@ -99,6 +111,18 @@ func disconnect_from_server() -> void:
 		opponent_id = ""
 		DebugUtils.debug("Disconnected from server")
 
+func authenticate(token: String) -> void:
+	if state != NetworkState.CONNECTED:
+		DebugUtils.debug("Not connected to server")
+		return
+
+	var message = {
+		"type": "Authenticate",
+		"token": token
+	}
+	_send_message(message)
+	DebugUtils.debug("Authenticating")
+
 func join_matchmaking() -> void:
 	if state != NetworkState.CONNECTED:
 		DebugUtils.debug("Not connected to server")
@@ -112,6 +136,78 @@ func join_matchmaking() -> void:
 	state = NetworkState.IN_MATCHMAKING
 	DebugUtils.debug("Joining matchmaking queue")
 
+func reconnect_to_game(game_id: String) -> void:
+	if state != NetworkState.CONNECTED:
+		DebugUtils.debug("Not connected to server")
+		return
+
+	var message = {
+		"type": "Reconnect",
+		"game_id": game_id,
+		"player_id": player_id
+	}
+	_send_message(message)
+	current_game_id = game_id
+	state = NetworkState.IN_GAME
+	DebugUtils.debug_var("Reconnecting to game", game_id)
+
+func leave_matchmaking() -> void:
+	if state != NetworkState.IN_MATCHMAKING:
+		DebugUtils.debug("Not in matchmaking")
+		return
+
+	var message = {
+		"type": "LeaveMatchmaking",
+		"player_id": player_id
+	}
+	_send_message(message)
+	state = NetworkState.CONNECTED
+	DebugUtils.debug("Leaving matchmaking queue")
+
+func play_vs_computer(difficulty: int = 0) -> void:
+	if state != NetworkState.CONNECTED:
+		DebugUtils.debug("Not connected to server")
+		return
+
+	var message = {
+		"type": "PlayVsComputer",
+		"player_id": player_id,
+		"difficulty": difficulty
+	}
+	_send_message(message)
+	DebugUtils.debug("Requesting a game against the computer")
+
+func create_room(initial_seconds: int = 0, increment_seconds: int = 0) -> void:
+	if state != NetworkState.CONNECTED:
+		DebugUtils.debug("Not connected to server")
+		return
+
+	var message = {
+		"type": "CreateRoom",
+		"player_id": player_id
+	}
+	# Leave the time control out for an untimed game
+	if initial_seconds > 0:
+		message["time_control"] = {
+			"initial_seconds": initial_seconds,
+			"increment_seconds": increment_seconds
+		}
+	_send_message(message)
+	DebugUtils.debug("Creating a room")
+
+func join_room(code: String) -> void:
+	if state != NetworkState.CONNECTED:
+		DebugUtils.debug("Not connected to server")
+		return
+
+	var message = {
+		"type": "JoinRoom",
+		"player_id": player_id,
+		"code": code
+	}
+	_send_message(message)
+	DebugUtils.debug_var("Joining room", code)
+
 func submit_move(from_row: int, from_col: int, to_row: int, to_col: int, promotion: String = "") -> void:
 	if state != NetworkState.IN_GAME or current_game_id == "":
 		DebugUtils.debug("Not in a game")
@@ -153,6 +249,171 @@ func resign() -> void:
 	DebugUtils.debug("Resigning from game")
 	_send_message(message)
 
+func offer_draw() -> void:
+	if state != NetworkState.IN_GAME or current_game_id == "":
+		DebugUtils.debug("Not in a game")
+		return
+
+	var action = {
+		"action_type": "OfferDraw"
+	}
+
+	var message = {
+		"type": "SubmitAction",
+		"game_id": current_game_id,
+		"action": action
+	}
+
+	DebugUtils.debug("Offering a draw")
+	_send_message(message)
+
+func accept_draw() -> void:
+	if state != NetworkState.IN_GAME or current_game_id == "":
+		DebugUtils.debug("Not in a game")
+		return
+
+	var action = {
+		"action_type": "AcceptDraw"
+	}
+
+	var message = {
+		"type": "SubmitAction",
+		"game_id": current_game_id,
+		"action": action
+	}
+
+	DebugUtils.debug("Accepting the draw offer")
+	_send_message(message)
+
+func decline_draw() -> void:
+	if state != NetworkState.IN_GAME or current_game_id == "":
+		DebugUtils.debug("Not in a game")
+		return
+
+	var action = {
+		"action_type": "DeclineDraw"
+	}
+
+	var message = {
+		"type": "SubmitAction",
+		"game_id": current_game_id,
+		"action": action
+	}
+
+	DebugUtils.debug("Declining the draw offer")
+	_send_message(message)
+
+func request_takeback() -> void:
+	if state != NetworkState.IN_GAME or current_game_id == "":
+		DebugUtils.debug("Not in a game")
+		return
+
+	var action = {
+		"action_type": "RequestTakeback"
+	}
+
+	var message = {
+		"type": "SubmitAction",
+		"game_id": current_game_id,
+		"action": action
+	}
+
+	DebugUtils.debug("Requesting a takeback")
+	_send_message(message)
+
+func accept_takeback() -> void:
+	if state != NetworkState.IN_GAME or current_game_id == "":
+		DebugUtils.debug("Not in a game")
+		return
+
+	var action = {
+		"action_type": "AcceptTakeback"
+	}
+
+	var message = {
+		"type": "SubmitAction",
+		"game_id": current_game_id,
+		"action": action
+	}
+
+	DebugUtils.debug("Accepting the takeback request")
+	_send_message(message)
+
+func decline_takeback() -> void:
+	if state != NetworkState.IN_GAME or current_game_id == "":
+		DebugUtils.debug("Not in a game")
+		return
+
+	var action = {
+		"action_type": "DeclineTakeback"
+	}
+
+	var message = {
+		"type": "SubmitAction",
+		"game_id": current_game_id,
+		"action": action
+	}
+
+	DebugUtils.debug("Declining the takeback request")
+	_send_message(message)
+
+func play_card(card_id: String, target_row: int = -1, target_col: int = -1) -> void:
+	if state != NetworkState.IN_GAME or current_game_id == "":
+		DebugUtils.debug("Not in a game")
+		return
+
+	var action = {
+		"action_type": "PlayCard",
+		"card_id": card_id
+	}
+
+	if target_row >= 0 and target_col >= 0:
+		action["target"] = {"row": target_row, "col": target_col}
+
+	var message = {
+		"type": "SubmitAction",
+		"game_id": current_game_id,
+		"action": action
+	}
+
+	DebugUtils.debug_var("Playing card", card_id)
+	_send_message(message)
+
+func spectate_game(game_id: String) -> void:
+	var message = {
+		"type": "SpectateGame",
+		"game_id": game_id
+	}
+
+	DebugUtils.debug_var("Spectating game", game_id)
+	_send_message(message)
+
+func send_chat(text: String) -> void:
+	if state != NetworkState.IN_GAME or current_game_id == "":
+		DebugUtils.debug("Not in a game")
+		return
+
+	var message = {
+		"type": "SendChat",
+		"game_id": current_game_id,
+		"text": text
+	}
+
+	_send_message(message)
+
+func request_rematch() -> void:
+	if last_game_id == "":
+		DebugUtils.debug("No finished game to rematch")
+		return
+
+	var message = {
+		"type": "RequestRematch",
+		"game_id": last_game_id
+	}
+
+	DebugUtils.debug("Requesting a rematch")
+	_send_message(message)
+
 func leave_game() -> void:
 	if current_game_id == "":
 		return
@@ -187,14 +448,34 @@ func _handle_server_message(json_string: String) -> void:
 	DebugUtils.debug_var("Handling message type", msg_type)
 
 	match msg_type:
+		"Authenticated":
+			player_id = message.get("player_id", player_id)
+			DebugUtils.debug_var("Authenticated as", player_id)
+			authenticated.emit(player_id)
+
 		"MatchmakingJoined":
 			DebugUtils.debug("Matchmaking joined")
 			matchmaking_joined.emit()
 
+		"MatchmakingLeft":
+			DebugUtils.debug("Matchmaking left")
+			matchmaking_left.emit()
+
+		"MatchmakingStatus":
+			var queue_position = int(message.get("queue_position", 0))
+			DebugUtils.debug_var("Matchmaking queue position", queue_position)
+			matchmaking_status.emit(queue_position)
+
+		"RoomCreated":
+			var code = message.get("code", "")
+			DebugUtils.debug_var("Room created", code)
+			room_created.emit(code)
+
 		"MatchFound":
 			current_game_id = message.get("game_id", "")
 			opponent_id = message.get("opponent_id", "")
 			your_color = message.get("your_color", "").to_lower()
+			game_seed = int(message.get("seed", 0))
 			state = NetworkState.IN_GAME
 			DebugUtils.debug_vars({
 				"Match found - Game ID": current_game_id,
@@ -208,21 +489,53 @@ func _handle_server_message(json_string: String) -> void:
 			DebugUtils.debug_var("Game state update", game_state)
 			game_state_updated.emit(game_state)
 
+		"StateDelta":
+			var delta = message.get("delta", {})
+			DebugUtils.debug_var("State delta", delta)
+			state_delta_received.emit(delta)
+
 		"OpponentAction":
 			var action = message.get("action", {})
 			DebugUtils.debug_var("Opponent action", action)
 			opponent_action_received.emit(action)
 
+		"DrawOffered":
+			DebugUtils.debug("Opponent offers a draw")
+			draw_offered.emit()
+
+		"DrawDeclined":
+			DebugUtils.debug("Opponent declined the draw offer")
+			draw_declined.emit()
+
 		"GameOver":
 			var winner = message.get("winner", null)
 			var reason = message.get("reason", "")
 			DebugUtils.debug_vars({"Game over - Winner": winner, "Reason": reason})
 			game_over.emit(str(winner) if winner != null else "", reason)
+			last_game_id = current_game_id
 			current_game_id = ""
 			your_color = ""
 			opponent_id = ""
 			state = NetworkState.CONNECTED
 
+		"ChatMessage":
+			var from = message.get("from", "")
+			var text = message.get("text", "")
+			DebugUtils.debug_vars({"Chat from": from, "Text": text})
+			chat_received.emit(from, text)
+
+		"RematchOffered":
+			var rematch_game_id = message.get("game_id", "")
+			DebugUtils.debug("Opponent wants a rematch")
+			rematch_offered.emit(rematch_game_id)
+
+		"RematchStarted":
+			var previous_game_id = message.get("previous_game_id", "")
+			var new_game_id = message.get("game_id", "")
+			DebugUtils.debug_var("Rematch starting", new_game_id)
+			last_game_id = ""
+			rematch_started.emit(previous_game_id, new_game_id)
+
 		"InvalidAction":
 			var reason = message.get("reason", "")
 			DebugUtils.debug_var("Invalid action", reason)
@@ -0,0 +1,11 @@
+-- Create ratings table
+-- Elo rating per player ID, updated by the server when a rated game ends
+CREATE TABLE IF NOT EXISTS ratings (
+    player_id VARCHAR(255) PRIMARY KEY,
+    rating DOUBLE PRECISION NOT NULL DEFAULT 1000,
+    games_played INTEGER NOT NULL DEFAULT 0,
+    updated_on TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+-- Index for leaderboards
+CREATE INDEX idx_ratings_rating ON ratings(rating DESC);
@@ -3,4 +3,79 @@
 
 pub mod evaluation;
 pub mod search;
+pub mod self_play;
 pub mod simple_opponent;
+
+use crate::game::board::Board;
+use crate::game::piece::Move;
+
+/// Strength of the computer opponent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Difficulty {
+    /// Weighted random moves, the casual opponent
+    #[default]
+    Random,
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    /// Map a numeric level (0 = Random, 1-3 = search depth) to a difficulty; higher levels are Hard
+    pub fn from_level(level: u8) -> Self {
+        match level {
+            0 => Difficulty::Random,
+            1 => Difficulty::Easy,
+            2 => Difficulty::Medium,
+            _ => Difficulty::Hard,
+        }
+    }
+
+    /// Parse a difficulty name ("random", "easy", "medium", "hard"), case-insensitively
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "random" => Some(Difficulty::Random),
+            "easy" => Some(Difficulty::Easy),
+            "medium" => Some(Difficulty::Medium),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+
+    /// Alpha-beta search depth in plies, None for the weighted random opponent
+    pub fn search_depth(self) -> Option<u32> {
+        match self {
+            Difficulty::Random => None,
+            Difficulty::Easy => Some(1),
+            Difficulty::Medium => Some(2),
+            Difficulty::Hard => Some(3),
+        }
+    }
+
+    /// Pick a move for the side to move, None if there is no legal move
+    pub fn choose_move(self, board: &Board) -> Option<Move> {
+        match self.search_depth() {
+            None => simple_opponent::select_weighted_move(board),
+            Some(depth) => search::find_best_move(board, depth),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_difficulty_levels_and_names() {
+        assert_eq!(Difficulty::from_level(0), Difficulty::Random);
+        assert_eq!(Difficulty::from_level(2), Difficulty::Medium);
+        assert_eq!(Difficulty::from_level(9), Difficulty::Hard);
+
+        assert_eq!(Difficulty::from_name("Hard"), Some(Difficulty::Hard));
+        assert_eq!(Difficulty::from_name("impossible"), None);
+
+        assert_eq!(Difficulty::Random.search_depth(), None);
+        assert!(Difficulty::Easy.search_depth() < Difficulty::Medium.search_depth());
+        assert!(Difficulty::Medium.search_depth() < Difficulty::Hard.search_depth());
+    }
+}
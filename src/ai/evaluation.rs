@@ -1 +1,353 @@
 // Position evaluation with incomplete information
+use crate::game::board::Board;
+use crate::game::piece::{Color, PieceType, Position};
+use crate::game::rules::{generate_all_legal_moves, is_in_check};
+
+// Game phase weights: rooks and queens keep the middlegame going more than minor pieces
+const MINOR_PHASE: i32 = 1;
+const MAJOR_PHASE: i32 = 3;
+
+/// Phase of the starting position, blending fully into the middlegame tables; 0 is a bare endgame
+const OPENING_PHASE: i32 = 2 * (4 * MINOR_PHASE + 3 * MAJOR_PHASE);
+
+/// Centipawns per legal move available to a side
+const MOBILITY_WEIGHT: i32 = 2;
+
+/// Penalty for the side whose king is in check
+const CHECK_PENALTY: i32 = 30;
+
+// Piece-square tables from White's point of view, indexed [row][col] (row 0 = rank 1)
+// Pawns are pushed towards promotion, the center pawns off their home squares first
+#[rustfmt::skip]
+const PAWN_TABLE: [[i32; 8]; 8] = [
+    [  0,   0,   0,   0,   0,   0,   0,   0],
+    [  5,  10,  10, -20, -20,  10,  10,   5],
+    [  5,  -5, -10,   0,   0, -10,  -5,   5],
+    [  0,   0,   0,  20,  20,   0,   0,   0],
+    [  5,   5,  10,  25,  25,  10,   5,   5],
+    [ 10,  10,  20,  30,  30,  20,  10,  10],
+    [ 50,  50,  50,  50,  50,  50,  50,  50],
+    [  0,   0,   0,   0,   0,   0,   0,   0],
+];
+
+// Knights are strong in the center and poor on the rim
+#[rustfmt::skip]
+const KNIGHT_TABLE: [[i32; 8]; 8] = [
+    [-50, -40, -30, -30, -30, -30, -40, -50],
+    [-40, -20,   0,   5,   5,   0, -20, -40],
+    [-30,   5,  10,  15,  15,  10,   5, -30],
+    [-30,   0,  15,  20,  20,  15,   0, -30],
+    [-30,   5,  15,  20,  20,  15,   5, -30],
+    [-30,   0,  10,  15,  15,  10,   0, -30],
+    [-40, -20,   0,   0,   0,   0, -20, -40],
+    [-50, -40, -30, -30, -30, -30, -40, -50],
+];
+
+// Bishops avoid corners and edges and like long diagonals
+#[rustfmt::skip]
+const BISHOP_TABLE: [[i32; 8]; 8] = [
+    [-20, -10, -10, -10, -10, -10, -10, -20],
+    [-10,   5,   0,   0,   0,   0,   5, -10],
+    [-10,  10,  10,  10,  10,  10,  10, -10],
+    [-10,   0,  10,  10,  10,  10,   0, -10],
+    [-10,   5,   5,  10,  10,   5,   5, -10],
+    [-10,   0,   5,  10,  10,   5,   0, -10],
+    [-10,   0,   0,   0,   0,   0,   0, -10],
+    [-20, -10, -10, -10, -10, -10, -10, -20],
+];
+
+// Rooks want the seventh rank and the central files
+#[rustfmt::skip]
+const ROOK_TABLE: [[i32; 8]; 8] = [
+    [  0,   0,   0,   5,   5,   0,   0,   0],
+    [ -5,   0,   0,   0,   0,   0,   0,  -5],
+    [ -5,   0,   0,   0,   0,   0,   0,  -5],
+    [ -5,   0,   0,   0,   0,   0,   0,  -5],
+    [ -5,   0,   0,   0,   0,   0,   0,  -5],
+    [ -5,   0,   0,   0,   0,   0,   0,  -5],
+    [  5,  10,  10,  10,  10,  10,  10,   5],
+    [  0,   0,   0,   0,   0,   0,   0,   0],
+];
+
+// The queen is mildly centralized and kept off the edges
+#[rustfmt::skip]
+const QUEEN_TABLE: [[i32; 8]; 8] = [
+    [-20, -10, -10,  -5,  -5, -10, -10, -20],
+    [-10,   0,   5,   0,   0,   0,   0, -10],
+    [-10,   5,   5,   5,   5,   5,   0, -10],
+    [  0,   0,   5,   5,   5,   5,   0,  -5],
+    [ -5,   0,   5,   5,   5,   5,   0,  -5],
+    [-10,   0,   5,   5,   5,   5,   0, -10],
+    [-10,   0,   0,   0,   0,   0,   0, -10],
+    [-20, -10, -10,  -5,  -5, -10, -10, -20],
+];
+
+// King piece-square tables from White's point of view, indexed [row][col] (row 0 = rank 1)
+// In the middlegame the king belongs castled behind its pawns
+#[rustfmt::skip]
+const KING_MIDDLEGAME: [[i32; 8]; 8] = [
+    [ 20,  30,  10,   0,   0,  10,  30,  20],
+    [ 20,  20,   0,   0,   0,   0,  20,  20],
+    [-10, -20, -20, -20, -20, -20, -20, -10],
+    [-20, -30, -30, -40, -40, -30, -30, -20],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+];
+
+// In the endgame it is a fighting piece and wants the center
+#[rustfmt::skip]
+const KING_ENDGAME: [[i32; 8]; 8] = [
+    [-50, -30, -30, -30, -30, -30, -30, -50],
+    [-30, -30,   0,   0,   0,   0, -30, -30],
+    [-30, -10,  20,  30,  30,  20, -10, -30],
+    [-30, -10,  30,  40,  40,  30, -10, -30],
+    [-30, -10,  30,  40,  40,  30, -10, -30],
+    [-30, -10,  20,  30,  30,  20, -10, -30],
+    [-30, -20, -10,   0,   0, -10, -20, -30],
+    [-50, -40, -30, -20, -20, -30, -40, -50],
+];
+
+/// Material value of a piece in centipawns
+pub fn piece_value(piece_type: PieceType) -> i32 {
+    match piece_type {
+        PieceType::Pawn => 100,
+        PieceType::Knight => 320,
+        PieceType::Bishop => 330,
+        PieceType::Rook => 500,
+        PieceType::Queen => 900,
+        PieceType::King => 0,
+    }
+}
+
+/// Static evaluation in centipawns from White's perspective, whoever is to move
+/// Material, piece-square tables, king placement, mobility and a penalty for being in check
+/// Positive means White is ahead; negamax search negates it when Black is to move
+pub fn evaluate(board: &Board) -> i32 {
+    let mut score = 0;
+
+    for row in 0..8 {
+        for col in 0..8 {
+            if let Some(piece) = board.get_piece(Position::new(row, col)) {
+                let value = piece_value(piece.piece_type)
+                    + piece_square(piece.piece_type, piece.color, Position::new(row, col));
+                if piece.color == Color::White {
+                    score += value;
+                } else {
+                    score -= value;
+                }
+            }
+        }
+    }
+
+    let phase = game_phase(board);
+    score +=
+        king_placement(board, Color::White, phase) - king_placement(board, Color::Black, phase);
+    score += mop_up_bonus(board, Color::White) - mop_up_bonus(board, Color::Black);
+    score += MOBILITY_WEIGHT * (mobility(board, Color::White) - mobility(board, Color::Black));
+
+    if is_in_check(board, Color::White) {
+        score -= CHECK_PENALTY;
+    }
+    if is_in_check(board, Color::Black) {
+        score += CHECK_PENALTY;
+    }
+
+    score
+}
+
+/// Table bonus for a non-king piece; Black reads the tables mirrored top to bottom
+fn piece_square(piece_type: PieceType, color: Color, pos: Position) -> i32 {
+    let table = match piece_type {
+        PieceType::Pawn => &PAWN_TABLE,
+        PieceType::Knight => &KNIGHT_TABLE,
+        PieceType::Bishop => &BISHOP_TABLE,
+        PieceType::Rook => &ROOK_TABLE,
+        PieceType::Queen => &QUEEN_TABLE,
+        PieceType::King => return 0, // Phase-dependent, see king_placement
+    };
+
+    let row = match color {
+        Color::White => pos.row,
+        Color::Black => 7 - pos.row,
+    } as usize;
+    table[row][pos.col as usize]
+}
+
+/// Number of legal moves `color` would have if it were their turn
+fn mobility(board: &Board, color: Color) -> i32 {
+    if board.current_turn() == color {
+        return generate_all_legal_moves(board).len() as i32;
+    }
+
+    let mut flipped = board.clone();
+    flipped.set_current_turn(color);
+    generate_all_legal_moves(&flipped).len() as i32
+}
+
+/// How much middlegame is left, from OPENING_PHASE down to 0 as pieces come off
+fn game_phase(board: &Board) -> i32 {
+    let phase: i32 = [Color::White, Color::Black]
+        .into_iter()
+        .map(|color| {
+            board.minor_piece_count(color) as i32 * MINOR_PHASE
+                + board.major_piece_count(color) as i32 * MAJOR_PHASE
+        })
+        .sum();
+    phase.min(OPENING_PHASE)
+}
+
+/// Square bonus for `color`'s king, blended between the middlegame and endgame tables by phase
+fn king_placement(board: &Board, color: Color, phase: i32) -> i32 {
+    let Some(king) = board.find_king(color) else {
+        return 0;
+    };
+
+    let row = match color {
+        Color::White => king.row,
+        Color::Black => 7 - king.row,
+    } as usize;
+    let col = king.col as usize;
+
+    (KING_MIDDLEGAME[row][col] * phase + KING_ENDGAME[row][col] * (OPENING_PHASE - phase))
+        / OPENING_PHASE
+}
+
+/// Endgame bonus for `color` when the opponent has only a king left: rewards pushing
+/// that king to the edge and bringing our own king closer, so won endings get converted
+fn mop_up_bonus(board: &Board, color: Color) -> i32 {
+    let defender = color.opposite();
+    if board.get_pieces(defender).len() != 1 {
+        return 0;
+    }
+
+    let material: i32 = board
+        .get_pieces(color)
+        .iter()
+        .map(|(_, piece)| piece_value(piece.piece_type))
+        .sum();
+    if material < piece_value(PieceType::Rook) {
+        return 0;
+    }
+
+    let (Some(our_king), Some(their_king)) = (board.find_king(color), board.find_king(defender))
+    else {
+        return 0;
+    };
+
+    let center_distance =
+        (3 - their_king.row).max(their_king.row - 4) + (3 - their_king.col).max(their_king.col - 4);
+    let king_distance =
+        (our_king.row - their_king.row).abs() + (our_king.col - their_king.col).abs();
+
+    10 * center_distance as i32 + 4 * (14 - king_distance as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::board::BoardBuilder;
+    use crate::game::piece::Move;
+
+    #[test]
+    fn test_initial_position_is_even() {
+        assert_eq!(evaluate(&Board::new()), 0);
+    }
+
+    #[test]
+    fn test_evaluation_is_white_relative() {
+        let mut board = Board::new();
+        board.set_piece(Position::new(7, 3), None); // Remove black queen
+
+        let white_view = evaluate(&board);
+        assert!(white_view > 800);
+        board.set_current_turn(Color::Black);
+        assert_eq!(evaluate(&board), white_view);
+    }
+
+    #[test]
+    fn test_evaluate_is_positive_when_white_is_up_a_rook() {
+        assert_eq!(evaluate(&Board::new()), 0);
+
+        let mut board = Board::new();
+        board.set_piece(Position::new(7, 0), None); // Remove the a8 rook
+        assert!(evaluate(&board) > 400);
+
+        let mut board = Board::new();
+        board.set_piece(Position::new(0, 7), None); // Remove the h1 rook
+        assert!(evaluate(&board) < -400);
+    }
+
+    #[test]
+    fn test_piece_square_tables_reward_development() {
+        let mut developed = Board::new();
+        developed.make_move(Move::new(Position::new(0, 6), Position::new(2, 5))); // Nf3
+
+        assert!(evaluate(&developed) > evaluate(&Board::new()));
+        // Mirrored tables: Black's ...Nf6 is worth the same to Black
+        assert_eq!(
+            piece_square(PieceType::Knight, Color::Black, Position::new(5, 5)),
+            piece_square(PieceType::Knight, Color::White, Position::new(2, 5))
+        );
+    }
+
+    #[test]
+    fn test_king_placement_depends_on_phase() {
+        // All minor and major pieces still on: the castled king is safer than a central one
+        let middlegame = |king: &str| {
+            BoardBuilder::empty()
+                .piece("a1", PieceType::Rook, Color::White)
+                .piece("b1", PieceType::Knight, Color::White)
+                .piece("c1", PieceType::Bishop, Color::White)
+                .piece("d1", PieceType::Queen, Color::White)
+                .piece("f1", PieceType::Bishop, Color::White)
+                .piece("d2", PieceType::Knight, Color::White)
+                .piece("h1", PieceType::Rook, Color::White)
+                .piece(king, PieceType::King, Color::White)
+                .piece("a8", PieceType::Rook, Color::Black)
+                .piece("b8", PieceType::Knight, Color::Black)
+                .piece("c8", PieceType::Bishop, Color::Black)
+                .piece("d8", PieceType::Queen, Color::Black)
+                .piece("e8", PieceType::King, Color::Black)
+                .piece("f8", PieceType::Bishop, Color::Black)
+                .piece("g8", PieceType::Knight, Color::Black)
+                .piece("h8", PieceType::Rook, Color::Black)
+                .build()
+                .unwrap()
+        };
+        assert!(evaluate(&middlegame("g1")) > evaluate(&middlegame("e3")));
+
+        // Only kings and pawns left: the same squares rank the other way round
+        let endgame = |king: &str| {
+            BoardBuilder::empty()
+                .piece("a2", PieceType::Pawn, Color::White)
+                .piece("b2", PieceType::Pawn, Color::White)
+                .piece(king, PieceType::King, Color::White)
+                .piece("a7", PieceType::Pawn, Color::Black)
+                .piece("b7", PieceType::Pawn, Color::Black)
+                .piece("h8", PieceType::King, Color::Black)
+                .build()
+                .unwrap()
+        };
+        assert!(evaluate(&endgame("e3")) > evaluate(&endgame("g1")));
+    }
+
+    #[test]
+    fn test_mop_up_prefers_cornered_lone_king() {
+        let cornered = BoardBuilder::empty()
+            .piece("a8", PieceType::King, Color::Black)
+            .piece("c6", PieceType::King, Color::White)
+            .piece("h2", PieceType::Queen, Color::White)
+            .build()
+            .unwrap();
+        let centered = BoardBuilder::empty()
+            .piece("d5", PieceType::King, Color::Black)
+            .piece("c3", PieceType::King, Color::White)
+            .piece("h2", PieceType::Queen, Color::White)
+            .build()
+            .unwrap();
+
+        assert!(evaluate(&cornered) > evaluate(&centered));
+    }
+}
@@ -1 +1,229 @@
 // AI search algorithms
+use super::evaluation::{evaluate, piece_value};
+use crate::game::board::Board;
+use crate::game::piece::{Color, Move};
+use crate::game::rules::{generate_pseudo_legal_all, has_insufficient_material, is_in_check};
+use std::time::{Duration, Instant};
+
+/// Score of a checkmate; mates found closer to the root score higher
+pub const MATE_SCORE: i32 = 100_000;
+
+const INFINITY: i32 = MATE_SCORE + 1;
+
+/// Depth cap for iterative deepening, reached only when the tree is tiny (e.g. forced lines)
+const MAX_TIMED_DEPTH: u32 = 64;
+
+/// Find the best move for the side to move with a fixed-depth alpha-beta search
+/// Returns None when there are no legal moves
+pub fn find_best_move(board: &Board, depth: u32) -> Option<Move> {
+    let children = ordered_children(board);
+    Search::unbounded().root(&children, depth.max(1))
+}
+
+/// Iterative deepening from depth 1 until `max_millis` runs out, returning the best move
+/// of the deepest completed iteration; an iteration cut short by the deadline is discarded
+/// Returns None only when there are no legal moves
+pub fn find_best_move_timed(board: &Board, max_millis: u64) -> Option<Move> {
+    let mut children = ordered_children(board);
+    let mut best_move = children.first()?.0;
+    let mut search = Search::until(Instant::now() + Duration::from_millis(max_millis));
+
+    for depth in 1..=MAX_TIMED_DEPTH {
+        let Some(mv) = search.root(&children, depth) else {
+            break;
+        };
+        best_move = mv;
+
+        // Search the previous best first so the next iteration prunes more
+        if let Some(index) = children.iter().position(|(child_mv, _)| *child_mv == mv) {
+            let best = children.remove(index);
+            children.insert(0, best);
+        }
+        if search.out_of_time() {
+            break;
+        }
+    }
+
+    Some(best_move)
+}
+
+/// Alpha-beta search state, aborting once an optional deadline passes
+struct Search {
+    deadline: Option<Instant>,
+    aborted: bool,
+}
+
+impl Search {
+    fn unbounded() -> Self {
+        Self {
+            deadline: None,
+            aborted: false,
+        }
+    }
+
+    fn until(deadline: Instant) -> Self {
+        Self {
+            deadline: Some(deadline),
+            aborted: false,
+        }
+    }
+
+    fn out_of_time(&mut self) -> bool {
+        if let Some(deadline) = self.deadline {
+            self.aborted |= Instant::now() >= deadline;
+        }
+        self.aborted
+    }
+
+    /// Best root move at `depth`, None if there are no moves or the deadline cut the search short
+    fn root(&mut self, children: &[(Move, Board)], depth: u32) -> Option<Move> {
+        let mut best_move = None;
+        let mut alpha = -INFINITY;
+
+        for (mv, child) in children {
+            let score = -self.negamax(child, depth - 1, 1, -INFINITY, -alpha);
+            if self.aborted {
+                return None;
+            }
+            if best_move.is_none() || score > alpha {
+                alpha = score;
+                best_move = Some(*mv);
+            }
+        }
+
+        best_move
+    }
+
+    fn negamax(&mut self, board: &Board, depth: u32, ply: i32, mut alpha: i32, beta: i32) -> i32 {
+        if self.out_of_time() {
+            return 0;
+        }
+
+        let children = ordered_children(board);
+        if children.is_empty() {
+            return if is_in_check(board, board.current_turn()) {
+                -MATE_SCORE + ply
+            } else {
+                0
+            };
+        }
+
+        // Treat any repetition as a draw so the side that is ahead keeps making progress
+        if has_insufficient_material(board) || board.repetition_count() >= 2 {
+            return 0;
+        }
+
+        if depth == 0 {
+            // Negamax scores from the side to move, the evaluation from White
+            return match board.current_turn() {
+                Color::White => evaluate(board),
+                Color::Black => -evaluate(board),
+            };
+        }
+
+        for (_, child) in children {
+            let score = -self.negamax(&child, depth - 1, ply + 1, -beta, -alpha);
+            if score >= beta {
+                return beta;
+            }
+            alpha = alpha.max(score);
+        }
+
+        alpha
+    }
+}
+
+/// Legal moves paired with the resulting boards, captures and promotions first
+fn ordered_children(board: &Board) -> Vec<(Move, Board)> {
+    let mover = board.current_turn();
+    let mut children: Vec<(Move, Board)> = generate_pseudo_legal_all(board)
+        .into_iter()
+        .map(|mv| (mv, board.make_move_copy(mv)))
+        .filter(|(_, child)| !is_in_check(child, mover))
+        .collect();
+
+    children.sort_by_key(|(mv, _)| {
+        let victim = board
+            .get_piece(mv.to)
+            .map_or(0, |p| piece_value(p.piece_type));
+        let promotion = mv.promotion.map_or(0, piece_value);
+        -(victim + promotion)
+    });
+
+    children
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::board::BoardBuilder;
+    use crate::game::piece::{Color, PieceType, Position};
+
+    #[test]
+    fn test_finds_mate_in_one() {
+        // Back-rank mate: Ra1-a8#
+        let board = BoardBuilder::empty()
+            .piece("g8", PieceType::King, Color::Black)
+            .piece("f7", PieceType::Pawn, Color::Black)
+            .piece("g7", PieceType::Pawn, Color::Black)
+            .piece("h7", PieceType::Pawn, Color::Black)
+            .piece("g1", PieceType::King, Color::White)
+            .piece("a1", PieceType::Rook, Color::White)
+            .build()
+            .unwrap();
+
+        let mv = find_best_move(&board, 2).unwrap();
+        assert_eq!(mv.from, Position::from_algebraic("a1").unwrap());
+        assert_eq!(mv.to, Position::from_algebraic("a8").unwrap());
+    }
+
+    #[test]
+    fn test_no_move_when_checkmated() {
+        let board = BoardBuilder::empty()
+            .piece("h8", PieceType::King, Color::Black)
+            .piece("g7", PieceType::Queen, Color::White)
+            .piece("f6", PieceType::King, Color::White)
+            .turn(Color::Black)
+            .build()
+            .unwrap();
+
+        assert!(find_best_move(&board, 3).is_none());
+    }
+
+    #[test]
+    fn test_prefers_capturing_hanging_queen() {
+        let board = Board::from_fen("4k3/8/8/3q4/8/4N3/8/4K3 w - - 0 1").unwrap();
+
+        let mv = find_best_move(&board, 2).unwrap();
+        assert_eq!(mv, Move::new(Position::new(2, 4), Position::new(4, 3)));
+        // Same board, same answer
+        assert_eq!(find_best_move(&board, 2), Some(mv));
+    }
+
+    #[test]
+    fn test_no_move_when_stalemated() {
+        let board = Board::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert!(find_best_move(&board, 3).is_none());
+    }
+
+    #[test]
+    fn test_timed_search_finds_mate_within_budget() {
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+
+        let start = Instant::now();
+        let mv = find_best_move_timed(&board, 200).unwrap();
+        assert_eq!(mv, Move::new(Position::new(0, 0), Position::new(7, 0)));
+        // Only the final, abandoned iteration may run past the budget, and it stops promptly
+        assert!(start.elapsed() < Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_timed_search_always_returns_a_legal_move() {
+        let board = Board::new();
+        let mv = find_best_move_timed(&board, 0).unwrap();
+        assert!(crate::game::rules::generate_all_legal_moves(&board).contains(&mv));
+
+        let mated = Board::from_fen("7k/6Q1/5K2/8/8/8/8/8 b - - 0 1").unwrap();
+        assert!(find_best_move_timed(&mated, 50).is_none());
+    }
+}
@@ -0,0 +1,97 @@
+// Self-play harness: pits two move pickers against each other from any starting position
+use crate::game::board::{Board, GameStatus};
+use crate::game::piece::{Color, Move};
+use crate::game::rules::{generate_legal_moves, get_game_status};
+
+/// Outcome of a simulated game
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    /// Checkmate, or the other side failed to produce a legal move
+    Win(Color),
+    /// Stalemate, insufficient material or repetition
+    Draw(GameStatus),
+    /// Neither side finished the game within the move limit
+    MoveLimitReached,
+}
+
+/// Play a game between two AIs starting from `start`
+/// `move_limit` counts half-moves; each AI maps a board to the move it wants to play
+pub fn simulate_game<W, B>(
+    start: Board,
+    mut white_ai: W,
+    mut black_ai: B,
+    move_limit: usize,
+) -> GameResult
+where
+    W: FnMut(&Board) -> Option<Move>,
+    B: FnMut(&Board) -> Option<Move>,
+{
+    let mut board = start;
+
+    for _ in 0..move_limit {
+        if let Some(result) = game_result(&board) {
+            return result;
+        }
+
+        let mover = board.current_turn();
+        let mv = match mover {
+            Color::White => white_ai(&board),
+            Color::Black => black_ai(&board),
+        };
+
+        match mv {
+            // Only moves the rules generate count; anything else forfeits
+            Some(mv) if generate_legal_moves(&board, mv.from).contains(&mv) => {
+                board.make_move(mv);
+            }
+            _ => return GameResult::Win(mover.opposite()),
+        }
+    }
+
+    game_result(&board).unwrap_or(GameResult::MoveLimitReached)
+}
+
+fn game_result(board: &Board) -> Option<GameResult> {
+    match get_game_status(board) {
+        GameStatus::Checkmate(winner) => Some(GameResult::Win(winner)),
+        GameStatus::Ongoing | GameStatus::Check => None,
+        status => Some(GameResult::Draw(status)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::search::find_best_move;
+    use crate::game::board::BoardBuilder;
+    use crate::game::piece::{PieceType, Position};
+
+    #[test]
+    fn test_search_converts_king_and_queen_vs_king() {
+        let start = BoardBuilder::empty()
+            .piece("e1", PieceType::King, Color::White)
+            .piece("d1", PieceType::Queen, Color::White)
+            .piece("e5", PieceType::King, Color::Black)
+            .build()
+            .unwrap();
+
+        let search = |board: &Board| find_best_move(board, 3);
+        let result = simulate_game(start, search, search, 100);
+
+        assert_eq!(result, GameResult::Win(Color::White));
+    }
+
+    #[test]
+    fn test_forfeit_when_ai_has_no_move() {
+        let result = simulate_game(Board::new(), |_: &Board| None, |_: &Board| None, 10);
+        assert_eq!(result, GameResult::Win(Color::Black));
+    }
+
+    #[test]
+    fn test_forfeit_when_ai_plays_an_illegal_move() {
+        // A rook sliding through its own pawn leaves the king safe but isn't a rook move
+        let rook_jump = |_: &Board| Some(Move::new(Position::new(0, 0), Position::new(4, 0)));
+        let result = simulate_game(Board::new(), rook_jump, |_: &Board| None, 10);
+        assert_eq!(result, GameResult::Win(Color::Black));
+    }
+}
@@ -67,44 +67,74 @@ fn calculate_move_weight(board: &Board, mv: &Move) -> f64 {
     weight
 }
 
-/// Select a move using weighted random selection
-pub fn select_weighted_move(board: &Board) -> Option<Move> {
-    let legal_moves = generate_all_legal_moves(board);
-
-    if legal_moves.is_empty() {
-        return None;
-    }
+/// Check if the opponent could answer the move with checkmate
+fn allows_mate_in_one(board: &Board, mv: &Move) -> bool {
+    let mover = board.current_turn();
+    let after = board.make_move_copy(*mv);
+
+    generate_all_legal_moves(&after).into_iter().any(|reply| {
+        let reply_board = after.make_move_copy(reply);
+        is_in_check(&reply_board, mover) && generate_all_legal_moves(&reply_board).is_empty()
+    })
+}
 
-    // Calculate weights for all moves
-    let weights: Vec<f64> = legal_moves
-        .iter()
-        .map(|mv| calculate_move_weight(board, mv))
-        .collect();
+/// Pick an index with probability proportional to its weight
+fn pick_weighted(weights: &[f64]) -> usize {
+    let mut rng = rand::thread_rng();
 
     // Calculate total weight
     let total_weight: f64 = weights.iter().sum();
 
     if total_weight <= 0.0 {
         // If all weights are 0, just pick randomly
-        let mut rng = rand::thread_rng();
-        let index = rng.gen_range(0..legal_moves.len());
-        return Some(legal_moves[index]);
+        return rng.gen_range(0..weights.len());
     }
 
     // Select a random value between 0 and total_weight
-    let mut rng = rand::thread_rng();
     let mut random_value = rng.gen_range(0.0..total_weight);
 
     // Find the move corresponding to this random value
     for (i, weight) in weights.iter().enumerate() {
         random_value -= weight;
         if random_value <= 0.0 {
-            return Some(legal_moves[i]);
+            return i;
         }
     }
 
     // Fallback: return last move (should never reach here)
-    legal_moves.last().copied()
+    weights.len() - 1
+}
+
+/// Select a move using weighted random selection
+/// Candidates that allow a mate-in-one reply are redrawn while a safe move remains
+pub fn select_weighted_move(board: &Board) -> Option<Move> {
+    let mut candidates = generate_all_legal_moves(board);
+
+    if candidates.is_empty() {
+        return None;
+    }
+
+    // Calculate weights for all moves
+    let mut weights: Vec<f64> = candidates
+        .iter()
+        .map(|mv| calculate_move_weight(board, mv))
+        .collect();
+
+    let first_choice = candidates[pick_weighted(&weights)];
+    let mut choice = first_choice;
+    while allows_mate_in_one(board, &choice) {
+        let index = candidates.iter().position(|mv| *mv == choice).unwrap();
+        candidates.remove(index);
+        weights.remove(index);
+
+        // Every move loses to mate, so play the one we wanted anyway
+        if candidates.is_empty() {
+            return Some(first_choice);
+        }
+        choice = candidates[pick_weighted(&weights)];
+    }
+
+    Some(choice)
 }
 
 #[cfg(test)]
@@ -136,4 +166,36 @@ mod tests {
             assert!(weight > 0.0, "All moves should have positive weight");
         }
     }
+
+    #[test]
+    fn test_avoids_move_allowing_mate_in_one() {
+        use crate::game::board::BoardBuilder;
+        use crate::game::piece::{Color, Position};
+
+        // The knight on c3 guards b1; taking the queen lets Black mate with Rb1
+        let board = BoardBuilder::empty()
+            .piece("g1", PieceType::King, Color::White)
+            .piece("f2", PieceType::Pawn, Color::White)
+            .piece("g2", PieceType::Pawn, Color::White)
+            .piece("h2", PieceType::Pawn, Color::White)
+            .piece("c3", PieceType::Knight, Color::White)
+            .piece("h8", PieceType::King, Color::Black)
+            .piece("g7", PieceType::Pawn, Color::Black)
+            .piece("h7", PieceType::Pawn, Color::Black)
+            .piece("b8", PieceType::Rook, Color::Black)
+            .piece("a4", PieceType::Queen, Color::Black)
+            .build()
+            .unwrap();
+
+        let knight_takes_queen = Move::new(
+            Position::from_algebraic("c3").unwrap(),
+            Position::from_algebraic("a4").unwrap(),
+        );
+        assert!(allows_mate_in_one(&board, &knight_takes_queen));
+
+        for _ in 0..20 {
+            let mv = select_weighted_move(&board).unwrap();
+            assert!(!allows_mate_in_one(&board, &mv), "{:?} allows mate", mv);
+        }
+    }
 }
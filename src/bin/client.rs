@@ -79,8 +79,7 @@ fn print_board_compact(board_state: &Vec<chessmate::networking::types::PieceStat
 
     // Fill board from ID-based representation
     for piece_state in board_state {
-        // Color is inferred from ID: 0-15 = White, 16-31 = Black
-        let is_white = piece_state.id < 16;
+        let is_white = piece_state.color == chessmate::game::piece::Color::White;
 
         if let Some(pos) = Position::from_algebraic(&piece_state.position) {
             if pos.is_valid() {
@@ -1,23 +1,27 @@
 // ChessMate multiplayer server - combines REST API and WebSocket game server
 use axum::{
-    extract::{State, WebSocketUpgrade},
+    extract::{Path, Query, State, WebSocketUpgrade},
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
     response::Response,
     routing::get,
     Router,
 };
-use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
 use serde_json::json;
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
-use std::time::Duration;
-use tokio::sync::mpsc;
-use tokio::time::sleep;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::time::{interval, sleep};
 use tower_http::cors::{Any, CorsLayer};
-use tracing_subscriber;
+use tracing::Instrument;
+use tracing_subscriber::EnvFilter;
 
-use chessmate::networking::matchmaking::WaitingPlayer;
-use chessmate::networking::protocol::{ClientMessage, ServerMessage};
+use chessmate::networking::auth::{TokenVerifier, AUTH_SECRET_ENV};
+use chessmate::networking::connection::handle_websocket;
 use chessmate::networking::server::GameServer;
+use chessmate::networking::types::GameResult;
+use chessmate::rating::{self, DEFAULT_K_FACTOR, DEFAULT_RATING};
 
 // Application state
 #[derive(Clone)]
@@ -43,100 +47,61 @@ async fn stats(State(state): State<AppState>) -> axum::Json<serde_json::Value> {
     }))
 }
 
-// WebSocket handler
-async fn websocket_handler(ws: WebSocketUpgrade, State(state): State<AppState>) -> Response {
-    ws.on_upgrade(|socket| handle_websocket(socket, state.game_server))
+/// Reject requests without a valid `Authorization: Bearer <token>` when the server requires one
+fn require_token(server: &GameServer, headers: &HeaderMap) -> Result<(), StatusCode> {
+    if !server.requires_auth() {
+        return Ok(());
+    }
+    let token = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    server
+        .verify_token(token)
+        .map(|_| ())
+        .map_err(|_| StatusCode::UNAUTHORIZED)
 }
 
-async fn handle_websocket(socket: axum::extract::ws::WebSocket, server: GameServer) {
-    let (mut ws_tx, mut ws_rx) = socket.split();
+async fn player_game(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(player_id): Path<String>,
+) -> Result<axum::Json<serde_json::Value>, StatusCode> {
+    require_token(&state.game_server, &headers)?;
+    match state.game_server.find_player_game(&player_id).await {
+        Some(game_id) => Ok(axum::Json(json!({
+            "player_id": player_id,
+            "game_id": game_id
+        }))),
+        None => Err(StatusCode::NOT_FOUND),
+    }
+}
 
-    // Create a channel for sending messages to this client
-    let (tx, mut rx) = mpsc::unbounded_channel::<ServerMessage>();
+#[derive(Deserialize)]
+struct GamesQuery {
+    player: Option<String>,
+}
 
-    // Spawn a task to forward messages from the channel to the WebSocket
-    tokio::spawn(async move {
-        while let Some(msg) = rx.recv().await {
-            if let Ok(json) = serde_json::to_string(&msg) {
-                if ws_tx
-                    .send(axum::extract::ws::Message::Text(json))
-                    .await
-                    .is_err()
-                {
-                    break;
-                }
-            }
-        }
-    });
+async fn list_games(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<GamesQuery>,
+) -> Result<axum::Json<serde_json::Value>, StatusCode> {
+    require_token(&state.game_server, &headers)?;
+    let games = match query.player {
+        Some(player_id) => state.game_server.games_with_player(&player_id).await,
+        None => state.game_server.list_games().await,
+    };
 
-    // Variable to store player ID once they join matchmaking
-    let mut player_id: Option<String> = None;
-
-    // Process incoming messages from the WebSocket
-    while let Some(result) = ws_rx.next().await {
-        match result {
-            Ok(msg) => {
-                if let axum::extract::ws::Message::Text(text) = msg {
-                    // Try to deserialize the message
-                    match serde_json::from_str::<ClientMessage>(&text) {
-                        Ok(client_msg) => {
-                            // Extract player_id from the message if we don't have it yet
-                            if player_id.is_none() {
-                                if let ClientMessage::JoinMatchmaking { player_id: ref pid } =
-                                    client_msg
-                                {
-                                    player_id = Some(pid.clone());
-
-                                    // Add player to matchmaking queue
-                                    let player = WaitingPlayer::new(pid.clone(), tx.clone());
-                                    if let Err(e) = server.add_to_matchmaking(player).await {
-                                        tracing::error!(
-                                            "Failed to add player to matchmaking: {}",
-                                            e
-                                        );
-                                        let _ = tx.send(ServerMessage::error(e));
-                                        continue;
-                                    }
-
-                                    // Send acknowledgment
-                                    let _ = tx.send(ServerMessage::matchmaking_joined());
-                                    tracing::info!("Player {} joined matchmaking", pid);
-                                }
-                            }
-
-                            // Handle the message
-                            if let Some(ref pid) = player_id {
-                                if let Err(e) = server.handle_message(pid, client_msg).await {
-                                    tracing::error!("Error handling message from {}: {}", pid, e);
-                                    let _ = tx.send(ServerMessage::error(e));
-                                }
-                            } else {
-                                let _ = tx.send(ServerMessage::error(
-                                    "Must join matchmaking first".to_string(),
-                                ));
-                            }
-                        }
-                        Err(e) => {
-                            tracing::error!("Failed to deserialize message: {}", e);
-                            let _ = tx.send(ServerMessage::error(format!(
-                                "Invalid message format: {}",
-                                e
-                            )));
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                tracing::error!("WebSocket error: {}", e);
-                break;
-            }
-        }
-    }
+    Ok(axum::Json(json!({ "games": games })))
+}
 
-    // Client disconnected
-    if let Some(pid) = player_id {
-        tracing::info!("Player {} disconnected", pid);
-    }
+// WebSocket handler
+async fn websocket_handler(ws: WebSocketUpgrade, State(state): State<AppState>) -> Response {
+    // The player ID is filled in once the connection joins matchmaking
+    let span = tracing::info_span!("connection", player_id = tracing::field::Empty);
+    ws.on_upgrade(|socket| handle_websocket(socket, state.game_server).instrument(span))
 }
 
 // Database initialization
@@ -160,6 +125,13 @@ async fn matchmaking_loop(server: GameServer) {
         // Try to create matches every 500ms
         sleep(Duration::from_millis(500)).await;
 
+        for player_id in server.prune_stale_players().await {
+            tracing::info!("Pruned stale player {} from matchmaking", player_id);
+        }
+        for code in server.prune_expired_rooms().await {
+            tracing::info!("Closed expired room {}", code);
+        }
+
         let matches = server.try_matchmaking().await;
 
         for m in matches {
@@ -175,10 +147,167 @@ async fn matchmaking_loop(server: GameServer) {
     }
 }
 
+// Background sweep ending games that exceeded their move or duration limit,
+// or whose player disconnected and didn't come back
+async fn game_limit_loop(server: GameServer) {
+    loop {
+        sleep(Duration::from_secs(5)).await;
+
+        for game_id in server.sweep_game_limits().await {
+            tracing::info!("Game {} ended as a draw (limit reached)", game_id);
+        }
+        for game_id in server.sweep_disconnected().await {
+            tracing::info!("Game {} forfeited by a disconnected player", game_id);
+        }
+    }
+}
+
+// Background clock: ticks every timed game once a second and flags players out of time
+async fn clock_loop(server: GameServer) {
+    let mut ticker = interval(Duration::from_secs(1));
+    loop {
+        ticker.tick().await;
+
+        for game_id in server.sweep_clocks().await {
+            tracing::info!("Game {} ended on time", game_id);
+        }
+    }
+}
+
+// Attempts at recording a rated result before it's dropped
+const MAX_RATING_ATTEMPTS: u32 = 5;
+
+// Wait before retrying a result after its first failure, doubled after each later one
+const RATING_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+// Background ratings task: applies the Elo updates of finished rated games to the database
+async fn ratings_loop(server: GameServer, db: PgPool) {
+    let mut ticker = interval(Duration::from_secs(1));
+    // game_id -> (failed attempts, when to try again)
+    let mut retries: HashMap<String, (u32, Instant)> = HashMap::new();
+    loop {
+        ticker.tick().await;
+        let now = Instant::now();
+
+        // Results that fail to record go back in the queue and are retried with backoff, until
+        // MAX_RATING_ATTEMPTS failures get them logged and dropped
+        let mut pending = Vec::new();
+        for result in server.take_rated_results() {
+            let failures = retries.get(&result.game_id).copied();
+            if failures.is_some_and(|(_, retry_at)| retry_at > now) {
+                pending.push(result);
+                continue;
+            }
+
+            match record_rated_result(&db, &result).await {
+                // Matchmaking rates players by what the database now holds
+                Ok(new_ratings) => {
+                    retries.remove(&result.game_id);
+                    for (player_id, rating) in new_ratings {
+                        server
+                            .set_player_rating(player_id, rating.round() as u32)
+                            .await;
+                    }
+                }
+                Err(e) => {
+                    let attempts = failures.map_or(0, |(attempts, _)| attempts) + 1;
+                    if attempts >= MAX_RATING_ATTEMPTS {
+                        tracing::error!(
+                            "Dropping ratings update for game {} after {} attempts: {} ({:?})",
+                            result.game_id,
+                            attempts,
+                            e,
+                            result
+                        );
+                        retries.remove(&result.game_id);
+                        continue;
+                    }
+
+                    let delay = RATING_RETRY_DELAY * 2u32.pow(attempts - 1);
+                    tracing::warn!(
+                        "Failed to update ratings for game {}, retrying in {:?}: {}",
+                        result.game_id,
+                        delay,
+                        e
+                    );
+                    retries.insert(result.game_id.clone(), (attempts, now + delay));
+                    pending.push(result);
+                }
+            }
+        }
+        if !pending.is_empty() {
+            server.requeue_rated_results(pending);
+        }
+    }
+}
+
+// Load every stored rating into the game server, which matchmakes by them
+async fn load_ratings(server: &GameServer, db: &PgPool) -> Result<(), sqlx::Error> {
+    let rows: Vec<(String, f64)> = sqlx::query_as("SELECT player_id, rating FROM ratings")
+        .fetch_all(db)
+        .await?;
+    for (player_id, rating) in rows {
+        server
+            .set_player_rating(&player_id, rating.round() as u32)
+            .await;
+    }
+    Ok(())
+}
+
+// Update both players' stored ratings from a rated game's result, atomically
+// Returns each player's new rating
+async fn record_rated_result<'a>(
+    db: &PgPool,
+    result: &'a GameResult,
+) -> Result<[(&'a str, f64); 2], sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let mut ratings = Vec::new();
+    for player_id in [&result.white_player_id, &result.black_player_id] {
+        let rating: Option<f64> =
+            sqlx::query_scalar("SELECT rating FROM ratings WHERE player_id = $1 FOR UPDATE")
+                .bind(player_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        ratings.push(rating.unwrap_or(DEFAULT_RATING));
+    }
+
+    let (white, black) = rating::rate_game(ratings[0], ratings[1], result.winner, DEFAULT_K_FACTOR);
+    for (player_id, new_rating) in [
+        (&result.white_player_id, white),
+        (&result.black_player_id, black),
+    ] {
+        sqlx::query(
+            "INSERT INTO ratings (player_id, rating, games_played) VALUES ($1, $2, 1) \
+             ON CONFLICT (player_id) DO UPDATE \
+             SET rating = EXCLUDED.rating, games_played = ratings.games_played + 1, \
+             updated_on = NOW()",
+        )
+        .bind(player_id)
+        .bind(new_rating)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok([
+        (result.white_player_id.as_str(), white),
+        (result.black_player_id.as_str(), black),
+    ])
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize tracing
-    tracing_subscriber::fmt::init();
+    // Initialize tracing: RUST_LOG takes precedence, --verbose turns on debug logs
+    let verbose = std::env::args().any(|arg| arg == "--verbose" || arg == "-v");
+    let default_filter = if verbose {
+        "info,chessmate=debug,chessmate_server=debug"
+    } else {
+        "info"
+    };
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter));
+    tracing_subscriber::fmt().with_env_filter(filter).init();
 
     tracing::info!("🚀 Starting ChessMate multiplayer server...");
 
@@ -192,7 +321,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing::info!("✓ Database connected and migrations applied");
 
     // Initialize game server
-    let game_server = GameServer::new();
+    let mut game_server = GameServer::new();
+    match TokenVerifier::from_env() {
+        Some(verifier) => {
+            game_server = game_server.with_token_verifier(verifier);
+            tracing::info!("✓ Player token authentication enabled");
+        }
+        None => tracing::warn!(
+            "{} not set, player IDs are self-declared (testing only)",
+            AUTH_SECRET_ENV
+        ),
+    }
+    load_ratings(&game_server, &db_pool).await?;
     tracing::info!("✓ Game server initialized");
 
     // Start matchmaking background task
@@ -202,6 +342,27 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     });
     tracing::info!("✓ Matchmaking loop started");
 
+    // Start game limit sweep background task
+    let sweep_server = game_server.clone_refs();
+    tokio::spawn(async move {
+        game_limit_loop(sweep_server).await;
+    });
+
+    // Start clock background task
+    let clock_server = game_server.clone_refs();
+    tokio::spawn(async move {
+        clock_loop(clock_server).await;
+    });
+    tracing::info!("✓ Clock loop started");
+
+    // Start ratings background task
+    let ratings_server = game_server.clone_refs();
+    let ratings_db = db_pool.clone();
+    tokio::spawn(async move {
+        ratings_loop(ratings_server, ratings_db).await;
+    });
+    tracing::info!("✓ Ratings loop started");
+
     // Create application state
     let state = AppState {
         db: db_pool,
@@ -218,6 +379,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let app = Router::new()
         .route("/health", get(health_check))
         .route("/stats", get(stats))
+        .route("/games", get(list_games))
+        .route("/players/:id/game", get(player_game))
         .route("/ws", get(websocket_handler))
         .layer(cors)
         .with_state(state);
@@ -225,6 +388,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing::info!("✓ Routes configured:");
     tracing::info!("  - Health:    http://0.0.0.0:3000/health");
     tracing::info!("  - Stats:     http://0.0.0.0:3000/stats");
+    tracing::info!("  - Games:     http://0.0.0.0:3000/games?player=:id");
+    tracing::info!("  - Game:      http://0.0.0.0:3000/players/:id/game");
     tracing::info!("  - WebSocket: ws://0.0.0.0:3000/ws");
 
     // Start server
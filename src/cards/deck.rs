@@ -1 +1,112 @@
 // Deck and hand management
+
+use super::effects::Effect;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// A playable card
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Card {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub effect: Effect,
+}
+
+impl Card {
+    pub fn new(id: &str, name: &str, description: &str, effect: Effect) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: description.to_string(),
+            effect,
+        }
+    }
+}
+
+/// An ordered pile of cards, drawn from the top
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deck {
+    cards: VecDeque<Card>,
+}
+
+impl Deck {
+    /// Create a deck whose first card is on top
+    pub fn new(cards: Vec<Card>) -> Self {
+        Self {
+            cards: cards.into(),
+        }
+    }
+
+    /// Shuffle the deck; the same seed always gives the same order
+    pub fn shuffle(&mut self, seed: u64) {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        self.cards.make_contiguous().shuffle(&mut rng);
+    }
+
+    /// Take the top card, if any are left
+    pub fn draw(&mut self) -> Option<Card> {
+        self.cards.pop_front()
+    }
+
+    /// Number of cards left to draw
+    pub fn remaining(&self) -> usize {
+        self.cards.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::piece::Color;
+
+    fn sample_deck() -> Deck {
+        Deck::new(
+            (0..10)
+                .map(|i| {
+                    Card::new(
+                        &format!("time-{}", i),
+                        "Extra Time",
+                        "Add 10 seconds to your clock",
+                        Effect::GrantTime {
+                            player: Color::White,
+                            seconds: 10,
+                        },
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    fn ids(deck: &mut Deck) -> Vec<String> {
+        std::iter::from_fn(|| deck.draw())
+            .map(|card| card.id)
+            .collect()
+    }
+
+    #[test]
+    fn test_draw_takes_top_card_and_shrinks_deck() {
+        let mut deck = sample_deck();
+        assert_eq!(deck.remaining(), 10);
+
+        assert_eq!(deck.draw().unwrap().id, "time-0");
+        assert_eq!(deck.remaining(), 9);
+
+        assert_eq!(ids(&mut deck).len(), 9);
+        assert_eq!(deck.draw(), None);
+        assert_eq!(deck.remaining(), 0);
+    }
+
+    #[test]
+    fn test_same_seed_gives_same_order() {
+        let mut first = sample_deck();
+        let mut second = sample_deck();
+        first.shuffle(42);
+        second.shuffle(42);
+        assert_eq!(first.remaining(), 10);
+        assert_eq!(ids(&mut first), ids(&mut second));
+    }
+}
@@ -1 +1,174 @@
 // Card drafting mechanics
+
+use super::deck::{Card, Deck};
+use crate::game::piece::Color;
+use std::fmt;
+
+/// Reasons a draft pick can be rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DraftError {
+    /// Every pick has been made
+    DraftComplete,
+    /// The other player is picking
+    NotYourTurn(Color),
+    /// The card was already picked by a player
+    AlreadyTaken(String),
+    /// No card in the pool has this ID
+    UnknownCard(String),
+}
+
+impl fmt::Display for DraftError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DraftError::DraftComplete => write!(f, "The draft is complete"),
+            DraftError::NotYourTurn(color) => write!(f, "It is {:?}'s pick", color),
+            DraftError::AlreadyTaken(id) => write!(f, "Card {} was already picked", id),
+            DraftError::UnknownCard(id) => write!(f, "No card {} in the pool", id),
+        }
+    }
+}
+
+/// Pre-game draft: players take turns picking cards from a shared pool, White first
+#[derive(Debug, Clone)]
+pub struct Draft {
+    pool: Vec<Card>,
+    picks_per_player: usize,
+    turn: Color,
+    white_picks: Vec<Card>,
+    black_picks: Vec<Card>,
+}
+
+impl Draft {
+    pub fn new(pool: Vec<Card>, picks_per_player: usize) -> Self {
+        Self {
+            pool,
+            picks_per_player,
+            turn: Color::White,
+            white_picks: Vec::new(),
+            black_picks: Vec::new(),
+        }
+    }
+
+    /// The player whose pick it is
+    pub fn turn(&self) -> Color {
+        self.turn
+    }
+
+    /// Cards still available to pick
+    pub fn pool(&self) -> &[Card] {
+        &self.pool
+    }
+
+    /// Take a card from the pool for `player`, then pass the pick to the opponent
+    pub fn pick(&mut self, player: Color, card_id: &str) -> Result<(), DraftError> {
+        if self.is_complete() {
+            return Err(DraftError::DraftComplete);
+        }
+        if player != self.turn {
+            return Err(DraftError::NotYourTurn(self.turn));
+        }
+
+        let Some(index) = self.pool.iter().position(|card| card.id == card_id) else {
+            let taken = self
+                .white_picks
+                .iter()
+                .chain(&self.black_picks)
+                .any(|card| card.id == card_id);
+            return Err(if taken {
+                DraftError::AlreadyTaken(card_id.to_string())
+            } else {
+                DraftError::UnknownCard(card_id.to_string())
+            });
+        };
+
+        let card = self.pool.remove(index);
+        match player {
+            Color::White => self.white_picks.push(card),
+            Color::Black => self.black_picks.push(card),
+        }
+        self.turn = self.turn.opposite();
+        Ok(())
+    }
+
+    /// Whether both players have made all their picks (or the pool ran out)
+    pub fn is_complete(&self) -> bool {
+        let all_picked = self.white_picks.len() >= self.picks_per_player
+            && self.black_picks.len() >= self.picks_per_player;
+        all_picked || self.pool.is_empty()
+    }
+
+    /// The (white, black) decks built from each player's picks, in pick order
+    pub fn decks(&self) -> (Deck, Deck) {
+        (
+            Deck::new(self.white_picks.clone()),
+            Deck::new(self.black_picks.clone()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::effects::Effect;
+
+    fn pool(size: usize) -> Vec<Card> {
+        (0..size)
+            .map(|i| {
+                Card::new(
+                    &format!("card-{}", i),
+                    "Second Wind",
+                    "Move twice in a row",
+                    Effect::ExtraMove,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_alternating_draft_builds_both_decks() {
+        let mut draft = Draft::new(pool(8), 3);
+
+        for (i, player) in [Color::White, Color::Black]
+            .repeat(3)
+            .into_iter()
+            .enumerate()
+        {
+            assert!(!draft.is_complete());
+            assert_eq!(draft.turn(), player);
+            assert_eq!(draft.pick(player, &format!("card-{}", i)), Ok(()));
+        }
+
+        assert!(draft.is_complete());
+        assert_eq!(draft.pool().len(), 2);
+        assert_eq!(
+            draft.pick(Color::White, "card-6"),
+            Err(DraftError::DraftComplete)
+        );
+
+        let (mut white, black) = draft.decks();
+        assert_eq!(white.remaining(), 3);
+        assert_eq!(black.remaining(), 3);
+        assert_eq!(white.draw().unwrap().id, "card-0");
+    }
+
+    #[test]
+    fn test_draft_rejects_out_of_turn_and_taken_cards() {
+        let mut draft = Draft::new(pool(4), 2);
+
+        assert_eq!(
+            draft.pick(Color::Black, "card-0"),
+            Err(DraftError::NotYourTurn(Color::White))
+        );
+        assert_eq!(draft.pick(Color::White, "card-0"), Ok(()));
+        assert_eq!(
+            draft.pick(Color::Black, "card-0"),
+            Err(DraftError::AlreadyTaken("card-0".to_string()))
+        );
+        assert_eq!(
+            draft.pick(Color::Black, "card-9"),
+            Err(DraftError::UnknownCard("card-9".to_string()))
+        );
+        assert_eq!(draft.turn(), Color::Black);
+        assert_eq!(draft.pool().len(), 3);
+    }
+}
@@ -1 +1,437 @@
 // Card effects and abilities
+
+use crate::game::board::Board;
+use crate::game::piece::{Color, PieceType, Position};
+use crate::game::rules::is_in_check;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// What a card does when it is played
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Effect {
+    /// The player moves again before the opponent
+    ExtraMove,
+    /// Move a piece between two squares regardless of how it normally moves
+    TeleportPiece { from: Position, to: Position },
+    /// Add seconds to a player's clock
+    GrantTime { player: Color, seconds: i32 },
+}
+
+impl Effect {
+    /// The effect as played by `color`: time grants go to whoever plays the card
+    pub fn played_by(&self, color: Color) -> Effect {
+        match self {
+            Effect::GrantTime { seconds, .. } => Effect::GrantTime {
+                player: color,
+                seconds: *seconds,
+            },
+            effect => effect.clone(),
+        }
+    }
+
+    /// The effect aimed at a square chosen when the card is played
+    /// A teleport's target replaces its destination; other effects take no target
+    pub fn with_target(&self, target: Option<Position>) -> Result<Effect, EffectError> {
+        match (self, target) {
+            (effect, None) => Ok(effect.clone()),
+            (Effect::TeleportPiece { from, .. }, Some(to)) => {
+                Ok(Effect::TeleportPiece { from: *from, to })
+            }
+            (_, Some(_)) => Err(EffectError::UnexpectedTarget),
+        }
+    }
+}
+
+/// Reasons a card effect can't be applied; the board is left unchanged
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EffectError {
+    /// Cards can't be played once the game is over
+    GameOver,
+    /// A target was given for an effect that doesn't take one
+    UnexpectedTarget,
+    /// The side to move already has an extra move pending
+    ExtraMoveAlreadyPending,
+    /// Square is off the board
+    InvalidSquare(Position),
+    /// There is no piece to teleport
+    NoPieceAtSource(Position),
+    /// The piece belongs to the side not to move
+    NotYourPiece(Position),
+    /// A piece can't teleport onto its own square
+    SameSquare(Position),
+    /// The destination holds a piece of the same color (own king included)
+    OccupiedByOwnPiece(Position),
+    /// Pawns can't be placed on the first or last rank
+    PawnOnBackRank(Position),
+    /// The effect would leave the player's king in check
+    LeavesKingInCheck,
+    /// Time can't be granted in a game without a clock
+    NoClock,
+    /// Granted time must be positive
+    InvalidTime(i32),
+}
+
+impl fmt::Display for EffectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EffectError::GameOver => write!(f, "The game is over"),
+            EffectError::UnexpectedTarget => write!(f, "This card takes no target"),
+            EffectError::ExtraMoveAlreadyPending => write!(f, "An extra move is already pending"),
+            EffectError::InvalidSquare(pos) => {
+                write!(f, "Invalid square: ({}, {})", pos.row, pos.col)
+            }
+            EffectError::NoPieceAtSource(pos) => {
+                write!(f, "No piece on {}", pos.to_algebraic())
+            }
+            EffectError::NotYourPiece(pos) => {
+                write!(f, "The piece on {} is not yours", pos.to_algebraic())
+            }
+            EffectError::SameSquare(pos) => {
+                write!(f, "The piece is already on {}", pos.to_algebraic())
+            }
+            EffectError::OccupiedByOwnPiece(pos) => {
+                write!(f, "{} holds one of your own pieces", pos.to_algebraic())
+            }
+            EffectError::PawnOnBackRank(pos) => {
+                write!(f, "A pawn can't be placed on {}", pos.to_algebraic())
+            }
+            EffectError::LeavesKingInCheck => write!(f, "That would leave your king in check"),
+            EffectError::NoClock => write!(f, "This game has no clock"),
+            EffectError::InvalidTime(seconds) => {
+                write!(f, "Granted time must be positive, got {}", seconds)
+            }
+        }
+    }
+}
+
+/// Something a card does to the game when played by the side to move
+pub trait CardEffect {
+    fn apply(&self, board: &mut Board) -> Result<(), EffectError>;
+}
+
+/// The side to move keeps the turn after its next move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtraMove;
+
+impl CardEffect for ExtraMove {
+    fn apply(&self, board: &mut Board) -> Result<(), EffectError> {
+        if board.grant_extra_move() {
+            Ok(())
+        } else {
+            Err(EffectError::ExtraMoveAlreadyPending)
+        }
+    }
+}
+
+/// Move one of the side to move's pieces to any square not holding its own pieces
+/// Landing on an enemy piece removes it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TeleportPiece {
+    pub from: Position,
+    pub to: Position,
+}
+
+impl CardEffect for TeleportPiece {
+    fn apply(&self, board: &mut Board) -> Result<(), EffectError> {
+        for square in [self.from, self.to] {
+            if !square.is_valid() {
+                return Err(EffectError::InvalidSquare(square));
+            }
+        }
+        if self.from == self.to {
+            return Err(EffectError::SameSquare(self.to));
+        }
+
+        let piece = board
+            .get_piece(self.from)
+            .ok_or(EffectError::NoPieceAtSource(self.from))?;
+        if piece.color != board.current_turn() {
+            return Err(EffectError::NotYourPiece(self.from));
+        }
+        if board
+            .get_piece(self.to)
+            .is_some_and(|target| target.color == piece.color)
+        {
+            return Err(EffectError::OccupiedByOwnPiece(self.to));
+        }
+        if piece.piece_type == PieceType::Pawn && (self.to.row == 0 || self.to.row == 7) {
+            return Err(EffectError::PawnOnBackRank(self.to));
+        }
+
+        let mut after = board.clone();
+        after.teleport_piece(self.from, self.to);
+        if is_in_check(&after, piece.color) {
+            return Err(EffectError::LeavesKingInCheck);
+        }
+
+        *board = after;
+        Ok(())
+    }
+}
+
+/// Add seconds to a player's clock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GrantTime {
+    pub player: Color,
+    pub seconds: i32,
+}
+
+impl CardEffect for GrantTime {
+    fn apply(&self, board: &mut Board) -> Result<(), EffectError> {
+        if self.seconds <= 0 {
+            return Err(EffectError::InvalidTime(self.seconds));
+        }
+        if board.add_time(self.player, self.seconds) {
+            Ok(())
+        } else {
+            Err(EffectError::NoClock)
+        }
+    }
+}
+
+impl CardEffect for Effect {
+    fn apply(&self, board: &mut Board) -> Result<(), EffectError> {
+        match *self {
+            Effect::ExtraMove => ExtraMove.apply(board),
+            Effect::TeleportPiece { from, to } => TeleportPiece { from, to }.apply(board),
+            Effect::GrantTime { player, seconds } => GrantTime { player, seconds }.apply(board),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::board::{BoardBuilder, HistoryEntry};
+    use crate::game::chess_clock::{ChessClockSettings, ClockMode};
+    use crate::game::piece::Move;
+    use std::collections::HashMap;
+
+    fn square(notation: &str) -> Position {
+        Position::from_algebraic(notation).unwrap()
+    }
+
+    fn teleport(from: &str, to: &str) -> TeleportPiece {
+        TeleportPiece {
+            from: square(from),
+            to: square(to),
+        }
+    }
+
+    #[test]
+    fn test_extra_move_keeps_the_turn_for_one_move() {
+        let mut board = Board::new();
+        assert_eq!(ExtraMove.apply(&mut board), Ok(()));
+        assert_eq!(
+            ExtraMove.apply(&mut board),
+            Err(EffectError::ExtraMoveAlreadyPending)
+        );
+
+        // e4 then d4, both by White
+        assert!(board.make_move(Move::new(square("e2"), square("e4"))));
+        assert_eq!(board.current_turn(), Color::White);
+        assert!(!board.has_extra_move());
+        assert!(board.make_move(Move::new(square("d2"), square("d4"))));
+        assert_eq!(board.current_turn(), Color::Black);
+
+        // Taking back the first move restores the pending extra move
+        assert!(board.unmake_move());
+        assert!(board.unmake_move());
+        assert_eq!(board.current_turn(), Color::White);
+        assert!(board.has_extra_move());
+    }
+
+    #[test]
+    fn test_teleport_moves_piece_and_captures() {
+        let mut board = Board::new();
+        let knight = board.get_piece(square("g1")).unwrap();
+
+        assert_eq!(teleport("g1", "e6").apply(&mut board), Ok(()));
+        assert_eq!(board.get_piece(square("g1")), None);
+        assert_eq!(board.get_piece(square("e6")), Some(knight));
+
+        // Onto the e7 pawn, which is removed
+        let pawn = board.get_piece(square("e7")).unwrap();
+        assert_eq!(teleport("e6", "e7").apply(&mut board), Ok(()));
+        assert_eq!(board.get_piece(square("e7")), Some(knight));
+        assert_eq!(board.get_pieces(Color::Black).len(), 15);
+        assert!(board
+            .get_pieces(Color::Black)
+            .iter()
+            .all(|(_, piece)| piece.id != pawn.id));
+        // Teleporting doesn't pass the turn
+        assert_eq!(board.current_turn(), Color::White);
+    }
+
+    #[test]
+    fn test_teleport_is_undone_like_a_move() {
+        let mut board = Board::new();
+        let start = board.to_fen();
+        assert!(board.make_move(Move::new(square("e2"), square("e4"))));
+        let after_e4 = board.to_fen();
+
+        // Black teleports a knight onto the e4 pawn, then plays a move
+        assert_eq!(teleport("g8", "e4").apply(&mut board), Ok(()));
+        // A teleport isn't a move
+        assert_eq!(board.move_count(), 1);
+        assert_eq!(
+            board.move_history(),
+            [Move::new(square("e2"), square("e4"))]
+        );
+        assert!(board.make_move(Move::new(square("d7"), square("d5"))));
+
+        assert!(board.unmake_move());
+        assert!(board.last_entry_is_teleport());
+        assert!(board.unmake_move());
+        assert_eq!(board.to_fen(), after_e4);
+        assert!(!board.card_effects_applied());
+        assert!(board.unmake_move());
+        assert_eq!(board.to_fen(), start);
+    }
+
+    #[test]
+    fn test_card_effects_are_kept_out_of_san_history() {
+        let mut board = Board::new();
+        assert!(board.make_move(Move::new(square("e2"), square("e4"))));
+        assert_eq!(teleport("b8", "c6").apply(&mut board), Ok(()));
+        assert!(board.make_move(Move::new(square("c6"), square("d4"))));
+        assert_eq!(board.san_history(), ["e4", "Nd4"]);
+        assert_eq!(
+            board.history(),
+            [
+                HistoryEntry::Move(Move::new(square("e2"), square("e4"))),
+                HistoryEntry::Teleport {
+                    from: square("b8"),
+                    to: square("c6")
+                },
+                HistoryEntry::Move(Move::new(square("c6"), square("d4"))),
+            ]
+        );
+
+        // Teleporting back and forth repeats the position
+        assert_eq!(teleport("g1", "h3").apply(&mut board), Ok(()));
+        assert_eq!(teleport("h3", "g1").apply(&mut board), Ok(()));
+        assert_eq!(board.repetition_count(), 2);
+
+        let mut board = Board::new();
+        assert_eq!(ExtraMove.apply(&mut board), Ok(()));
+        assert!(board.make_move(Move::new(square("e2"), square("e4"))));
+        assert!(board.make_move(Move::new(square("d2"), square("d4"))));
+        assert_eq!(board.san_history(), ["e4", "d4"]);
+        assert_eq!(board.history()[0], HistoryEntry::ExtraMove);
+    }
+
+    #[test]
+    fn test_teleport_rejects_illegal_targets() {
+        let mut board = Board::new();
+        let before = board.to_fen();
+
+        assert_eq!(
+            teleport("g1", "e1").apply(&mut board),
+            Err(EffectError::OccupiedByOwnPiece(square("e1")))
+        );
+        assert_eq!(
+            teleport("e4", "e5").apply(&mut board),
+            Err(EffectError::NoPieceAtSource(square("e4")))
+        );
+        assert_eq!(
+            teleport("g8", "f6").apply(&mut board),
+            Err(EffectError::NotYourPiece(square("g8")))
+        );
+        assert_eq!(
+            teleport("e2", "e8").apply(&mut board),
+            Err(EffectError::PawnOnBackRank(square("e8")))
+        );
+        assert_eq!(board.to_fen(), before);
+
+        // Moving the pinned bishop away exposes the king
+        let mut pinned = BoardBuilder::empty()
+            .piece("e1", PieceType::King, Color::White)
+            .piece("e2", PieceType::Bishop, Color::White)
+            .piece("e8", PieceType::King, Color::Black)
+            .piece("e7", PieceType::Rook, Color::Black)
+            .build()
+            .unwrap();
+        assert_eq!(
+            teleport("e2", "a6").apply(&mut pinned),
+            Err(EffectError::LeavesKingInCheck)
+        );
+        assert!(pinned.get_piece(square("e2")).is_some());
+    }
+
+    #[test]
+    fn test_grant_time_adds_to_clock() {
+        let mut board = Board::new_with_clock(Some(ChessClockSettings {
+            initial_times: HashMap::from([(0, 60), (1, 60)]),
+            move_increments: HashMap::new(),
+            first_move_increment: None,
+            mode: ClockMode::Fischer,
+            triggers: vec![],
+            stages: vec![],
+        }));
+
+        let grant = GrantTime {
+            player: Color::Black,
+            seconds: 15,
+        };
+        assert_eq!(grant.apply(&mut board), Ok(()));
+        assert_eq!(board.get_remaining_time(Color::Black), Some(75));
+        assert_eq!(board.get_remaining_time(Color::White), Some(60));
+
+        let nothing = GrantTime {
+            player: Color::Black,
+            seconds: 0,
+        };
+        assert_eq!(nothing.apply(&mut board), Err(EffectError::InvalidTime(0)));
+        assert_eq!(grant.apply(&mut Board::new()), Err(EffectError::NoClock));
+    }
+
+    #[test]
+    fn test_target_replaces_teleport_destination() {
+        let effect = Effect::TeleportPiece {
+            from: square("b1"),
+            to: square("c3"),
+        };
+        assert_eq!(effect.with_target(None), Ok(effect.clone()));
+        assert_eq!(
+            effect.with_target(Some(square("d5"))),
+            Ok(Effect::TeleportPiece {
+                from: square("b1"),
+                to: square("d5"),
+            })
+        );
+        assert_eq!(
+            Effect::ExtraMove.with_target(Some(square("d5"))),
+            Err(EffectError::UnexpectedTarget)
+        );
+    }
+
+    #[test]
+    fn test_time_grant_goes_to_the_player_of_the_card() {
+        let grant = Effect::GrantTime {
+            player: Color::White,
+            seconds: 30,
+        };
+        assert_eq!(
+            grant.played_by(Color::Black),
+            Effect::GrantTime {
+                player: Color::Black,
+                seconds: 30,
+            }
+        );
+        assert_eq!(Effect::ExtraMove.played_by(Color::Black), Effect::ExtraMove);
+    }
+
+    #[test]
+    fn test_effect_enum_dispatches_to_concrete_effects() {
+        let mut board = Board::new();
+        let effect = Effect::TeleportPiece {
+            from: square("b1"),
+            to: square("d5"),
+        };
+        assert_eq!(effect.apply(&mut board), Ok(()));
+        assert!(board.get_piece(square("d5")).is_some());
+        assert_eq!(Effect::ExtraMove.apply(&mut board), Ok(()));
+        assert!(board.has_extra_move());
+    }
+}
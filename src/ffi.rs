@@ -1,9 +1,11 @@
 // FFI layer for communicating with external clients
 // This layer should be thin and performant
 
+use crate::ai::Difficulty;
 use crate::game::board::GameStatus;
-use crate::game::game_state::ChessGame;
-use crate::game::piece::{Color, PieceType};
+use crate::game::game_state::{AiMoveOutcome, ChessGame};
+use crate::game::piece::{Color, PieceType, Position};
+use serde::Deserialize;
 use std::collections::HashMap;
 use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
@@ -20,7 +22,7 @@ static NEXT_GAME_ID: LazyLock<Mutex<u32>> = LazyLock::new(|| Mutex::new(0));
 pub struct GameState {
     pub game_id: u32,
     pub current_turn: u8,         // 0 = White, 1 = Black
-    pub status: u8, // 0 = Ongoing, 1 = Check, 2 = Checkmate White, 3 = Checkmate Black, 4 = Stalemate, 5 = Draw, 6 = TimeLoss White, 7 = TimeLoss Black
+    pub status: u8, // 0 = Ongoing, 1 = Check, 2 = Checkmate White, 3 = Checkmate Black, 4 = Stalemate, 5 = Draw, 6 = TimeLoss White, 7 = TimeLoss Black, 8 = Draw by repetition, 9 = Draw (both out of time), 10 = King captured (White wins), 11 = King captured (Black wins), 12 = Invalid (no kings), 13 = Draw by fifty-move rule
     pub white_time: i32, // -1 if no clock
     pub black_time: i32, // -1 if no clock
     pub board_state: *mut c_char, // JSON representation of board state
@@ -31,8 +33,12 @@ pub struct ActionResult {
     pub success: bool,
     pub game_state: GameState,
     pub error_message: *mut c_char,
+    pub error_code: u8, // 0 = None, 1-8 = IllegalReason::code() for rejected moves, 100 = Other error
 }
 
+/// Error code for failures that aren't an illegal move (bad input, unknown game, ...)
+const ERROR_OTHER: u8 = 100;
+
 /// Initialize a new game
 /// Returns game_id
 #[no_mangle]
@@ -52,9 +58,31 @@ pub extern "C" fn initialize_game(initial_time_seconds: i32, increment_seconds:
     game_id
 }
 
+/// Initialize an untimed queen-odds game
+/// odds_color: the side playing without its queen (0 = White, 1 = Black)
+/// Returns game_id
+#[no_mangle]
+pub extern "C" fn initialize_queen_odds_game(odds_color: u8) -> u32 {
+    let mut game = ChessGame::new();
+    match odds_color {
+        0 => game.reset_with_handicap(&[PieceType::Queen], &[]),
+        _ => game.reset_with_handicap(&[], &[PieceType::Queen]),
+    }
+
+    let mut instances = GAME_INSTANCES.lock().unwrap();
+    let mut next_id = NEXT_GAME_ID.lock().unwrap();
+    let game_id = *next_id;
+    *next_id += 1;
+
+    instances.insert(game_id, game);
+    game_id
+}
+
 /// Process an action and return the new game state
 /// action_type: 0 = MovePiece
-/// data: JSON string with action data
+/// data: JSON like {"from":{"row":6,"col":4},"to":{"row":4,"col":4},"promotion":"queen"}
+/// (promotion is optional). The old "from_row,from_col,to_row,to_col[,promotion]" string is
+/// still accepted but deprecated and will be removed
 #[no_mangle]
 pub extern "C" fn process_action(
     game_id: u32,
@@ -68,6 +96,7 @@ pub extern "C" fn process_action(
         None => {
             return ActionResult {
                 success: false,
+                error_code: ERROR_OTHER,
                 game_state: get_empty_game_state(),
                 error_message: create_c_string("Invalid game_id"),
             };
@@ -83,6 +112,7 @@ pub extern "C" fn process_action(
                     Err(_) => {
                         return ActionResult {
                             success: false,
+                            error_code: ERROR_OTHER,
                             game_state: get_game_state_from_game(game_id, game),
                             error_message: create_c_string("Invalid UTF-8 in data"),
                         };
@@ -90,92 +120,50 @@ pub extern "C" fn process_action(
                 }
             };
 
-            // Parse data as JSON: {"from": {"row": 0, "col": 0}, "to": {"row": 1, "col": 0}, "promotion": "queen"}
-            // For simplicity in this implementation, we'll use a simple format: "from_row,from_col,to_row,to_col" or "from_row,from_col,to_row,to_col,promotion"
-            let parts: Vec<&str> = data_str.split(',').collect();
-            if parts.len() < 4 {
-                return ActionResult {
-                    success: false,
-                    game_state: get_game_state_from_game(game_id, game),
-                    error_message: create_c_string("Invalid move data format"),
-                };
-            }
-
-            let from_row: i8 = match parts[0].parse() {
-                Ok(v) => v,
-                Err(_) => {
-                    return ActionResult {
-                        success: false,
-                        game_state: get_game_state_from_game(game_id, game),
-                        error_message: create_c_string("Invalid from_row"),
-                    };
-                }
-            };
-
-            let from_col: i8 = match parts[1].parse() {
-                Ok(v) => v,
-                Err(_) => {
+            let MoveData {
+                from,
+                to,
+                promotion,
+            } = match parse_move_data(data_str) {
+                Ok(m) => m,
+                Err(e) => {
                     return ActionResult {
                         success: false,
+                        error_code: ERROR_OTHER,
                         game_state: get_game_state_from_game(game_id, game),
-                        error_message: create_c_string("Invalid from_col"),
+                        error_message: create_c_string(&e),
                     };
                 }
             };
 
-            let to_row: i8 = match parts[2].parse() {
-                Ok(v) => v,
-                Err(_) => {
-                    return ActionResult {
-                        success: false,
-                        game_state: get_game_state_from_game(game_id, game),
-                        error_message: create_c_string("Invalid to_row"),
-                    };
-                }
-            };
-
-            let to_col: i8 = match parts[3].parse() {
-                Ok(v) => v,
-                Err(_) => {
-                    return ActionResult {
-                        success: false,
-                        game_state: get_game_state_from_game(game_id, game),
-                        error_message: create_c_string("Invalid to_col"),
-                    };
-                }
-            };
+            // Explain up front why an illegal move would fail
+            if let Err(reason) = game.explain_move(from, to) {
+                return ActionResult {
+                    success: false,
+                    error_code: reason.code(),
+                    game_state: get_game_state_from_game(game_id, game),
+                    error_message: create_c_string(&reason.to_string()),
+                };
+            }
 
             // Select the piece first
-            if !game.select_piece(from_row, from_col) {
+            if !game.select_piece(from.row, from.col) {
                 return ActionResult {
                     success: false,
+                    error_code: ERROR_OTHER,
                     game_state: get_game_state_from_game(game_id, game),
                     error_message: create_c_string("Cannot select piece at from position"),
                 };
             }
 
-            // Check if promotion
-            let success = if parts.len() > 4 {
-                let promotion_piece = match parts[4] {
-                    "queen" => PieceType::Queen,
-                    "rook" => PieceType::Rook,
-                    "bishop" => PieceType::Bishop,
-                    "knight" => PieceType::Knight,
-                    _ => {
-                        return ActionResult {
-                            success: false,
-                            game_state: get_game_state_from_game(game_id, game),
-                            error_message: create_c_string("Invalid promotion piece"),
-                        };
-                    }
-                };
-                game.try_move_selected_with_promotion(to_row, to_col, promotion_piece)
-            } else {
-                game.try_move_selected(to_row, to_col)
+            let success = match promotion {
+                Some(piece) => game.try_move_selected_with_promotion(to.row, to.col, piece),
+                None => game.try_move_selected(to.row, to.col),
             };
 
             ActionResult {
                 success,
+                error_code: if success { 0 } else { ERROR_OTHER },
                 game_state: get_game_state_from_game(game_id, game),
                 error_message: if success {
                     ptr::null_mut()
@@ -186,6 +174,7 @@ pub extern "C" fn process_action(
         }
         _ => ActionResult {
             success: false,
+            error_code: ERROR_OTHER,
             game_state: get_game_state_from_game(game_id, game),
             error_message: create_c_string("Unknown action type"),
         },
@@ -203,6 +192,19 @@ pub extern "C" fn get_game_state(game_id: u32) -> GameState {
     }
 }
 
+/// Get the game status as a stable lowercase reason, e.g. "checkmate_white" or "draw_repetition"
+/// Matches the strings returned by the Godot bridge; "invalid_game_id" for an unknown game
+/// The caller must release the string with free_string
+#[no_mangle]
+pub extern "C" fn get_status_string(game_id: u32) -> *mut c_char {
+    let instances = GAME_INSTANCES.lock().unwrap();
+
+    match instances.get(&game_id) {
+        Some(game) => create_c_string(game.get_game_status().name()),
+        None => create_c_string("invalid_game_id"),
+    }
+}
+
 /// Tick the game clock
 #[no_mangle]
 pub extern "C" fn tick_clock(game_id: u32) -> bool {
@@ -215,8 +217,9 @@ pub extern "C" fn tick_clock(game_id: u32) -> bool {
 }
 
 /// Make an AI move
+/// difficulty: 0 = Random, 1 = Easy, 2 = Medium, 3 = Hard (higher values play as Hard)
 #[no_mangle]
-pub extern "C" fn make_ai_move(game_id: u32) -> ActionResult {
+pub extern "C" fn make_ai_move(game_id: u32, difficulty: u8) -> ActionResult {
     let mut instances = GAME_INSTANCES.lock().unwrap();
 
     let game = match instances.get_mut(&game_id) {
@@ -224,25 +227,87 @@ pub extern "C" fn make_ai_move(game_id: u32) -> ActionResult {
         None => {
             return ActionResult {
                 success: false,
+                error_code: ERROR_OTHER,
                 game_state: get_empty_game_state(),
                 error_message: create_c_string("Invalid game_id"),
             };
         }
     };
 
-    let success = game.make_ai_move();
+    let outcome = game.make_ai_move_outcome_with_difficulty(Difficulty::from_level(difficulty));
 
     ActionResult {
-        success,
-        game_state: get_game_state_from_game(game_id, game),
-        error_message: if success {
-            ptr::null_mut()
+        success: outcome == AiMoveOutcome::Moved,
+        error_code: if outcome == AiMoveOutcome::Moved {
+            0
         } else {
-            create_c_string("No legal moves available")
+            ERROR_OTHER
+        },
+        game_state: get_game_state_from_game(game_id, game),
+        error_message: match outcome {
+            AiMoveOutcome::Moved => ptr::null_mut(),
+            AiMoveOutcome::Resigned(_) => create_c_string("AI resigned"),
+            AiMoveOutcome::NoLegalMoves => create_c_string("No legal moves available"),
         },
     }
 }
 
+/// Explain whether a move is legal and why not
+/// Returns JSON like {"legal": false, "reason": "LeavesKingInCheck"} (reason is null when legal)
+/// The caller must release the string with free_string
+#[no_mangle]
+pub extern "C" fn explain_move(
+    game_id: u32,
+    from_row: i8,
+    from_col: i8,
+    to_row: i8,
+    to_col: i8,
+) -> *mut c_char {
+    let instances = GAME_INSTANCES.lock().unwrap();
+
+    let result = match instances.get(&game_id) {
+        Some(game) => game.explain_move(
+            Position::new(from_row, from_col),
+            Position::new(to_row, to_col),
+        ),
+        None => return create_c_string("{\"legal\":false,\"reason\":\"InvalidGameId\"}"),
+    };
+
+    let json = match result {
+        Ok(()) => "{\"legal\":true,\"reason\":null}".to_string(),
+        Err(reason) => format!("{{\"legal\":false,\"reason\":\"{}\"}}", reason.name()),
+    };
+    create_c_string(&json)
+}
+
+/// Get the legal moves of the piece on a square
+/// Returns a JSON array like [{"row":5,"col":4},{"row":7,"col":4,"promotion":"queen"}], empty
+/// when the square is empty or holds a piece of the side not to move
+/// The caller must release the string with free_string
+#[no_mangle]
+pub extern "C" fn get_legal_moves(game_id: u32, row: i8, col: i8) -> *mut c_char {
+    let instances = GAME_INSTANCES.lock().unwrap();
+
+    let moves = match instances.get(&game_id) {
+        Some(game) => game.get_legal_moves_from(Position::new(row, col)),
+        None => Vec::new(),
+    };
+
+    let entries: Vec<String> = moves
+        .iter()
+        .map(|mv| match mv.promotion {
+            Some(piece) => format!(
+                "{{\"row\":{},\"col\":{},\"promotion\":\"{}\"}}",
+                mv.to.row,
+                mv.to.col,
+                piece_type_name(piece)
+            ),
+            None => format!("{{\"row\":{},\"col\":{}}}", mv.to.row, mv.to.col),
+        })
+        .collect();
+    create_c_string(&format!("[{}]", entries.join(",")))
+}
+
 /// Free a game instance
 #[no_mangle]
 pub extern "C" fn free_game(game_id: u32) {
@@ -262,6 +327,61 @@ pub extern "C" fn free_string(s: *mut c_char) {
 
 // Helper functions
 
+/// Move payload accepted by process_action
+#[derive(Debug, Deserialize)]
+struct MoveData {
+    from: Position,
+    to: Position,
+    #[serde(default)]
+    promotion: Option<PieceType>,
+}
+
+/// Parse move data as JSON, falling back to the deprecated comma-separated form
+fn parse_move_data(data: &str) -> Result<MoveData, String> {
+    if !data.trim_start().starts_with('{') {
+        return parse_legacy_move_data(data);
+    }
+
+    let move_data: MoveData =
+        serde_json::from_str(data).map_err(|e| format!("Invalid move JSON: {}", e))?;
+    if matches!(move_data.promotion, Some(PieceType::Pawn | PieceType::King)) {
+        return Err("Invalid promotion piece".to_string());
+    }
+    Ok(move_data)
+}
+
+/// Parse "from_row,from_col,to_row,to_col" with an optional ",promotion"
+/// Deprecated in favour of JSON move data
+fn parse_legacy_move_data(data: &str) -> Result<MoveData, String> {
+    let parts: Vec<&str> = data.split(',').collect();
+    if parts.len() < 4 {
+        return Err("Invalid move data format".to_string());
+    }
+
+    let coord = |index: usize, name: &str| -> Result<i8, String> {
+        parts[index]
+            .parse()
+            .map_err(|_| format!("Invalid {}", name))
+    };
+    let from = Position::new(coord(0, "from_row")?, coord(1, "from_col")?);
+    let to = Position::new(coord(2, "to_row")?, coord(3, "to_col")?);
+
+    let promotion = match parts.get(4) {
+        None => None,
+        Some(&"queen") => Some(PieceType::Queen),
+        Some(&"rook") => Some(PieceType::Rook),
+        Some(&"bishop") => Some(PieceType::Bishop),
+        Some(&"knight") => Some(PieceType::Knight),
+        Some(_) => return Err("Invalid promotion piece".to_string()),
+    };
+
+    Ok(MoveData {
+        from,
+        to,
+        promotion,
+    })
+}
+
 fn get_game_state_from_game(game_id: u32, game: &ChessGame) -> GameState {
     let status = game.get_game_status();
     let status_code = match status {
@@ -273,6 +393,12 @@ fn get_game_state_from_game(game_id: u32, game: &ChessGame) -> GameState {
         GameStatus::DrawInsufficientMaterial => 5,
         GameStatus::TimeLoss(Color::White) => 6,
         GameStatus::TimeLoss(Color::Black) => 7,
+        GameStatus::DrawRepetition => 8,
+        GameStatus::DrawTimeout => 9,
+        GameStatus::KingCaptured(Color::White) => 10,
+        GameStatus::KingCaptured(Color::Black) => 11,
+        GameStatus::Invalid => 12,
+        GameStatus::DrawFiftyMove => 13,
     };
 
     let current_turn = match game.get_current_turn() {
@@ -330,9 +456,171 @@ fn get_empty_game_state() -> GameState {
     }
 }
 
+fn piece_type_name(piece_type: PieceType) -> &'static str {
+    match piece_type {
+        PieceType::Pawn => "pawn",
+        PieceType::Knight => "knight",
+        PieceType::Bishop => "bishop",
+        PieceType::Rook => "rook",
+        PieceType::Queen => "queen",
+        PieceType::King => "king",
+    }
+}
+
 fn create_c_string(s: &str) -> *mut c_char {
     match CString::new(s) {
         Ok(cs) => cs.into_raw(),
         Err(_) => ptr::null_mut(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::rules::IllegalReason;
+
+    fn explain(game_id: u32, from: (i8, i8), to: (i8, i8)) -> String {
+        let ptr = explain_move(game_id, from.0, from.1, to.0, to.1);
+        let json = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+        free_string(ptr);
+        json
+    }
+
+    fn play(game_id: u32, data: &str) -> ActionResult {
+        let data = CString::new(data).unwrap();
+        process_action(game_id, 0, data.as_ptr())
+    }
+
+    fn status_string(game_id: u32) -> String {
+        let ptr = get_status_string(game_id);
+        let status = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+        free_string(ptr);
+        status
+    }
+
+    #[test]
+    fn test_status_string_reports_checkmate() {
+        let game_id = initialize_game(0, 0);
+        assert_eq!(status_string(game_id), "ongoing");
+
+        // Fool's mate: 1. f3 e5 2. g4 Qh4#
+        for data in ["1,5,2,5", "6,4,4,4", "1,6,3,6", "7,3,3,7"] {
+            assert!(play(game_id, data).success);
+        }
+        assert_eq!(status_string(game_id), "checkmate_black");
+
+        free_game(game_id);
+        assert_eq!(status_string(game_id), "invalid_game_id");
+    }
+
+    #[test]
+    fn test_json_move_data_plays_move() {
+        let game_id = initialize_game(0, 0);
+
+        let result = play(
+            game_id,
+            r#"{"from":{"row":1,"col":4},"to":{"row":3,"col":4}}"#,
+        );
+        assert!(result.success);
+        assert_eq!(result.game_state.current_turn, 1);
+
+        free_game(game_id);
+    }
+
+    #[test]
+    fn test_invalid_move_data_is_rejected_with_reason() {
+        let game_id = initialize_game(0, 0);
+
+        let message = |result: ActionResult| {
+            assert!(!result.success);
+            let text = unsafe { CStr::from_ptr(result.error_message) }
+                .to_str()
+                .unwrap()
+                .to_string();
+            free_string(result.error_message);
+            text
+        };
+
+        assert!(message(play(game_id, r#"{"from":{"row":1"#)).starts_with("Invalid move JSON"));
+        assert_eq!(message(play(game_id, "1,x,3,4")), "Invalid from_col");
+        assert_eq!(message(play(game_id, "1,4")), "Invalid move data format");
+
+        free_game(game_id);
+    }
+
+    fn legal_moves(game_id: u32, row: i8, col: i8) -> String {
+        let ptr = get_legal_moves(game_id, row, col);
+        let json = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+        free_string(ptr);
+        json
+    }
+
+    #[test]
+    fn test_legal_moves_lists_destinations_for_side_to_move() {
+        let game_id = initialize_game(0, 0);
+
+        // e2 pawn
+        assert_eq!(
+            legal_moves(game_id, 1, 4),
+            "[{\"row\":2,\"col\":4},{\"row\":3,\"col\":4}]"
+        );
+        // Empty square and a black piece on white's turn
+        assert_eq!(legal_moves(game_id, 3, 3), "[]");
+        assert_eq!(legal_moves(game_id, 6, 4), "[]");
+
+        free_game(game_id);
+        assert_eq!(legal_moves(game_id, 1, 4), "[]");
+    }
+
+    #[test]
+    fn test_legal_moves_include_promotion_choices() {
+        let game_id = initialize_game(0, 0);
+        // 1. h4 g5 2. hxg5 Nf6 3. g6 Ne4 4. gxh7 Rg8: h7 pawn can take g8 or push to h8
+        for data in [
+            "1,7,3,7", "6,6,4,6", "3,7,4,6", "7,6,5,5", "4,6,5,6", "5,5,3,4", "5,6,6,7", "7,7,7,6",
+        ] {
+            assert!(play(game_id, data).success);
+        }
+
+        let json = legal_moves(game_id, 6, 7);
+        for col in [6, 7] {
+            for piece in ["queen", "rook", "bishop", "knight"] {
+                assert!(json.contains(&format!(
+                    "{{\"row\":7,\"col\":{},\"promotion\":\"{}\"}}",
+                    col, piece
+                )));
+            }
+        }
+
+        free_game(game_id);
+    }
+
+    #[test]
+    fn test_pinned_piece_reports_leaves_king_in_check() {
+        let game_id = initialize_game(0, 0);
+        // 1. d4 e6 2. c4 Bb4+ 3. Nc3 e5: the c3 knight is pinned to the king
+        for data in [
+            "1,3,3,3", "6,4,5,4", "1,2,3,2", "7,5,3,1", "0,1,2,2", "5,4,4,4",
+        ] {
+            assert!(play(game_id, data).success);
+        }
+
+        // Nc3-d5
+        assert_eq!(
+            explain(game_id, (2, 2), (4, 3)),
+            "{\"legal\":false,\"reason\":\"LeavesKingInCheck\"}"
+        );
+        let result = play(game_id, "2,2,4,3");
+        assert!(!result.success);
+        assert_eq!(result.error_code, IllegalReason::LeavesKingInCheck.code());
+        free_string(result.error_message);
+
+        // Ng1-f3 is fine
+        assert_eq!(
+            explain(game_id, (0, 6), (2, 5)),
+            "{\"legal\":true,\"reason\":null}"
+        );
+
+        free_game(game_id);
+    }
+}
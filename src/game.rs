@@ -5,5 +5,7 @@ pub mod board;
 pub mod chess_clock;
 pub mod game_state;
 pub mod moves;
+pub mod perft;
 pub mod piece;
 pub mod rules;
+pub mod zobrist;
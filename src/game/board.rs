@@ -1,6 +1,14 @@
-use super::chess_clock::{ChessClock, ChessClockSettings};
+use super::chess_clock::{ChessClock, ChessClockSettings, TriggerType};
+use super::moves;
 use super::piece::{CastleSide, Color, GameAction, Move, Piece, PieceType, Position};
+use super::rules::{
+    attackers_of, checkers, generate_legal_moves, generate_legal_moves_by_piece, is_in_check,
+};
+use super::zobrist::{en_passant_capturable, hash_position};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Instant;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GameStatus {
@@ -9,10 +17,37 @@ pub enum GameStatus {
     Checkmate(Color), // Winner
     Stalemate,
     DrawInsufficientMaterial,
-    TimeLoss(Color), // Player who lost on time
+    TimeLoss(Color),     // Player who lost on time
+    DrawRepetition,      // Same position occurred five times
+    DrawFiftyMove,       // Fifty moves by each side without a capture or pawn move
+    DrawTimeout,         // Both players ran out of time
+    KingCaptured(Color), // Winner; the other king was removed (e.g. by a card effect)
+    Invalid,             // Neither king is on the board
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl GameStatus {
+    /// Stable lowercase reason string shared by the Godot bridge and the FFI
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameStatus::Ongoing => "ongoing",
+            GameStatus::Check => "check",
+            GameStatus::Checkmate(Color::White) => "checkmate_white",
+            GameStatus::Checkmate(Color::Black) => "checkmate_black",
+            GameStatus::Stalemate => "stalemate",
+            GameStatus::DrawInsufficientMaterial => "draw",
+            GameStatus::TimeLoss(Color::White) => "timeloss_white",
+            GameStatus::TimeLoss(Color::Black) => "timeloss_black",
+            GameStatus::DrawRepetition => "draw_repetition",
+            GameStatus::DrawFiftyMove => "draw_fifty_move",
+            GameStatus::DrawTimeout => "draw_timeout",
+            GameStatus::KingCaptured(Color::White) => "king_captured_white",
+            GameStatus::KingCaptured(Color::Black) => "king_captured_black",
+            GameStatus::Invalid => "invalid",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CastlingRights {
     pub white_kingside: bool,
     pub white_queenside: bool,
@@ -31,6 +66,88 @@ impl CastlingRights {
     }
 }
 
+/// Identity of a position for repetition and transposition checks
+/// Ignores piece IDs and move counters, like the Zobrist hash
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PositionKey {
+    squares: [[Option<(PieceType, Color)>; 8]; 8],
+    turn: Color,
+    castling: [bool; 4],
+    en_passant: Option<Position>, // Only when a pawn can actually capture there
+}
+
+/// Optional rules that chess variants can switch off
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RulesConfig {
+    pub castling_enabled: bool,
+    pub en_passant_enabled: bool,
+}
+
+impl RulesConfig {
+    /// Standard chess: every rule enabled
+    pub fn standard() -> Self {
+        Self {
+            castling_enabled: true,
+            en_passant_enabled: true,
+        }
+    }
+}
+
+impl Default for RulesConfig {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+/// One step of a game's history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryEntry {
+    Move(Move),
+    /// A card let the side to move keep the turn after its next move
+    ExtraMove,
+    /// A card moved a piece regardless of movement rules
+    Teleport {
+        from: Position,
+        to: Position,
+    },
+}
+
+/// What `make_move` changed, so `unmake_move` can restore the previous position exactly
+#[derive(Debug, Clone)]
+struct UndoRecord {
+    mv: Move,
+    moved_piece: Piece,
+    captured: Option<(Position, Piece)>, // Square and piece taken (en passant included)
+    rook_move: Option<(Position, Position)>, // Rook from/to when castling
+    castling_rights: CastlingRights,
+    en_passant_target: Option<Position>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    last_action: Option<GameAction>,
+    next_piece_id: u8,
+    chess_clock: Option<ChessClock>,
+    extra_move: bool,
+    current_turn: Color,
+    teleport: bool, // A card teleport rather than a move: the turn and move counts stay
+    card_effects_applied: bool,
+}
+
+/// Squares a move tried for legality changed, with what they held before
+/// At most four: from and to, plus an en passant victim or a castling rook's two squares
+#[derive(Debug, Default)]
+struct TrialMove {
+    saved: [Option<(Position, Option<Piece>)>; 4],
+    len: usize,
+}
+
+impl TrialMove {
+    fn set_piece(&mut self, board: &mut Board, pos: Position, piece: Option<Piece>) {
+        self.saved[self.len] = Some((pos, board.get_piece(pos)));
+        self.len += 1;
+        board.set_piece(pos, piece);
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Board {
     squares: [[Option<Piece>; 8]; 8],
@@ -40,9 +157,16 @@ pub struct Board {
     halfmove_clock: u32,
     fullmove_number: u32,
     chess_clock: Option<ChessClock>,
-    move_history: Vec<Move>,        // Track all moves for replay/undo
+    move_history: Vec<Move>,        // Track all moves for replay/undo (not card teleports)
     last_action: Option<GameAction>, // Track the last action for client animation
     next_piece_id: u8,               // Counter for creating new pieces (starts at 32)
+    position_history: Vec<u64>,      // Zobrist hashes of positions before each move
+    undo_stack: Vec<UndoRecord>,     // One record per move or card teleport, in order
+    move_counts: HashMap<u8, u32>,   // Piece ID -> times moved, absent if never moved
+    rules_config: RulesConfig,
+    extra_move: bool, // Side to move keeps the turn after its next move (card effect)
+    card_effects_applied: bool, // A card has changed the game
+    start_fen: String, // Position before the first move or teleport
 }
 
 impl Board {
@@ -50,6 +174,13 @@ impl Board {
         Self::new_with_clock(None)
     }
 
+    /// Create a board in the starting position with the given variant rules
+    pub fn new_with_rules(rules_config: RulesConfig) -> Self {
+        let mut board = Self::new();
+        board.rules_config = rules_config;
+        board
+    }
+
     pub fn new_with_clock(clock_settings: Option<ChessClockSettings>) -> Self {
         let mut board = Self {
             squares: [[None; 8]; 8],
@@ -62,6 +193,13 @@ impl Board {
             move_history: Vec::new(),
             last_action: None,
             next_piece_id: 32, // Start after the 32 initial pieces (0-31)
+            position_history: Vec::new(),
+            undo_stack: Vec::new(),
+            move_counts: HashMap::new(),
+            rules_config: RulesConfig::standard(),
+            extra_move: false,
+            card_effects_applied: false,
+            start_fen: String::new(),
         };
         board.setup_initial_position();
 
@@ -110,6 +248,7 @@ impl Board {
         self.squares[7][7] = Some(Piece::new(PieceType::Rook, Color::Black, 31));
 
         // Reset game state
+        self.move_counts.clear();
         self.current_turn = Color::White;
         self.castling_rights = CastlingRights::new();
         self.en_passant_target = None;
@@ -117,6 +256,68 @@ impl Board {
         self.fullmove_number = 1;
     }
 
+    /// Remove one piece of this type for an odds game, taking the queenside one first
+    /// Castling on the side of a removed rook is lost; kings can't be removed
+    /// Returns false if there was no such piece to remove
+    pub fn remove_handicap_piece(&mut self, color: Color, piece_type: PieceType) -> bool {
+        if piece_type == PieceType::King {
+            return false;
+        }
+
+        let Some(pos) = self
+            .get_pieces(color)
+            .into_iter()
+            .filter(|(_, piece)| piece.piece_type == piece_type)
+            .map(|(pos, _)| pos)
+            .min_by_key(|pos| (pos.col, pos.row))
+        else {
+            return false;
+        };
+        self.set_piece(pos, None);
+
+        if piece_type == PieceType::Rook {
+            match (color, pos.col) {
+                (Color::White, 0) => self.castling_rights.white_queenside = false,
+                (Color::White, 7) => self.castling_rights.white_kingside = false,
+                (Color::Black, 0) => self.castling_rights.black_queenside = false,
+                (Color::Black, 7) => self.castling_rights.black_kingside = false,
+                _ => {}
+            }
+        }
+        true
+    }
+
+    /// Check that the position is legal to start a game from: one king per color,
+    /// at most 16 pieces per color, no pawns on the back ranks and the side not to move not in check
+    pub fn validate(&self) -> Result<(), PositionError> {
+        for color in [Color::White, Color::Black] {
+            let pieces = self.get_pieces(color);
+            if let Some((pos, _)) = pieces.iter().find(|(pos, piece)| {
+                piece.piece_type == PieceType::Pawn && (pos.row == 0 || pos.row == 7)
+            }) {
+                return Err(PositionError::PawnOnBackRank(pos.to_algebraic()));
+            }
+            if pieces.len() > 16 {
+                return Err(PositionError::TooManyPieces(color));
+            }
+
+            let kings = pieces
+                .iter()
+                .filter(|(_, piece)| piece.piece_type == PieceType::King)
+                .count();
+            if kings != 1 {
+                return Err(PositionError::KingCount(color, kings));
+            }
+        }
+
+        let waiting = self.current_turn.opposite();
+        if is_in_check(self, waiting) {
+            return Err(PositionError::OpponentInCheck(waiting));
+        }
+
+        Ok(())
+    }
+
     pub fn get_piece(&self, pos: Position) -> Option<Piece> {
         if !pos.is_valid() {
             return None;
@@ -139,6 +340,24 @@ impl Board {
         self.current_turn = color;
     }
 
+    /// Hand out the next piece ID that no piece on the board is using
+    /// Skips IDs taken by hand-placed pieces so promotions never alias a live piece
+    fn allocate_piece_id(&mut self) -> u8 {
+        let in_use: Vec<u8> = self
+            .squares
+            .iter()
+            .flatten()
+            .flatten()
+            .map(|p| p.id)
+            .collect();
+        while in_use.contains(&self.next_piece_id) {
+            self.next_piece_id = self.next_piece_id.wrapping_add(1);
+        }
+        let id = self.next_piece_id;
+        self.next_piece_id = self.next_piece_id.wrapping_add(1);
+        id
+    }
+
     /// Convert Color to player ID (White=0, Black=1)
     fn color_to_player_id(color: Color) -> usize {
         match color {
@@ -151,6 +370,10 @@ impl Board {
         &self.castling_rights
     }
 
+    pub fn rules_config(&self) -> RulesConfig {
+        self.rules_config
+    }
+
     pub fn en_passant_target(&self) -> Option<Position> {
         self.en_passant_target
     }
@@ -174,16 +397,62 @@ impl Board {
     }
 
     pub fn make_move(&mut self, mv: Move) -> bool {
-        let piece = match self.get_piece(mv.from) {
-            Some(p) => p,
-            None => return false,
-        };
+        self.make_move_with_clock(mv, None)
+    }
 
+    /// Make a move with the clock switching sides at `now` rather than the system time
+    /// (for servers with their own clock source)
+    pub fn make_move_at(&mut self, mv: Move, now: Instant) -> bool {
+        self.make_move_with_clock(mv, Some(now))
+    }
+
+    /// The piece `mv` would move, if it belongs to the side to move and any promotion is valid
+    fn movable_piece(&self, mv: Move) -> Option<Piece> {
+        let piece = self.get_piece(mv.from)?;
         if piece.color != self.current_turn {
-            return false;
+            return None;
         }
 
+        // Only pawns reaching the last rank may promote
+        if mv.promotion.is_some() {
+            let promotion_row = if piece.color == Color::White { 7 } else { 0 };
+            if piece.piece_type != PieceType::Pawn || mv.to.row != promotion_row {
+                return None;
+            }
+        }
+        Some(piece)
+    }
+
+    fn make_move_with_clock(&mut self, mv: Move, now: Option<Instant>) -> bool {
+        let Some(piece) = self.movable_piece(mv) else {
+            return false;
+        };
+
+        // Remember the position we're leaving for repetition detection
+        self.record_start_position();
+        self.position_history.push(hash_position(self));
+
+        let mut undo = UndoRecord {
+            mv,
+            moved_piece: piece,
+            captured: None,
+            rook_move: None,
+            castling_rights: self.castling_rights.clone(),
+            en_passant_target: self.en_passant_target,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            last_action: self.last_action.clone(),
+            next_piece_id: self.next_piece_id,
+            chess_clock: self.chess_clock.clone(),
+            extra_move: self.extra_move,
+            current_turn: self.current_turn,
+            teleport: false,
+            card_effects_applied: self.card_effects_applied,
+        };
+        let keep_turn = std::mem::take(&mut self.extra_move);
+
         let captured_piece = self.get_piece(mv.to);
+        undo.captured = captured_piece.map(|p| (mv.to, p));
 
         // Handle en passant capture
         let is_en_passant = piece.piece_type == PieceType::Pawn
@@ -199,7 +468,9 @@ impl Board {
                 mv.to.row + 1
             };
             let captured_pawn_pos = Position::new(captured_pawn_row, mv.to.col);
-            self.get_piece(captured_pawn_pos).map(|p| (p.id, captured_pawn_pos))
+            let captured_pawn = self.get_piece(captured_pawn_pos);
+            undo.captured = captured_pawn.map(|p| (captured_pawn_pos, p));
+            captured_pawn.map(|p| (p.id, captured_pawn_pos))
         } else {
             None
         };
@@ -211,14 +482,14 @@ impl Board {
 
         // Handle promotion (create new piece with new ID)
         let (moving_piece, new_piece_id) = if let Some(promotion_type) = mv.promotion {
-            let new_id = self.next_piece_id;
-            self.next_piece_id += 1;
+            let new_id = self.allocate_piece_id();
             (Piece::new(promotion_type, piece.color, new_id), Some(new_id))
         } else {
             (piece, None)
         };
 
         self.set_piece(mv.to, Some(moving_piece));
+        *self.move_counts.entry(moving_piece.id).or_insert(0) += 1;
 
         // Handle en passant capture (remove the captured pawn)
         if is_en_passant {
@@ -245,6 +516,10 @@ impl Board {
             let rook = self.get_piece(rook_from_pos);
             self.set_piece(rook_from_pos, None);
             self.set_piece(rook_to_pos, rook);
+            if let Some(r) = rook {
+                *self.move_counts.entry(r.id).or_insert(0) += 1;
+            }
+            undo.rook_move = Some((rook_from_pos, rook_to_pos));
 
             // Capture rook info for GameAction
             rook.map(|r| (r.id, rook_from_pos, rook_to_pos, castle_side))
@@ -290,25 +565,30 @@ impl Board {
             self.halfmove_clock += 1;
         }
 
-        // Update move counters
-        if self.current_turn == Color::Black {
-            self.fullmove_number += 1;
-        }
+        // An extra move keeps the turn (and the clock) with the mover
+        if !keep_turn {
+            // Update move counters
+            if self.current_turn == Color::Black {
+                self.fullmove_number += 1;
+            }
 
-        // Handle chess clock
-        if let Some(ref mut clock) = self.chess_clock {
-            let current_player_id = Self::color_to_player_id(self.current_turn);
-            clock.end_turn(current_player_id);
+            // Handle chess clock
+            if let Some(ref mut clock) = self.chess_clock {
+                let now = now.unwrap_or_else(Instant::now);
+                let current_player_id = Self::color_to_player_id(self.current_turn);
+                clock.end_turn_at(current_player_id, now);
 
-            let next_player_id = Self::color_to_player_id(self.current_turn.opposite());
-            clock.start_player_clock(next_player_id);
-        }
+                let next_player_id = Self::color_to_player_id(self.current_turn.opposite());
+                clock.start_player_clock_at(next_player_id, now);
+            }
 
-        // Switch turns
-        self.current_turn = self.current_turn.opposite();
+            // Switch turns
+            self.current_turn = self.current_turn.opposite();
+        }
 
         // Record move in history
         self.move_history.push(mv);
+        self.undo_stack.push(undo);
 
         // Create GameAction for client animation
         self.last_action = if let Some((rook_id, rook_from, rook_to, side)) = castling_rook_info {
@@ -362,6 +642,239 @@ impl Board {
         true
     }
 
+    /// Take back the last move made with `make_move` (or card teleport), restoring the exact
+    /// previous state
+    /// Returns false if there is nothing to take back
+    pub fn unmake_move(&mut self) -> bool {
+        let Some(undo) = self.undo_stack.pop() else {
+            return false;
+        };
+
+        if !undo.teleport {
+            self.move_history.pop();
+        }
+        self.position_history.pop();
+        self.current_turn = undo.current_turn;
+
+        if let Some(piece) = self.get_piece(undo.mv.to).filter(|_| !undo.teleport) {
+            self.uncount_move(piece.id);
+        }
+        self.set_piece(undo.mv.to, None);
+        self.set_piece(undo.mv.from, Some(undo.moved_piece));
+        if let Some((pos, piece)) = undo.captured {
+            self.set_piece(pos, Some(piece));
+        }
+        if let Some((rook_from, rook_to)) = undo.rook_move {
+            let rook = self.get_piece(rook_to);
+            if let Some(r) = rook {
+                self.uncount_move(r.id);
+            }
+            self.set_piece(rook_to, None);
+            self.set_piece(rook_from, rook);
+        }
+
+        self.castling_rights = undo.castling_rights;
+        self.en_passant_target = undo.en_passant_target;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_number = undo.fullmove_number;
+        self.last_action = undo.last_action;
+        self.next_piece_id = undo.next_piece_id;
+        self.chess_clock = undo.chess_clock;
+        self.extra_move = undo.extra_move;
+        self.card_effects_applied = undo.card_effects_applied;
+        true
+    }
+
+    /// Remember the current position as the start of the history if nothing has been played
+    fn record_start_position(&mut self) {
+        if self.undo_stack.is_empty() {
+            self.start_fen = self.to_fen();
+        }
+    }
+
+    /// FEN of the position the history starts from (handicaps, edits and FEN setups
+    /// included)
+    pub fn start_fen(&self) -> String {
+        if self.undo_stack.is_empty() {
+            self.to_fen()
+        } else {
+            self.start_fen.clone()
+        }
+    }
+
+    /// Let the side to move move again after its next move
+    /// Returns false if an extra move is already pending
+    pub fn grant_extra_move(&mut self) -> bool {
+        if self.extra_move {
+            return false;
+        }
+        self.extra_move = true;
+        self.card_effects_applied = true;
+        true
+    }
+
+    /// Whether the side to move keeps the turn after its next move
+    pub fn has_extra_move(&self) -> bool {
+        self.extra_move
+    }
+
+    /// Move a piece from one square to another regardless of movement rules (card effects)
+    /// Whatever stood on `to` is removed. Castling rights tied to either square are lost
+    /// The teleport isn't a move, so it stays out of the move history and move count, but
+    /// `unmake_move` takes it back and `history` lists it
+    /// Returns false if there is no piece on `from` or a square is off the board
+    pub fn teleport_piece(&mut self, from: Position, to: Position) -> bool {
+        if !to.is_valid() {
+            return false;
+        }
+        let Some(piece) = self.get_piece(from) else {
+            return false;
+        };
+        let captured = self.get_piece(to);
+
+        self.record_start_position();
+        self.position_history.push(hash_position(self));
+        let mv = Move::new(from, to);
+        self.undo_stack.push(UndoRecord {
+            mv,
+            moved_piece: piece,
+            captured: captured.map(|victim| (to, victim)),
+            rook_move: None,
+            castling_rights: self.castling_rights.clone(),
+            en_passant_target: self.en_passant_target,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            last_action: self.last_action.clone(),
+            next_piece_id: self.next_piece_id,
+            chess_clock: self.chess_clock.clone(),
+            extra_move: self.extra_move,
+            current_turn: self.current_turn,
+            teleport: true,
+            card_effects_applied: self.card_effects_applied,
+        });
+        self.card_effects_applied = true;
+
+        self.set_piece(from, None);
+        self.set_piece(to, Some(piece));
+        self.en_passant_target = None;
+        for square in [from, to] {
+            self.revoke_castling_rights_at(square);
+        }
+
+        self.last_action = Some(match captured {
+            Some(victim) => GameAction::Capture {
+                attacker_id: piece.id,
+                victim_id: victim.id,
+                from,
+                to,
+            },
+            None => GameAction::Move {
+                piece_id: piece.id,
+                from,
+                to,
+            },
+        });
+        true
+    }
+
+    /// Drop the castling rights whose king or rook starts on this square
+    fn revoke_castling_rights_at(&mut self, square: Position) {
+        let rights = &mut self.castling_rights;
+        match (square.row, square.col) {
+            (0, 4) => {
+                rights.white_kingside = false;
+                rights.white_queenside = false;
+            }
+            (0, 0) => rights.white_queenside = false,
+            (0, 7) => rights.white_kingside = false,
+            (7, 4) => {
+                rights.black_kingside = false;
+                rights.black_queenside = false;
+            }
+            (7, 0) => rights.black_queenside = false,
+            (7, 7) => rights.black_kingside = false,
+            _ => {}
+        }
+    }
+
+    fn uncount_move(&mut self, id: u8) {
+        if let Some(count) = self.move_counts.get_mut(&id) {
+            *count -= 1;
+            if *count == 0 {
+                self.move_counts.remove(&id);
+            }
+        }
+    }
+
+    /// How many times the piece with this ID has moved (castling moves the rook too)
+    /// A promoted piece counts its promotion move
+    pub fn piece_move_count(&self, id: u8) -> u32 {
+        self.move_counts.get(&id).copied().unwrap_or(0)
+    }
+
+    /// Whether the piece with this ID has moved since the position was set up
+    pub fn has_piece_moved(&self, id: u8) -> bool {
+        self.piece_move_count(id) > 0
+    }
+
+    /// Check whether `color` would be in check after `mv`, testing in place with a trial
+    /// move that only touches the squares (no clock, history or start FEN to save and restore)
+    /// A move that can't be made leaves the board as it is
+    pub fn is_check_after(&mut self, mv: Move, color: Color) -> bool {
+        let Some(trial) = self.make_trial_move(mv) else {
+            return is_in_check(self, color);
+        };
+        let in_check = is_in_check(self, color);
+        self.unmake_trial_move(trial);
+        in_check
+    }
+
+    /// Play `mv` on the squares alone, for legality tests
+    /// Returns None, changing nothing, if the move can't be made
+    fn make_trial_move(&mut self, mv: Move) -> Option<TrialMove> {
+        let piece = self.movable_piece(mv)?;
+        let mut trial = TrialMove::default();
+
+        let is_en_passant = piece.piece_type == PieceType::Pawn
+            && Some(mv.to) == self.en_passant_target
+            && mv.from.col != mv.to.col
+            && self.get_piece(mv.to).is_none();
+        let is_castling =
+            piece.piece_type == PieceType::King && (mv.to.col - mv.from.col).abs() == 2;
+
+        // A promoted piece keeps the pawn's ID: no new ID is handed out for a trial
+        let moved = match mv.promotion {
+            Some(promotion_type) => Piece::new(promotion_type, piece.color, piece.id),
+            None => piece,
+        };
+        trial.set_piece(self, mv.from, None);
+        trial.set_piece(self, mv.to, Some(moved));
+
+        if is_en_passant {
+            trial.set_piece(self, Position::new(mv.from.row, mv.to.col), None);
+        }
+        if is_castling {
+            let (rook_from_col, rook_to_col) = if mv.to.col > mv.from.col {
+                (7, 5)
+            } else {
+                (0, 3)
+            };
+            let rook_from = Position::new(mv.from.row, rook_from_col);
+            let rook = self.get_piece(rook_from);
+            trial.set_piece(self, rook_from, None);
+            trial.set_piece(self, Position::new(mv.from.row, rook_to_col), rook);
+        }
+
+        Some(trial)
+    }
+
+    /// Put back the squares a trial move changed, last change first
+    fn unmake_trial_move(&mut self, trial: TrialMove) {
+        for (pos, piece) in trial.saved[..trial.len].iter().rev().flatten() {
+            self.set_piece(*pos, *piece);
+        }
+    }
+
     /// Creates a copy of the board and makes a move on it
     pub fn make_move_copy(&self, mv: Move) -> Board {
         let mut new_board = self.clone();
@@ -385,6 +898,62 @@ impl Board {
         pieces
     }
 
+    /// Get the legal destinations for every piece of the side to move, in a single pass
+    /// over the position (see `generate_legal_moves_by_piece`)
+    /// Pieces without legal moves are omitted, so the keys are exactly the movable pieces
+    pub fn legal_moves_map(&self) -> HashMap<Position, Vec<Position>> {
+        let mut map = HashMap::new();
+
+        for (pos, moves) in generate_legal_moves_by_piece(self) {
+            let mut destinations: Vec<Position> = Vec::new();
+            for mv in moves {
+                // Promotions produce several moves to the same square
+                if !destinations.contains(&mv.to) {
+                    destinations.push(mv.to);
+                }
+            }
+
+            if !destinations.is_empty() {
+                map.insert(pos, destinations);
+            }
+        }
+
+        map
+    }
+
+    /// Get the squares of the enemy pieces giving check to the side to move
+    /// Empty when not in check, two entries on a double check
+    pub fn pieces_giving_check(&self) -> Vec<Position> {
+        checkers(self, self.current_turn)
+    }
+
+    /// Get the squares of this side's pieces that the enemy attacks and nothing defends
+    /// The king is never included, an attack on it is a check
+    pub fn threatened_pieces(&self, color: Color) -> Vec<Position> {
+        let mut threatened = Vec::new();
+
+        for (pos, piece) in self.get_pieces(color) {
+            if piece.piece_type == PieceType::King
+                || attackers_of(self, pos, color.opposite()).is_empty()
+            {
+                continue;
+            }
+
+            // A piece defends a square only if it could recapture there, so swap in an
+            // enemy piece and look for attackers of our own color
+            let mut probe = self.clone();
+            probe.set_piece(
+                pos,
+                Some(Piece::new(piece.piece_type, color.opposite(), piece.id)),
+            );
+            if attackers_of(&probe, pos, color).is_empty() {
+                threatened.push(pos);
+            }
+        }
+
+        threatened
+    }
+
     /// Tick the chess clock (called every second)
     /// Returns false if the active player ran out of time
     pub fn tick_clock(&mut self) -> bool {
@@ -394,6 +963,30 @@ impl Board {
         true
     }
 
+    /// Restart the running side's clock at `now`, so time before it isn't charged
+    pub fn restart_clock_at(&mut self, now: Instant) {
+        if let Some(ref mut clock) = self.chess_clock {
+            if let Some(player_id) = clock.active_player() {
+                clock.start_player_clock_at(player_id, now);
+            }
+        }
+    }
+
+    /// Tick the chess clock by the wall time elapsed since the last tick
+    /// Returns false if the active player ran out of time
+    pub fn tick_clock_at(&mut self, now: Instant) -> bool {
+        if let Some(ref mut clock) = self.chess_clock {
+            return clock.tick_at(now);
+        }
+        true
+    }
+
+    /// Get remaining time for a player in milliseconds
+    pub fn get_remaining_time_ms(&self, color: Color) -> Option<i64> {
+        let clock = self.chess_clock.as_ref()?;
+        clock.get_remaining_time_ms(Self::color_to_player_id(color))
+    }
+
     /// Get remaining time for a player
     pub fn get_remaining_time(&self, color: Color) -> Option<i32> {
         if let Some(ref clock) = self.chess_clock {
@@ -404,18 +997,36 @@ impl Board {
     }
 
     /// Check if any player has lost on time
-    /// Returns Some(Color) for the player who lost, None otherwise
+    /// Returns Some(Color) for the player who lost, None otherwise (including when both flagged)
     pub fn check_time_loss(&self) -> Option<Color> {
-        if let Some(ref clock) = self.chess_clock {
-            if let Some(player_id) = clock.get_player_out_of_time() {
-                return Some(match player_id {
-                    0 => Color::White,
-                    1 => Color::Black,
-                    _ => return None,
-                });
-            }
+        match self.check_time_outcome() {
+            Some(GameStatus::TimeLoss(color)) => Some(color),
+            _ => None,
         }
-        None
+    }
+
+    /// Check the clock for a finished game
+    /// Returns TimeLoss for a single flagged player, DrawTimeout if both flagged, None otherwise
+    pub fn check_time_outcome(&self) -> Option<GameStatus> {
+        let clock = self.chess_clock.as_ref()?;
+        match clock.get_players_out_of_time().as_slice() {
+            [0] => Some(GameStatus::TimeLoss(Color::White)),
+            [1] => Some(GameStatus::TimeLoss(Color::Black)),
+            [0, 1] => Some(GameStatus::DrawTimeout),
+            _ => None,
+        }
+    }
+
+    /// Get the clock configuration (increments and triggers), if there is a clock
+    pub fn clock_settings(&self) -> Option<&ChessClockSettings> {
+        self.chess_clock.as_ref().map(ChessClock::settings)
+    }
+
+    /// Clock triggers that haven't fired yet (empty if there is no clock)
+    pub fn pending_clock_triggers(&self) -> Vec<(TriggerType, f32, i32)> {
+        self.chess_clock
+            .as_ref()
+            .map_or_else(Vec::new, ChessClock::pending_triggers)
     }
 
     /// Check if the board has a chess clock enabled
@@ -431,6 +1042,19 @@ impl Board {
         }
     }
 
+    /// Add seconds to a player's clock
+    /// Returns false if the board has no clock
+    pub fn add_time(&mut self, color: Color, seconds: i32) -> bool {
+        let player_id = Self::color_to_player_id(color);
+        match self.chess_clock {
+            Some(ref mut clock) => {
+                clock.add_time(player_id, seconds);
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Clear a square on the board (set to empty)
     pub fn clear_square(&mut self, pos: Position) {
         self.set_piece(pos, None);
@@ -441,6 +1065,90 @@ impl Board {
         &self.move_history
     }
 
+    /// Moves and card effects played so far, in order
+    pub fn history(&self) -> Vec<HistoryEntry> {
+        let mut entries = Vec::new();
+        for undo in &self.undo_stack {
+            if undo.teleport {
+                entries.push(HistoryEntry::Teleport {
+                    from: undo.mv.from,
+                    to: undo.mv.to,
+                });
+                continue;
+            }
+            if undo.extra_move {
+                entries.push(HistoryEntry::ExtraMove);
+            }
+            entries.push(HistoryEntry::Move(undo.mv));
+        }
+        if self.extra_move {
+            entries.push(HistoryEntry::ExtraMove);
+        }
+        entries
+    }
+
+    /// Replay the history from the start position, showing `visit` each entry together with
+    /// the position it was played in
+    pub fn replay_history(&self, mut visit: impl FnMut(&Board, &HistoryEntry)) {
+        let Ok(mut replay) = Board::from_fen(&self.start_fen()) else {
+            return;
+        };
+        replay.rules_config = self.rules_config;
+        for entry in self.history() {
+            visit(&replay, &entry);
+            match entry {
+                HistoryEntry::Move(mv) => {
+                    replay.make_move(mv);
+                }
+                HistoryEntry::ExtraMove => {
+                    replay.grant_extra_move();
+                }
+                HistoryEntry::Teleport { from, to } => {
+                    replay.teleport_piece(from, to);
+                }
+            }
+        }
+    }
+
+    /// SAN for every move played so far, replayed from the start position
+    /// Card effects aren't moves and have no SAN, but moves after them are written for the
+    /// position the card left
+    pub fn san_history(&self) -> Vec<String> {
+        let mut sans = Vec::new();
+        self.replay_history(|board, entry| {
+            if let HistoryEntry::Move(mv) = *entry {
+                sans.push(moves::to_san(board, mv));
+            }
+        });
+        sans
+    }
+
+    /// Whether the latest history entry is a card teleport rather than a move
+    /// Teleports aren't in `move_history`, but `unmake_move` takes them back first
+    pub fn last_entry_is_teleport(&self) -> bool {
+        self.undo_stack.last().is_some_and(|undo| undo.teleport)
+    }
+
+    /// Color that made the latest move (None if there is none or a teleport came after it)
+    pub fn last_mover(&self) -> Option<Color> {
+        self.undo_stack
+            .last()
+            .filter(|undo| !undo.teleport)
+            .map(|undo| undo.moved_piece.color)
+    }
+
+    /// Whether `color` has a move in the history (card teleports aren't moves)
+    pub fn has_moved(&self, color: Color) -> bool {
+        self.undo_stack
+            .iter()
+            .any(|undo| !undo.teleport && undo.moved_piece.color == color)
+    }
+
+    /// Whether a card effect has changed this game (undone teleports don't count)
+    pub fn card_effects_applied(&self) -> bool {
+        self.card_effects_applied
+    }
+
     /// Get the number of moves played
     pub fn move_count(&self) -> usize {
         self.move_history.len()
@@ -450,6 +1158,341 @@ impl Board {
     pub fn last_action(&self) -> Option<GameAction> {
         self.last_action.clone()
     }
+
+    /// Get the number of halfmoves since the last capture or pawn move
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    /// Get the fullmove number, which starts at 1 and goes up after each Black move
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    /// Sum of the standard material points of a color's pieces on the board
+    pub fn material_points(&self, color: Color) -> i32 {
+        self.squares
+            .iter()
+            .flatten()
+            .flatten()
+            .filter(|piece| piece.color == color)
+            .map(|piece| piece.piece_type.material_points())
+            .sum()
+    }
+
+    /// Number of knights and bishops a color has on the board
+    pub fn minor_piece_count(&self, color: Color) -> usize {
+        self.count_pieces(color, |piece_type| {
+            matches!(piece_type, PieceType::Knight | PieceType::Bishop)
+        })
+    }
+
+    /// Number of rooks and queens a color has on the board
+    pub fn major_piece_count(&self, color: Color) -> usize {
+        self.count_pieces(color, |piece_type| {
+            matches!(piece_type, PieceType::Rook | PieceType::Queen)
+        })
+    }
+
+    fn count_pieces(&self, color: Color, mut pred: impl FnMut(PieceType) -> bool) -> usize {
+        self.squares
+            .iter()
+            .flatten()
+            .flatten()
+            .filter(|piece| piece.color == color && pred(piece.piece_type))
+            .count()
+    }
+
+    /// Get the Zobrist hash of the current position
+    pub fn position_hash(&self) -> u64 {
+        hash_position(self)
+    }
+
+    /// Get the ID-agnostic identity of the current position
+    pub fn position_key(&self) -> PositionKey {
+        let mut squares = [[None; 8]; 8];
+        for (row, rank) in self.squares.iter().enumerate() {
+            for (col, square) in rank.iter().enumerate() {
+                squares[row][col] = square.map(|piece| (piece.piece_type, piece.color));
+            }
+        }
+
+        let rights = &self.castling_rights;
+        PositionKey {
+            squares,
+            turn: self.current_turn,
+            castling: [
+                rights.white_kingside,
+                rights.white_queenside,
+                rights.black_kingside,
+                rights.black_queenside,
+            ],
+            en_passant: self
+                .en_passant_target
+                .filter(|&target| en_passant_capturable(self, target)),
+        }
+    }
+
+    /// Check whether two boards hold the same position, regardless of piece IDs
+    pub fn position_equals(&self, other: &Board) -> bool {
+        self.position_key() == other.position_key()
+    }
+
+    /// Check whether the current position has the expected Zobrist hash
+    pub fn matches_hash(&self, expected: u64) -> bool {
+        self.position_hash() == expected
+    }
+
+    /// Replay a move list from the standard starting position
+    /// Returns the index of the first illegal move on failure
+    pub fn from_moves(moves: &[Move]) -> Result<Board, usize> {
+        let mut board = Board::new();
+        for (index, &mv) in moves.iter().enumerate() {
+            if !generate_legal_moves(&board, mv.from).contains(&mv) || !board.make_move(mv) {
+                return Err(index);
+            }
+        }
+        Ok(board)
+    }
+
+    /// Set up a position from Forsyth-Edwards Notation
+    /// Piece IDs follow the starting position's scheme (each color's pawns, then its other
+    /// pieces, in a1..h8 order), so the standard FEN gets the same IDs as `Board::new`
+    pub fn from_fen(fen: &str) -> Result<Board, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        let [placement, turn, castling, en_passant, halfmove, fullmove] = fields[..] else {
+            return Err(FenError::FieldCount(fields.len()));
+        };
+
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::RankCount(ranks.len()));
+        }
+
+        let mut placed = Vec::new();
+        for (rank, rank_fen) in ranks.iter().enumerate() {
+            let row = 7 - rank as i8;
+            let mut col = 0;
+            for c in rank_fen.chars() {
+                match c.to_digit(10) {
+                    Some(empty @ 1..=8) => col += empty as i8,
+                    _ => {
+                        let piece = Piece::from_fen_char(c, 0).ok_or(FenError::InvalidPiece(c))?;
+                        placed.push((Position::new(row, col), piece));
+                        col += 1;
+                    }
+                }
+                if col > 8 {
+                    return Err(FenError::RankLength(rank_fen.to_string()));
+                }
+            }
+            if col != 8 {
+                return Err(FenError::RankLength(rank_fen.to_string()));
+            }
+        }
+
+        let mut board = Board::new();
+        board.squares = [[None; 8]; 8];
+
+        placed.sort_by_key(|(pos, piece)| (piece.piece_type != PieceType::Pawn, pos.row, pos.col));
+        let mut next_ids = [0u8, 16u8];
+        for (pos, piece) in placed {
+            let index = Board::color_to_player_id(piece.color);
+            if next_ids[index] >= 16 * (index as u8 + 1) {
+                return Err(PositionError::TooManyPieces(piece.color).into());
+            }
+            let id = next_ids[index];
+            board.set_piece(pos, Some(Piece::new(piece.piece_type, piece.color, id)));
+            next_ids[index] += 1;
+        }
+
+        board.current_turn = match turn {
+            "w" => Color::White,
+            "b" => Color::Black,
+            _ => return Err(FenError::InvalidTurn(turn.to_string())),
+        };
+        board.castling_rights = board.parse_fen_castling(castling)?;
+        board.en_passant_target = board.parse_fen_en_passant(en_passant)?;
+
+        let counter = |field: &str| {
+            field
+                .parse::<u32>()
+                .map_err(|_| FenError::InvalidCounter(field.to_string()))
+        };
+        board.halfmove_clock = counter(halfmove)?;
+        board.fullmove_number = counter(fullmove)?;
+        if board.fullmove_number == 0 {
+            return Err(FenError::InvalidCounter(fullmove.to_string()));
+        }
+
+        board.validate()?;
+        Ok(board)
+    }
+
+    /// Castling rights from a FEN field, each of which needs its king and rook at home
+    fn parse_fen_castling(&self, field: &str) -> Result<CastlingRights, FenError> {
+        let invalid = || FenError::InvalidCastling(field.to_string());
+        let mut rights = CastlingRights {
+            white_kingside: false,
+            white_queenside: false,
+            black_kingside: false,
+            black_queenside: false,
+        };
+        if field == "-" {
+            return Ok(rights);
+        }
+
+        for c in field.chars() {
+            let (right, color, rook_col) = match c {
+                'K' => (&mut rights.white_kingside, Color::White, 7),
+                'Q' => (&mut rights.white_queenside, Color::White, 0),
+                'k' => (&mut rights.black_kingside, Color::Black, 7),
+                'q' => (&mut rights.black_queenside, Color::Black, 0),
+                _ => return Err(invalid()),
+            };
+            if *right {
+                return Err(invalid());
+            }
+
+            let row = match color {
+                Color::White => 0,
+                Color::Black => 7,
+            };
+            let has_piece = |col, piece_type| {
+                self.get_piece(Position::new(row, col))
+                    .is_some_and(|p| p.piece_type == piece_type && p.color == color)
+            };
+            if !has_piece(4, PieceType::King) || !has_piece(rook_col, PieceType::Rook) {
+                return Err(invalid());
+            }
+            *right = true;
+        }
+
+        Ok(rights)
+    }
+
+    /// En passant target from a FEN field; the opponent's pawn must have just double-pushed past it
+    fn parse_fen_en_passant(&self, field: &str) -> Result<Option<Position>, FenError> {
+        if field == "-" {
+            return Ok(None);
+        }
+
+        let invalid = || FenError::InvalidEnPassant(field.to_string());
+        let target = Position::from_algebraic(field).ok_or_else(invalid)?;
+        let pusher = self.current_turn.opposite();
+        // Rows the pawn passed over, then the one it started from and the one it landed on
+        let (target_row, start_row, pawn_row) = match pusher {
+            Color::White => (2, 1, 3),
+            Color::Black => (5, 6, 4),
+        };
+
+        let pawn = self.get_piece(Position::new(pawn_row, target.col));
+        let pushed = pawn.is_some_and(|p| p.piece_type == PieceType::Pawn && p.color == pusher);
+        let start = Position::new(start_row, target.col);
+        if target.row != target_row
+            || !pushed
+            || self.get_piece(target).is_some()
+            || self.get_piece(start).is_some()
+        {
+            return Err(invalid());
+        }
+
+        Ok(Some(target))
+    }
+
+    /// Describe the position in Forsyth-Edwards Notation
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+
+        for row in (0..8).rev() {
+            let mut empty = 0;
+            for col in 0..8 {
+                match self.get_piece(Position::new(row, col)) {
+                    Some(piece) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push(piece.to_fen_char());
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if row > 0 {
+                fen.push('/');
+            }
+        }
+
+        fen.push(' ');
+        fen.push(match self.current_turn {
+            Color::White => 'w',
+            Color::Black => 'b',
+        });
+
+        let rights = &self.castling_rights;
+        let castling: String = [
+            (rights.white_kingside, 'K'),
+            (rights.white_queenside, 'Q'),
+            (rights.black_kingside, 'k'),
+            (rights.black_queenside, 'q'),
+        ]
+        .iter()
+        .filter(|(allowed, _)| *allowed)
+        .map(|(_, c)| *c)
+        .collect();
+        fen.push(' ');
+        fen.push_str(if castling.is_empty() { "-" } else { &castling });
+
+        fen.push(' ');
+        match self.en_passant_target {
+            Some(target) => fen.push_str(&target.to_algebraic()),
+            None => fen.push('-'),
+        }
+
+        let counters = format!("{} {}", self.halfmove_clock, self.fullmove_number);
+        fen.push(' ');
+        fen.push_str(&counters);
+        fen
+    }
+
+    /// Render the board as plain ASCII with White at the bottom, for logs and test failures
+    /// Pieces use FEN letters (uppercase White, lowercase Black), empty squares are '.'
+    pub fn to_ascii(&self) -> String {
+        let mut ascii = String::new();
+
+        for row in (0..8).rev() {
+            ascii.push_str(&(row + 1).to_string());
+            for col in 0..8 {
+                ascii.push(' ');
+                ascii.push(
+                    self.get_piece(Position::new(row, col))
+                        .map_or('.', |piece| piece.to_fen_char()),
+                );
+            }
+            ascii.push('\n');
+        }
+        ascii.push_str("  a b c d e f g h\n");
+        ascii
+    }
+
+    /// Get how many times the current position has occurred, including now
+    pub fn repetition_count(&self) -> usize {
+        let current = self.position_hash();
+        1 + self
+            .position_history
+            .iter()
+            .filter(|&&hash| hash == current)
+            .count()
+    }
+
+    /// Check if the side to move may claim a draw
+    /// (threefold repetition or fifty-move rule)
+    pub fn can_claim_draw(&self) -> bool {
+        self.repetition_count() >= 3 || self.halfmove_clock >= 100
+    }
 }
 
 impl Default for Board {
@@ -457,3 +1500,796 @@ impl Default for Board {
         Self::new()
     }
 }
+
+/// Reasons a position built with BoardBuilder, or set up from outside, can be rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PositionError {
+    /// Square is not valid algebraic notation
+    InvalidSquare(String),
+    /// Two pieces were placed on the same square
+    DuplicateSquare(String),
+    /// A color does not have exactly one king
+    KingCount(Color, usize),
+    /// A color has more than 16 pieces
+    TooManyPieces(Color),
+    /// A pawn was placed on the first or last rank
+    PawnOnBackRank(String),
+    /// The side not to move is in check
+    OpponentInCheck(Color),
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PositionError::InvalidSquare(square) => write!(f, "Invalid square: {}", square),
+            PositionError::DuplicateSquare(square) => {
+                write!(f, "More than one piece on {}", square)
+            }
+            PositionError::KingCount(color, count) => {
+                write!(f, "{:?} has {} kings, expected 1", color, count)
+            }
+            PositionError::TooManyPieces(color) => write!(f, "{:?} has more than 16 pieces", color),
+            PositionError::PawnOnBackRank(square) => write!(f, "Pawn on back rank: {}", square),
+            PositionError::OpponentInCheck(color) => {
+                write!(f, "{:?} is in check but not to move", color)
+            }
+        }
+    }
+}
+
+/// Reasons `Board::from_fen` can reject a FEN string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenError {
+    /// FEN needs six space-separated fields
+    FieldCount(usize),
+    /// Piece placement needs eight ranks
+    RankCount(usize),
+    /// A rank does not describe exactly eight squares
+    RankLength(String),
+    /// Character is neither a piece letter nor an empty-square count
+    InvalidPiece(char),
+    /// Side to move is not "w" or "b"
+    InvalidTurn(String),
+    /// Castling field is malformed or claims a right without king and rook at home
+    InvalidCastling(String),
+    /// En passant square is malformed or no pawn just double-pushed past it
+    InvalidEnPassant(String),
+    /// Halfmove clock or fullmove number is not a valid count
+    InvalidCounter(String),
+    /// The fields parsed but describe an illegal position
+    Position(PositionError),
+}
+
+impl From<PositionError> for FenError {
+    fn from(error: PositionError) -> Self {
+        FenError::Position(error)
+    }
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::FieldCount(count) => write!(f, "Expected 6 FEN fields, found {}", count),
+            FenError::RankCount(count) => write!(f, "Expected 8 ranks, found {}", count),
+            FenError::RankLength(rank) => write!(f, "Rank does not have 8 squares: {}", rank),
+            FenError::InvalidPiece(c) => write!(f, "Invalid piece character: {}", c),
+            FenError::InvalidTurn(turn) => write!(f, "Invalid side to move: {}", turn),
+            FenError::InvalidCastling(field) => write!(f, "Invalid castling rights: {}", field),
+            FenError::InvalidEnPassant(field) => write!(f, "Invalid en passant square: {}", field),
+            FenError::InvalidCounter(field) => write!(f, "Invalid move counter: {}", field),
+            FenError::Position(error) => write!(f, "Illegal position: {}", error),
+        }
+    }
+}
+
+/// Fluent builder for custom positions (tests, puzzles)
+/// Piece IDs are assigned in placement order: 0-15 for White, 16-31 for Black
+#[derive(Debug, Clone)]
+pub struct BoardBuilder {
+    pieces: Vec<(String, PieceType, Color)>,
+    turn: Color,
+    castling_rights: CastlingRights,
+}
+
+impl BoardBuilder {
+    /// Start from an empty board with White to move and no castling rights
+    pub fn empty() -> Self {
+        Self {
+            pieces: Vec::new(),
+            turn: Color::White,
+            castling_rights: CastlingRights {
+                white_kingside: false,
+                white_queenside: false,
+                black_kingside: false,
+                black_queenside: false,
+            },
+        }
+    }
+
+    /// Place a piece on an algebraic square (e.g. "e1")
+    pub fn piece(mut self, square: &str, piece_type: PieceType, color: Color) -> Self {
+        self.pieces.push((square.to_string(), piece_type, color));
+        self
+    }
+
+    /// Set the side to move
+    pub fn turn(mut self, color: Color) -> Self {
+        self.turn = color;
+        self
+    }
+
+    /// Set the castling rights
+    pub fn castling(mut self, castling_rights: CastlingRights) -> Self {
+        self.castling_rights = castling_rights;
+        self
+    }
+
+    /// Build and validate the position
+    pub fn build(self) -> Result<Board, PositionError> {
+        let mut board = Board::new();
+        board.squares = [[None; 8]; 8];
+        board.current_turn = self.turn;
+        board.castling_rights = self.castling_rights;
+
+        let mut next_ids = [0u8, 16u8];
+        for (square, piece_type, color) in self.pieces {
+            let pos = Position::from_algebraic(&square)
+                .ok_or_else(|| PositionError::InvalidSquare(square.clone()))?;
+            if board.get_piece(pos).is_some() {
+                return Err(PositionError::DuplicateSquare(square));
+            }
+            if piece_type == PieceType::Pawn && (pos.row == 0 || pos.row == 7) {
+                return Err(PositionError::PawnOnBackRank(square));
+            }
+
+            let index = Board::color_to_player_id(color);
+            if next_ids[index] >= 16 * (index as u8 + 1) {
+                return Err(PositionError::TooManyPieces(color));
+            }
+            board.set_piece(pos, Some(Piece::new(piece_type, color, next_ids[index])));
+            next_ids[index] += 1;
+        }
+
+        board.validate()?;
+        Ok(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::rules::get_game_status;
+
+    #[test]
+    fn test_initial_position_assigns_unique_ids_by_color() {
+        let board = Board::new();
+        let mut seen = std::collections::HashSet::new();
+
+        for color in [Color::White, Color::Black] {
+            let pieces = board.get_pieces(color);
+            assert_eq!(pieces.len(), 16);
+            for (_, piece) in pieces {
+                assert!(seen.insert(piece.id), "duplicate piece id {}", piece.id);
+                match color {
+                    Color::White => assert!(piece.id < 16),
+                    Color::Black => assert!((16..32).contains(&piece.id)),
+                }
+            }
+        }
+        assert_eq!(seen.len(), 32);
+    }
+
+    #[test]
+    fn test_legal_moves_map_matches_per_piece_generation() {
+        // 8 pawns and 2 knights can move from the start position
+        assert_eq!(Board::new().legal_moves_map().len(), 10);
+
+        let positions = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            // Pinned knight and bishop, castling through an attacked square
+            "r3k2r/8/8/1b6/8/8/3N4/R3K2R w KQkq - 0 1",
+            // Check by a rook that can be blocked or captured
+            "4k3/8/8/8/4r3/8/3B4/R3K3 w Q - 0 1",
+            // Double check
+            "4k3/8/8/8/1b6/8/4r3/R3K2R w - - 0 1",
+            // En passant that would expose the king along the rank
+            "8/8/8/KPp4r/8/8/8/4k3 w - c6 0 2",
+            // Pawn pinned diagonally that may only capture its pinner
+            "4k3/8/8/8/8/2b5/3P4/4K3 w - - 0 1",
+        ];
+        for fen in positions {
+            let board = Board::from_fen(fen).unwrap();
+            let map = board.legal_moves_map();
+
+            for (pos, _) in board.get_pieces(board.current_turn()) {
+                let mut expected: Vec<Position> = Vec::new();
+                for mv in generate_legal_moves(&board, pos) {
+                    if !expected.contains(&mv.to) {
+                        expected.push(mv.to);
+                    }
+                }
+                expected.sort_by_key(|p| (p.row, p.col));
+
+                let mut actual = map.get(&pos).cloned().unwrap_or_default();
+                actual.sort_by_key(|p| (p.row, p.col));
+
+                assert_eq!(actual, expected, "{} from {}", fen, pos.to_algebraic());
+            }
+        }
+    }
+
+    #[test]
+    fn test_repetition_count_and_claim() {
+        let mut board = Board::new();
+        let shuffle = [
+            Move::new(Position::new(0, 6), Position::new(2, 5)), // Nf3
+            Move::new(Position::new(7, 6), Position::new(5, 5)), // Nf6
+            Move::new(Position::new(2, 5), Position::new(0, 6)), // Ng1
+            Move::new(Position::new(5, 5), Position::new(7, 6)), // Ng8
+        ];
+
+        assert_eq!(board.repetition_count(), 1);
+        for mv in shuffle {
+            board.make_move(mv);
+        }
+        assert_eq!(board.repetition_count(), 2);
+        assert!(!board.can_claim_draw());
+
+        for mv in shuffle {
+            board.make_move(mv);
+        }
+        assert_eq!(board.repetition_count(), 3);
+        assert!(board.can_claim_draw());
+    }
+
+    #[test]
+    fn test_castling_records_castle_action_with_both_ids() {
+        let mut board = Board::new();
+        // 1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5
+        for (from, to) in [
+            ("e2", "e4"),
+            ("e7", "e5"),
+            ("g1", "f3"),
+            ("b8", "c6"),
+            ("f1", "c4"),
+            ("f8", "c5"),
+        ] {
+            let mv = Move::new(
+                Position::from_algebraic(from).unwrap(),
+                Position::from_algebraic(to).unwrap(),
+            );
+            assert!(board.make_move(mv));
+        }
+        assert!(matches!(
+            board.last_action(),
+            Some(GameAction::Move { piece_id: 29, .. })
+        ));
+
+        // 4. O-O
+        assert!(board.make_move(Move::new(Position::new(0, 4), Position::new(0, 6))));
+        match board.last_action() {
+            Some(GameAction::Castle {
+                king_id,
+                rook_id,
+                king_from,
+                king_to,
+                rook_from,
+                rook_to,
+                side,
+            }) => {
+                assert_eq!(king_id, 12);
+                assert_eq!(rook_id, 15);
+                assert_eq!(
+                    (king_from, king_to),
+                    (Position::new(0, 4), Position::new(0, 6))
+                );
+                assert_eq!(
+                    (rook_from, rook_to),
+                    (Position::new(0, 7), Position::new(0, 5))
+                );
+                assert!(matches!(side, CastleSide::Kingside));
+            }
+            other => panic!("expected a castle, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_piece_ids_stable_across_capture_and_promotion() {
+        let mut board = BoardBuilder::empty()
+            .piece("e1", PieceType::King, Color::White)
+            .piece("b7", PieceType::Pawn, Color::White)
+            .piece("d4", PieceType::Knight, Color::White)
+            .piece("e8", PieceType::King, Color::Black)
+            .piece("a8", PieceType::Rook, Color::Black)
+            .piece("e6", PieceType::Pawn, Color::Black)
+            .build()
+            .unwrap();
+        let pawn_id = board.get_piece(Position::new(6, 1)).unwrap().id;
+        let knight_id = board.get_piece(Position::new(3, 3)).unwrap().id;
+        let rook_id = board.get_piece(Position::new(7, 0)).unwrap().id;
+        // A hand-placed piece already holding the next counter value
+        board.set_piece(
+            Position::new(0, 7),
+            Some(Piece::new(PieceType::Rook, Color::White, 32)),
+        );
+
+        // Nxe6 keeps the knight's ID
+        assert!(board.make_move(Move::new(Position::new(3, 3), Position::new(5, 4))));
+        assert_eq!(board.get_piece(Position::new(5, 4)).unwrap().id, knight_id);
+
+        // ...Ke7 steps off the knight's d8/f8, then bxa8=Q gets a fresh ID that no live piece uses
+        assert!(board.make_move(Move::new(Position::new(7, 4), Position::new(6, 4))));
+        let promotion =
+            Move::with_promotion(Position::new(6, 1), Position::new(7, 0), PieceType::Queen);
+        assert!(board.make_move(promotion));
+        let queen = board.get_piece(Position::new(7, 0)).unwrap();
+        assert_eq!(queen.id, 33);
+        match board.last_action() {
+            Some(GameAction::Promotion {
+                old_pawn_id,
+                new_piece_id,
+                captured_piece_id,
+                ..
+            }) => {
+                assert_eq!(old_pawn_id, pawn_id);
+                assert_eq!(new_piece_id, queen.id);
+                assert_eq!(captured_piece_id, Some(rook_id));
+            }
+            other => panic!("expected a promotion, got {:?}", other),
+        }
+        assert_eq!(board.get_piece(Position::new(5, 4)).unwrap().id, knight_id);
+    }
+
+    #[test]
+    fn test_promotion_rejected_off_the_last_rank() {
+        let mut board = BoardBuilder::empty()
+            .piece("e1", PieceType::King, Color::White)
+            .piece("d4", PieceType::Pawn, Color::White)
+            .piece("a1", PieceType::Rook, Color::White)
+            .piece("e8", PieceType::King, Color::Black)
+            .build()
+            .unwrap();
+
+        // Pawn pushing to rank 5 can't become a queen
+        let d5 = Position::from_algebraic("d5").unwrap();
+        let pawn_push = Move::with_promotion(Position::new(3, 3), d5, PieceType::Queen);
+        assert!(!board.make_move(pawn_push));
+        assert_eq!(board.get_piece(d5), None);
+
+        // Only pawns promote
+        let rook_move =
+            Move::with_promotion(Position::new(0, 0), Position::new(7, 0), PieceType::Queen);
+        assert!(!board.make_move(rook_move));
+        assert_eq!(
+            board.get_piece(Position::new(0, 0)).map(|p| p.piece_type),
+            Some(PieceType::Rook)
+        );
+        assert_eq!(board.move_count(), 0);
+    }
+
+    #[test]
+    fn test_builder_mate_in_one() {
+        let mut board = BoardBuilder::empty()
+            .piece("g6", PieceType::King, Color::White)
+            .piece("b1", PieceType::Queen, Color::White)
+            .piece("h8", PieceType::King, Color::Black)
+            .turn(Color::White)
+            .build()
+            .unwrap();
+
+        assert_eq!(get_game_status(&board), GameStatus::Ongoing);
+        assert_eq!(board.get_piece(Position::new(7, 7)).unwrap().id, 16);
+
+        // Qb8#
+        assert!(board.make_move(Move::new(Position::new(0, 1), Position::new(7, 1))));
+        assert_eq!(get_game_status(&board), GameStatus::Checkmate(Color::White));
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_positions() {
+        let kings = || {
+            BoardBuilder::empty()
+                .piece("e1", PieceType::King, Color::White)
+                .piece("e8", PieceType::King, Color::Black)
+        };
+        let rejected = |square: &str, piece_type: PieceType| {
+            kings()
+                .piece(square, piece_type, Color::White)
+                .build()
+                .unwrap_err()
+        };
+
+        assert_eq!(
+            BoardBuilder::empty()
+                .piece("e1", PieceType::King, Color::White)
+                .build()
+                .unwrap_err(),
+            PositionError::KingCount(Color::Black, 0)
+        );
+        assert_eq!(
+            rejected("z9", PieceType::Rook),
+            PositionError::InvalidSquare("z9".to_string())
+        );
+        assert_eq!(
+            rejected("e1", PieceType::Rook),
+            PositionError::DuplicateSquare("e1".to_string())
+        );
+        assert_eq!(
+            rejected("a8", PieceType::Pawn),
+            PositionError::PawnOnBackRank("a8".to_string())
+        );
+        // Black in check with White to move
+        assert_eq!(
+            rejected("e4", PieceType::Rook),
+            PositionError::OpponentInCheck(Color::Black)
+        );
+        assert!(kings()
+            .piece("e4", PieceType::Rook, Color::White)
+            .turn(Color::Black)
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_validate_hand_edited_position() {
+        let sq = |square: &str| Position::from_algebraic(square).unwrap();
+        let mut board = Board::new();
+        assert_eq!(board.validate(), Ok(()));
+
+        // Qh5 with f7 gone checks Black, who isn't to move
+        board.set_piece(sq("f7"), None);
+        board.set_piece(sq("h5"), board.get_piece(sq("d1")));
+        board.set_piece(sq("d1"), None);
+        assert_eq!(
+            board.validate(),
+            Err(PositionError::OpponentInCheck(Color::Black))
+        );
+        board.set_current_turn(Color::Black);
+        assert_eq!(board.validate(), Ok(()));
+
+        board.set_piece(sq("a1"), board.get_piece(sq("a2")));
+        board.set_piece(sq("a2"), None);
+        assert_eq!(
+            board.validate(),
+            Err(PositionError::PawnOnBackRank("a1".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_san_history() {
+        let mut board = Board::new();
+        for (from, to) in [
+            ("e2", "e4"),
+            ("d7", "d5"),
+            ("e4", "d5"),
+            ("f7", "f5"),
+            ("d1", "h5"),
+        ] {
+            let mv = Move::new(
+                Position::from_algebraic(from).unwrap(),
+                Position::from_algebraic(to).unwrap(),
+            );
+            assert!(board.make_move(mv));
+        }
+
+        assert_eq!(board.san_history(), ["e4", "d5", "exd5", "f5", "Qh5+"]);
+    }
+
+    #[test]
+    fn test_from_fen_round_trips() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let board = Board::from_fen(start).unwrap();
+        assert_eq!(board.to_fen(), start);
+        // Same pieces with the same IDs as a fresh board
+        for row in 0..8 {
+            for col in 0..8 {
+                let pos = Position::new(row, col);
+                assert_eq!(
+                    board.get_piece(pos).map(|p| p.id),
+                    Board::new().get_piece(pos).map(|p| p.id)
+                );
+            }
+        }
+
+        let sicilian = "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2";
+        let board = Board::from_fen(sicilian).unwrap();
+        assert_eq!(board.to_fen(), sicilian);
+        assert_eq!(board.current_turn(), Color::Black);
+        assert_eq!(board.halfmove_clock(), 1);
+
+        let en_passant = "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3";
+        let board = Board::from_fen(en_passant).unwrap();
+        assert_eq!(board.en_passant_target(), Position::from_algebraic("f6"));
+        assert_eq!(board.to_fen(), en_passant);
+    }
+
+    #[test]
+    fn test_from_fen_rejects_malformed() {
+        let cases = [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                FenError::RankCount(7),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1",
+                FenError::InvalidPiece('X'),
+            ),
+            (
+                "rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                FenError::InvalidPiece('9'),
+            ),
+            (
+                "rnbqkbnr/ppppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                FenError::RankLength("ppppppppp".to_string()),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+                FenError::InvalidTurn("x".to_string()),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 w KQkq - 0 1",
+                FenError::InvalidCastling("KQkq".to_string()),
+            ),
+            // No black pawn just pushed to e5, and e4 is on the wrong rank
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e6 0 1",
+                FenError::InvalidEnPassant("e6".to_string()),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e4 0 1",
+                FenError::InvalidEnPassant("e4".to_string()),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0",
+                FenError::FieldCount(5),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1",
+                FenError::InvalidCounter("x".to_string()),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQ1BNR w kq - 0 1",
+                FenError::Position(PositionError::KingCount(Color::White, 0)),
+            ),
+        ];
+
+        for (fen, expected) in cases {
+            assert_eq!(Board::from_fen(fen).unwrap_err(), expected, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_from_moves_and_fen() {
+        let mv = |from: &str, to: &str| {
+            Move::new(
+                Position::from_algebraic(from).unwrap(),
+                Position::from_algebraic(to).unwrap(),
+            )
+        };
+        // Ruy Lopez
+        let moves = [
+            mv("e2", "e4"),
+            mv("e7", "e5"),
+            mv("g1", "f3"),
+            mv("b8", "c6"),
+            mv("f1", "b5"),
+        ];
+
+        let board = Board::from_moves(&moves).unwrap();
+        assert_eq!(
+            board.to_fen(),
+            "r1bqkbnr/pppp1ppp/2n5/1B2p3/4P3/5N2/PPPP1PPP/RNBQK2R b KQkq - 3 3"
+        );
+
+        // A transposed move order reaches the same position hash
+        let transposed = [
+            mv("g1", "f3"),
+            mv("b8", "c6"),
+            mv("e2", "e4"),
+            mv("e7", "e5"),
+            mv("f1", "b5"),
+        ];
+        let expected_hash = Board::from_moves(&transposed).unwrap().position_hash();
+        assert!(board.matches_hash(expected_hash));
+        assert!(!board.matches_hash(Board::new().position_hash()));
+
+        assert_eq!(
+            Board::new().to_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+        );
+
+        // The third move is illegal (knight can't reach f4)
+        assert_eq!(
+            Board::from_moves(&[mv("e2", "e4"), mv("e7", "e5"), mv("g1", "f4")]).unwrap_err(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_material_points() {
+        let mut board = Board::new();
+        assert_eq!(board.material_points(Color::White), 39);
+        assert_eq!(board.material_points(Color::Black), 39);
+
+        // 1. e4 d5 2. exd5
+        for (from, to) in [("e2", "e4"), ("d7", "d5"), ("e4", "d5")] {
+            let mv = Move::new(
+                Position::from_algebraic(from).unwrap(),
+                Position::from_algebraic(to).unwrap(),
+            );
+            assert!(board.make_move(mv));
+        }
+        assert_eq!(
+            board.material_points(Color::White) - board.material_points(Color::Black),
+            1
+        );
+    }
+
+    #[test]
+    fn test_minor_and_major_piece_counts() {
+        let mut board = Board::new();
+        assert_eq!(board.minor_piece_count(Color::White), 4);
+        assert_eq!(board.major_piece_count(Color::White), 3);
+
+        board.set_piece(Position::new(7, 1), None); // Black knight b8
+        board.set_piece(Position::new(7, 3), None); // Black queen d8
+        assert_eq!(board.minor_piece_count(Color::Black), 3);
+        assert_eq!(board.major_piece_count(Color::Black), 2);
+        assert_eq!(board.minor_piece_count(Color::White), 4);
+    }
+
+    #[test]
+    fn test_position_equality_ignores_piece_ids() {
+        let board = Board::new();
+        let mut relabeled = Board::new();
+        let e2 = Position::new(1, 4);
+        relabeled.set_piece(e2, Some(Piece::new(PieceType::Pawn, Color::White, 40)));
+
+        assert_ne!(board.get_piece(e2), relabeled.get_piece(e2));
+        assert!(board.position_equals(&relabeled));
+        assert_eq!(board.position_key(), relabeled.position_key());
+        assert_eq!(board.position_hash(), relabeled.position_hash());
+
+        // A different piece type on the square is a different position
+        relabeled.set_piece(e2, Some(Piece::new(PieceType::Knight, Color::White, 40)));
+        assert!(!board.position_equals(&relabeled));
+    }
+
+    #[test]
+    fn test_unmake_restores_position_across_random_games() {
+        use crate::game::chess_clock::ClockMode;
+        use crate::game::rules::{generate_all_legal_moves, generate_pseudo_legal_all};
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let timed = Board::new_with_clock(Some(ChessClockSettings {
+            initial_times: HashMap::from([(0, 300), (1, 300)]),
+            move_increments: HashMap::from([(0, 5), (1, 5)]),
+            first_move_increment: None,
+            mode: ClockMode::Fischer,
+            triggers: vec![],
+            stages: vec![],
+        }));
+        // Castling both ways and an en passant capture are available
+        let from_fen =
+            Board::from_fen("r3k2r/ppp1qppp/2n2n2/3pP3/1b1P4/2N2N2/PPPQ1PPP/R3K2R w KQkq d6 0 9")
+                .unwrap();
+
+        let mut rng = StdRng::seed_from_u64(7);
+        for start in [Board::new(), timed, from_fen] {
+            for _ in 0..3 {
+                let mut board = start.clone();
+                for _ in 0..80 {
+                    let before = board.clone();
+                    let clock = format!("{:?}", board.chess_clock);
+                    let moves = generate_pseudo_legal_all(&board);
+
+                    // Legality tests leave the clock, history and start FEN alone
+                    for &mv in &moves {
+                        let color = board.current_turn();
+                        let expected = is_in_check(&before.make_move_copy(mv), color);
+                        assert_eq!(board.is_check_after(mv, color), expected, "{:?}", mv);
+                        assert!(board.position_equals(&before));
+                        assert_eq!(board.start_fen, before.start_fen);
+                        assert_eq!(format!("{:?}", board.chess_clock), clock);
+                        assert_eq!(board.position_history, before.position_history);
+                        assert_eq!(board.undo_stack.len(), before.undo_stack.len());
+                        assert_eq!(
+                            format!("{:?}", board.last_action),
+                            format!("{:?}", before.last_action)
+                        );
+                        assert_eq!(board.next_piece_id, before.next_piece_id);
+                    }
+
+                    for &mv in &moves {
+                        assert!(board.make_move(mv));
+                        assert!(board.unmake_move());
+                        assert!(
+                            board.position_equals(&before),
+                            "{:?} not undone:\n{}",
+                            mv,
+                            board.to_ascii()
+                        );
+                        assert_eq!(board.position_hash(), before.position_hash());
+                        assert_eq!(board.move_history(), before.move_history());
+                        assert_eq!(board.move_counts, before.move_counts);
+                        assert_eq!(board.start_fen(), before.start_fen());
+                        assert_eq!(format!("{:?}", board.chess_clock), clock);
+                    }
+
+                    let legal = generate_all_legal_moves(&board);
+                    if legal.is_empty() {
+                        break;
+                    }
+                    board.make_move(legal[rng.gen_range(0..legal.len())]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rook_returning_home_keeps_move_count_and_loses_castling() {
+        let mut board = Board::new();
+        let rook_id = board.get_piece(Position::new(0, 0)).unwrap().id;
+        assert!(!board.has_piece_moved(rook_id));
+
+        // 1. a4 a6 2. Ra3 b6 3. Ra1
+        for (from, to) in [
+            ("a2", "a4"),
+            ("a7", "a6"),
+            ("a1", "a3"),
+            ("b7", "b6"),
+            ("a3", "a1"),
+        ] {
+            let mv = Move::new(
+                Position::from_algebraic(from).unwrap(),
+                Position::from_algebraic(to).unwrap(),
+            );
+            assert!(board.make_move(mv));
+        }
+
+        assert_eq!(board.piece_move_count(rook_id), 2);
+        assert!(!board.castling_rights().white_queenside);
+        assert!(board.castling_rights().white_kingside);
+
+        assert!(board.unmake_move());
+        assert_eq!(board.piece_move_count(rook_id), 1);
+    }
+
+    #[test]
+    fn test_threatened_pieces_reports_only_undefended() {
+        let board = BoardBuilder::empty()
+            .piece("g1", PieceType::King, Color::White)
+            .piece("c3", PieceType::Knight, Color::White)
+            .piece("f3", PieceType::Knight, Color::White)
+            .piece("g2", PieceType::Pawn, Color::White)
+            .piece("g8", PieceType::King, Color::Black)
+            .piece("b4", PieceType::Pawn, Color::Black)
+            .piece("g4", PieceType::Pawn, Color::Black)
+            .build()
+            .unwrap();
+
+        // Both knights are attacked by pawns, only f3 is defended (by g2)
+        assert_eq!(
+            board.threatened_pieces(Color::White),
+            vec![Position::from_algebraic("c3").unwrap()]
+        );
+        assert!(board.threatened_pieces(Color::Black).is_empty());
+    }
+
+    #[test]
+    fn test_to_ascii_starting_position() {
+        let expected = "\
+8 r n b q k b n r
+7 p p p p p p p p
+6 . . . . . . . .
+5 . . . . . . . .
+4 . . . . . . . .
+3 . . . . . . . .
+2 P P P P P P P P
+1 R N B Q K B N R
+  a b c d e f g h
+";
+        assert_eq!(Board::new().to_ascii(), expected);
+    }
+}
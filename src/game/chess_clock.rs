@@ -1,14 +1,16 @@
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
 /// Trigger types for time increment events
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TriggerType {
     TotalMoves,
     TotalActionPoints,
 }
 
 /// Trigger configuration for adding time during the game
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TimeIncrementTrigger {
     pub trigger_type: TriggerType,
     pub threshold: f32,
@@ -16,52 +18,139 @@ pub struct TimeIncrementTrigger {
     pub targets: Vec<usize>,
 }
 
+/// A stage of a tournament time control: each player gets `seconds` more once they
+/// have made `moves` moves of their own (40/90 + 30 is a 30-minute stage at move 40)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeStage {
+    pub moves: u32,
+    pub seconds: i32,
+}
+
+/// How the per-move increment is credited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClockMode {
+    /// The full increment is added after every move
+    #[default]
+    Fischer,
+    /// The time spent on a move is given back after it, up to the increment
+    Bronstein,
+    /// The clock only starts running down once the increment has passed in each turn;
+    /// nothing is added after moves
+    SimpleDelay,
+}
+
 /// Clock settings for a chess game
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ChessClockSettings {
     /// Initial time for each player in seconds (indexed by player ID)
     pub initial_times: HashMap<usize, i32>,
     /// Time increment added after each move (indexed by player ID)
     pub move_increments: HashMap<usize, i32>,
+    /// Increment applied instead of `move_increments` on each player's first move
+    /// (None = first move uses the regular increment, Some(0) = no increment on the first move)
+    pub first_move_increment: Option<i32>,
+    /// How the increments are applied
+    #[serde(default)]
+    pub mode: ClockMode,
     /// Triggers for adding time based on game events
     pub triggers: Vec<TimeIncrementTrigger>,
+    /// Blocks of time granted to each player as they reach their own move counts
+    #[serde(default)]
+    pub stages: Vec<TimeStage>,
+}
+
+impl ChessClockSettings {
+    /// The same settings with players 0 and 1 exchanged, for a game with the colors swapped
+    pub fn with_players_swapped(&self) -> ChessClockSettings {
+        let swap = |player: usize| match player {
+            0 => 1,
+            1 => 0,
+            other => other,
+        };
+        let swap_keys = |map: &HashMap<usize, i32>| {
+            map.iter()
+                .map(|(&player, &value)| (swap(player), value))
+                .collect()
+        };
+
+        let mut settings = self.clone();
+        settings.initial_times = swap_keys(&self.initial_times);
+        settings.move_increments = swap_keys(&self.move_increments);
+        for trigger in &mut settings.triggers {
+            for target in &mut trigger.targets {
+                *target = swap(*target);
+            }
+        }
+        settings
+    }
 }
 
 /// Chess clock state tracking time for each player
 #[derive(Debug, Clone)]
 pub struct ChessClock {
     settings: ChessClockSettings,
-    /// Remaining time for each player in seconds (indexed by player ID)
-    remaining_times: HashMap<usize, i32>,
+    /// Remaining time for each player in milliseconds (indexed by player ID)
+    remaining_times: HashMap<usize, i64>,
     /// Track moves and action points for trigger evaluation
     total_moves: u32,
     total_action_points: u32,
+    /// Whether each trigger (indexed like `settings.triggers`) has already fired
+    fired_triggers: Vec<bool>,
+    /// Moves made by each player, for time stages
+    player_moves: HashMap<usize, u32>,
     /// Which player's clock is currently running (None if game hasn't started)
     active_player: Option<usize>,
+    /// When the active player's time was last deducted (for elapsed-time ticking)
+    last_tick: Option<Instant>,
+    /// Time the active player has spent on the current turn, in milliseconds
+    turn_elapsed_ms: i64,
 }
 
 impl ChessClock {
     /// Create a new chess clock from settings
     pub fn new(settings: ChessClockSettings) -> Self {
-        let remaining_times = settings.initial_times.clone();
+        let remaining_times = settings
+            .initial_times
+            .iter()
+            .map(|(&player_id, &seconds)| (player_id, seconds as i64 * 1000))
+            .collect();
+        let fired_triggers = vec![false; settings.triggers.len()];
 
         ChessClock {
             settings,
             remaining_times,
             total_moves: 0,
             total_action_points: 0,
+            fired_triggers,
+            player_moves: HashMap::new(),
             active_player: None,
+            last_tick: None,
+            turn_elapsed_ms: 0,
         }
     }
 
+    /// Get the settings this clock was created with
+    pub fn settings(&self) -> &ChessClockSettings {
+        &self.settings
+    }
+
     /// Start the clock for a specific player
     pub fn start_player_clock(&mut self, player_id: usize) {
+        self.start_player_clock_at(player_id, Instant::now());
+    }
+
+    /// Start the clock for a specific player at the given instant
+    pub fn start_player_clock_at(&mut self, player_id: usize, now: Instant) {
         self.active_player = Some(player_id);
+        self.last_tick = Some(now);
+        self.turn_elapsed_ms = 0;
     }
 
     /// Stop the current player's clock
     pub fn stop_clock(&mut self) {
         self.active_player = None;
+        self.last_tick = None;
+        self.turn_elapsed_ms = 0;
     }
 
     /// Get the currently active player (whose clock is running)
@@ -69,35 +158,134 @@ impl ChessClock {
         self.active_player
     }
 
-    /// Get remaining time for a player
+    /// Get remaining time for a player in whole seconds (partial seconds round up)
     pub fn get_remaining_time(&self, player_id: usize) -> Option<i32> {
+        self.get_remaining_time_ms(player_id).map(|ms| {
+            if ms > 0 {
+                ((ms + 999) / 1000) as i32
+            } else {
+                (ms / 1000) as i32
+            }
+        })
+    }
+
+    /// Get remaining time for a player in milliseconds
+    pub fn get_remaining_time_ms(&self, player_id: usize) -> Option<i64> {
         self.remaining_times.get(&player_id).copied()
     }
 
     /// Set remaining time for a player (used for network synchronization)
     pub fn set_remaining_time(&mut self, player_id: usize, seconds: i32) {
-        self.remaining_times.insert(player_id, seconds);
+        self.set_remaining_time_ms(player_id, seconds as i64 * 1000);
+    }
+
+    /// Set remaining time for a player in milliseconds
+    pub fn set_remaining_time_ms(&mut self, player_id: usize, ms: i64) {
+        self.remaining_times.insert(player_id, ms);
+    }
+
+    /// Add seconds to a player's remaining time (card effects)
+    pub fn add_time(&mut self, player_id: usize, seconds: i32) {
+        if let Some(time) = self.remaining_times.get_mut(&player_id) {
+            *time += seconds as i64 * 1000;
+        }
     }
 
     /// Decrement the active player's time by one second
+    /// That second counts as elapsed, so `end_turn` only charges time beyond the ticks
     /// Returns true if the player still has time, false if time ran out
     pub fn tick(&mut self) -> bool {
+        if let Some(last) = self.last_tick.as_mut() {
+            *last += Duration::from_secs(1);
+        }
+        self.deduct_active(1000)
+    }
+
+    /// Decrement the active player's time by the wall time elapsed since the last tick
+    /// Unlike `tick`, irregular tick intervals don't make the clock drift
+    /// Returns true if the player still has time, false if time ran out
+    pub fn tick_at(&mut self, now: Instant) -> bool {
+        let elapsed = self
+            .last_tick
+            .map_or(Duration::ZERO, |last| now.saturating_duration_since(last));
+        self.last_tick = Some(now);
+        self.deduct_active(elapsed.as_millis() as i64)
+    }
+
+    /// Deduct time from the active player, returning whether they still have time
+    /// In SimpleDelay mode, time within the turn's delay isn't deducted
+    fn deduct_active(&mut self, ms: i64) -> bool {
         if let Some(player_id) = self.active_player {
+            let delay_left = match self.settings.mode {
+                ClockMode::SimpleDelay => {
+                    (self.turn_increment_ms(player_id) - self.turn_elapsed_ms).max(0)
+                }
+                ClockMode::Fischer | ClockMode::Bronstein => 0,
+            };
+            self.turn_elapsed_ms += ms;
+
             if let Some(time) = self.remaining_times.get_mut(&player_id) {
-                *time -= 1;
+                *time -= (ms - delay_left).max(0);
                 return *time > 0;
             }
         }
         true
     }
 
+    /// Increment for a player's current turn in milliseconds, honoring `first_move_increment`
+    fn turn_increment_ms(&self, player_id: usize) -> i64 {
+        // Each player's first move happens while total_moves is 0 (White) or 1 (Black)
+        let is_first_move = self.total_moves < 2;
+
+        let increment = match self.settings.first_move_increment {
+            Some(first_increment) if is_first_move => Some(first_increment),
+            _ => self.settings.move_increments.get(&player_id).copied(),
+        };
+        increment.map_or(0, |seconds| seconds as i64 * 1000)
+    }
+
     /// Called when a player completes their move
-    /// Applies move increment and checks triggers
+    /// Applies move increment according to the clock mode and checks triggers
+    /// A player who has already flagged gets no time added, so the increment can't save them
     pub fn end_turn(&mut self, player_id: usize) {
+        self.end_turn_at(player_id, Instant::now());
+    }
+
+    /// Called when a player completes their move at `now`
+    /// The time spent since the last tick is charged before the increment is credited
+    pub fn end_turn_at(&mut self, player_id: usize, now: Instant) {
+        if self.active_player == Some(player_id) {
+            self.tick_at(now);
+        }
+
         // Apply move increment for this player
-        if let Some(increment) = self.settings.move_increments.get(&player_id) {
-            if let Some(time) = self.remaining_times.get_mut(&player_id) {
-                *time += increment;
+        let increment = self.turn_increment_ms(player_id);
+        let credit = match self.settings.mode {
+            ClockMode::Fischer => increment,
+            ClockMode::Bronstein => increment.min(self.turn_elapsed_ms),
+            ClockMode::SimpleDelay => 0,
+        };
+
+        if let Some(time) = self.remaining_times.get_mut(&player_id) {
+            if *time > 0 {
+                *time += credit;
+            }
+        }
+
+        // Grant the stages this move completes to the player who made it
+        let moves = self.player_moves.entry(player_id).or_default();
+        *moves += 1;
+        let moves = *moves;
+        let stage_ms: i64 = self
+            .settings
+            .stages
+            .iter()
+            .filter(|stage| stage.moves == moves)
+            .map(|stage| stage.seconds as i64 * 1000)
+            .sum();
+        if let Some(time) = self.remaining_times.get_mut(&player_id) {
+            if *time > 0 {
+                *time += stage_ms;
             }
         }
 
@@ -111,9 +299,41 @@ impl ChessClock {
         self.stop_clock();
     }
 
+    /// Number of moves a player has made
+    pub fn player_moves(&self, player_id: usize) -> u32 {
+        self.player_moves.get(&player_id).copied().unwrap_or(0)
+    }
+
+    /// Register action points earned during the game (for `TotalActionPoints` triggers)
+    pub fn add_action_points(&mut self, points: u32) {
+        self.total_action_points += points;
+        self.check_triggers();
+    }
+
+    /// Get the total action points registered so far
+    pub fn total_action_points(&self) -> u32 {
+        self.total_action_points
+    }
+
+    /// Triggers that haven't fired yet, as (type, threshold, increment in seconds)
+    pub fn pending_triggers(&self) -> Vec<(TriggerType, f32, i32)> {
+        self.settings
+            .triggers
+            .iter()
+            .zip(&self.fired_triggers)
+            .filter(|(_, &fired)| !fired)
+            .map(|(trigger, _)| (trigger.trigger_type, trigger.threshold, trigger.increment))
+            .collect()
+    }
+
     /// Check if any triggers should fire and apply them
+    /// Each trigger fires at most once, when its threshold is first reached
     fn check_triggers(&mut self) {
-        for trigger in &self.settings.triggers {
+        for (trigger, fired) in self.settings.triggers.iter().zip(&mut self.fired_triggers) {
+            if *fired {
+                continue;
+            }
+
             let should_fire = match trigger.trigger_type {
                 TriggerType::TotalMoves => self.total_moves as f32 >= trigger.threshold,
                 TriggerType::TotalActionPoints => {
@@ -122,10 +342,14 @@ impl ChessClock {
             };
 
             if should_fire {
-                // Apply increment to target players
+                *fired = true;
+
+                // Apply increment to target players who haven't flagged
                 for &player_id in &trigger.targets {
                     if let Some(time) = self.remaining_times.get_mut(&player_id) {
-                        *time += trigger.increment;
+                        if *time > 0 {
+                            *time += trigger.increment as i64 * 1000;
+                        }
                     }
                 }
             }
@@ -134,13 +358,21 @@ impl ChessClock {
 
     /// Check if any player has run out of time
     /// Returns Some(player_id) if a player lost on time, None otherwise
+    /// If several players are out of time, the lowest player ID is returned
     pub fn get_player_out_of_time(&self) -> Option<usize> {
-        for (&player_id, &time) in &self.remaining_times {
-            if time <= 0 {
-                return Some(player_id);
-            }
-        }
-        None
+        self.get_players_out_of_time().first().copied()
+    }
+
+    /// Get all players who have run out of time, sorted by player ID
+    pub fn get_players_out_of_time(&self) -> Vec<usize> {
+        let mut players: Vec<usize> = self
+            .remaining_times
+            .iter()
+            .filter(|(_, &time)| time <= 0)
+            .map(|(&player_id, _)| player_id)
+            .collect();
+        players.sort_unstable();
+        players
     }
 }
 
@@ -161,7 +393,10 @@ mod tests {
         let settings = ChessClockSettings {
             initial_times,
             move_increments: increments,
+            first_move_increment: None,
+            mode: ClockMode::Fischer,
             triggers: vec![],
+            stages: vec![],
         };
 
         let clock = ChessClock::new(settings);
@@ -177,7 +412,10 @@ mod tests {
         let settings = ChessClockSettings {
             initial_times,
             move_increments: HashMap::new(),
+            first_move_increment: None,
+            mode: ClockMode::Fischer,
             triggers: vec![],
+            stages: vec![],
         };
 
         let mut clock = ChessClock::new(settings);
@@ -189,6 +427,33 @@ mod tests {
         assert_eq!(clock.get_remaining_time(0), Some(5));
     }
 
+    #[test]
+    fn test_tick_at_deducts_elapsed_time() {
+        let mut initial_times = HashMap::new();
+        initial_times.insert(0, 10);
+
+        let settings = ChessClockSettings {
+            initial_times,
+            move_increments: HashMap::new(),
+            first_move_increment: None,
+            mode: ClockMode::Fischer,
+            triggers: vec![],
+            stages: vec![],
+        };
+
+        let mut clock = ChessClock::new(settings);
+        let start = Instant::now();
+        clock.start_player_clock_at(0, start);
+
+        // A late tick deducts the real gap, not a fixed second
+        assert!(clock.tick_at(start + Duration::from_millis(1500)));
+        assert_eq!(clock.get_remaining_time_ms(0), Some(8500));
+
+        assert!(clock.tick_at(start + Duration::from_millis(2400)));
+        assert_eq!(clock.get_remaining_time_ms(0), Some(7600));
+        assert_eq!(clock.get_remaining_time(0), Some(8));
+    }
+
     #[test]
     fn test_clock_timeout() {
         let mut initial_times = HashMap::new();
@@ -197,7 +462,10 @@ mod tests {
         let settings = ChessClockSettings {
             initial_times,
             move_increments: HashMap::new(),
+            first_move_increment: None,
+            mode: ClockMode::Fischer,
             triggers: vec![],
+            stages: vec![],
         };
 
         let mut clock = ChessClock::new(settings);
@@ -219,7 +487,10 @@ mod tests {
         let settings = ChessClockSettings {
             initial_times,
             move_increments: increments,
+            first_move_increment: None,
+            mode: ClockMode::Fischer,
             triggers: vec![],
+            stages: vec![],
         };
 
         let mut clock = ChessClock::new(settings);
@@ -235,4 +506,478 @@ mod tests {
         // Should have 60 - 5 + 10 = 65 seconds
         assert_eq!(clock.get_remaining_time(0), Some(65));
     }
+
+    #[test]
+    fn test_first_move_increment() {
+        let mut initial_times = HashMap::new();
+        initial_times.insert(0, 60);
+        initial_times.insert(1, 60);
+
+        let mut increments = HashMap::new();
+        increments.insert(0, 10);
+        increments.insert(1, 10);
+
+        let settings = ChessClockSettings {
+            initial_times,
+            move_increments: increments,
+            first_move_increment: Some(0),
+            mode: ClockMode::Fischer,
+            triggers: vec![],
+            stages: vec![],
+        };
+
+        let mut clock = ChessClock::new(settings);
+
+        // First move for each player: no increment
+        clock.start_player_clock(0);
+        clock.end_turn(0);
+        clock.start_player_clock(1);
+        clock.end_turn(1);
+        assert_eq!(clock.get_remaining_time(0), Some(60));
+        assert_eq!(clock.get_remaining_time(1), Some(60));
+
+        // Subsequent moves use the regular increment
+        clock.start_player_clock(0);
+        clock.end_turn(0);
+        clock.start_player_clock(1);
+        clock.end_turn(1);
+        assert_eq!(clock.get_remaining_time(0), Some(70));
+        assert_eq!(clock.get_remaining_time(1), Some(70));
+    }
+
+    #[test]
+    fn test_larger_first_move_increment() {
+        let mut initial_times = HashMap::new();
+        initial_times.insert(0, 60);
+
+        let mut increments = HashMap::new();
+        increments.insert(0, 5);
+
+        let settings = ChessClockSettings {
+            initial_times,
+            move_increments: increments,
+            first_move_increment: Some(30),
+            mode: ClockMode::Fischer,
+            triggers: vec![],
+            stages: vec![],
+        };
+
+        let mut clock = ChessClock::new(settings);
+        clock.end_turn(0);
+        assert_eq!(clock.get_remaining_time(0), Some(90));
+
+        // Black makes their first move, then White's second move gets the regular increment
+        clock.end_turn(1);
+        clock.end_turn(0);
+        assert_eq!(clock.get_remaining_time(0), Some(95));
+    }
+
+    #[test]
+    fn test_total_moves_trigger() {
+        let mut initial_times = HashMap::new();
+        initial_times.insert(0, 60);
+        initial_times.insert(1, 60);
+
+        let settings = ChessClockSettings {
+            initial_times,
+            move_increments: HashMap::new(),
+            first_move_increment: None,
+            mode: ClockMode::Fischer,
+            triggers: vec![TimeIncrementTrigger {
+                trigger_type: TriggerType::TotalMoves,
+                threshold: 10.0,
+                increment: 30,
+                targets: vec![1],
+            }],
+            stages: vec![],
+        };
+
+        let mut clock = ChessClock::new(settings);
+        for mv in 0..9 {
+            clock.end_turn(mv % 2);
+        }
+        assert_eq!(clock.get_remaining_time(1), Some(60));
+
+        // The tenth move fires the trigger for Black only
+        clock.end_turn(1);
+        assert_eq!(clock.get_remaining_time(0), Some(60));
+        assert_eq!(clock.get_remaining_time(1), Some(90));
+    }
+
+    #[test]
+    fn test_action_points_trigger() {
+        let mut initial_times = HashMap::new();
+        initial_times.insert(0, 60);
+        initial_times.insert(1, 60);
+
+        let settings = ChessClockSettings {
+            initial_times,
+            move_increments: HashMap::new(),
+            first_move_increment: None,
+            mode: ClockMode::Fischer,
+            triggers: vec![TimeIncrementTrigger {
+                trigger_type: TriggerType::TotalActionPoints,
+                threshold: 5.0,
+                increment: 15,
+                targets: vec![0, 1],
+            }],
+            stages: vec![],
+        };
+
+        let mut clock = ChessClock::new(settings);
+        clock.add_action_points(3);
+        assert_eq!(clock.get_remaining_time(0), Some(60));
+
+        clock.add_action_points(2);
+        assert_eq!(clock.total_action_points(), 5);
+        assert_eq!(clock.get_remaining_time(0), Some(75));
+        assert_eq!(clock.get_remaining_time(1), Some(75));
+    }
+
+    #[test]
+    fn test_trigger_fires_once() {
+        let mut initial_times = HashMap::new();
+        initial_times.insert(0, 60);
+
+        let settings = ChessClockSettings {
+            initial_times,
+            move_increments: HashMap::new(),
+            first_move_increment: None,
+            mode: ClockMode::Fischer,
+            triggers: vec![TimeIncrementTrigger {
+                trigger_type: TriggerType::TotalMoves,
+                threshold: 2.0,
+                increment: 30,
+                targets: vec![0],
+            }],
+            stages: vec![],
+        };
+
+        let mut clock = ChessClock::new(settings);
+        clock.end_turn(0);
+        clock.end_turn(1);
+        assert_eq!(clock.get_remaining_time(0), Some(90));
+
+        // Moves past the threshold don't re-apply the increment
+        for mv in 0..6 {
+            clock.end_turn(mv % 2);
+        }
+        assert_eq!(clock.get_remaining_time(0), Some(90));
+
+        // Nor do other events that re-check the triggers
+        clock.add_action_points(10);
+        assert_eq!(clock.get_remaining_time(0), Some(90));
+    }
+
+    #[test]
+    fn test_threshold_trigger_adds_time_once_over_twenty_moves() {
+        let settings = ChessClockSettings {
+            initial_times: HashMap::from([(0, 60), (1, 60)]),
+            move_increments: HashMap::new(),
+            first_move_increment: None,
+            mode: ClockMode::Fischer,
+            triggers: vec![TimeIncrementTrigger {
+                trigger_type: TriggerType::TotalMoves,
+                threshold: 10.0,
+                increment: 30,
+                targets: vec![0, 1],
+            }],
+            stages: vec![],
+        };
+
+        let mut clock = ChessClock::new(settings);
+        for mv in 0..20 {
+            clock.end_turn(mv % 2);
+        }
+        assert_eq!(clock.get_remaining_time(0), Some(90));
+        assert_eq!(clock.get_remaining_time(1), Some(90));
+    }
+
+    #[test]
+    fn test_pending_triggers_drop_once_fired() {
+        let settings = ChessClockSettings {
+            initial_times: HashMap::from([(0, 60), (1, 60)]),
+            move_increments: HashMap::new(),
+            first_move_increment: None,
+            mode: ClockMode::Fischer,
+            triggers: vec![
+                TimeIncrementTrigger {
+                    trigger_type: TriggerType::TotalMoves,
+                    threshold: 2.0,
+                    increment: 60,
+                    targets: vec![0, 1],
+                },
+                TimeIncrementTrigger {
+                    trigger_type: TriggerType::TotalActionPoints,
+                    threshold: 10.0,
+                    increment: 15,
+                    targets: vec![0],
+                },
+            ],
+            stages: vec![],
+        };
+
+        let mut clock = ChessClock::new(settings);
+        clock.end_turn(0);
+        assert_eq!(
+            clock.pending_triggers(),
+            vec![
+                (TriggerType::TotalMoves, 2.0, 60),
+                (TriggerType::TotalActionPoints, 10.0, 15),
+            ]
+        );
+
+        clock.end_turn(1);
+        assert_eq!(
+            clock.pending_triggers(),
+            vec![(TriggerType::TotalActionPoints, 10.0, 15)]
+        );
+    }
+
+    #[test]
+    fn test_settings_serde_round_trip() {
+        let settings = ChessClockSettings {
+            initial_times: HashMap::from([(0, 300), (1, 300)]),
+            move_increments: HashMap::from([(0, 5), (1, 5)]),
+            first_move_increment: Some(0),
+            mode: ClockMode::Fischer,
+            triggers: vec![TimeIncrementTrigger {
+                trigger_type: TriggerType::TotalMoves,
+                threshold: 40.0,
+                increment: 60,
+                targets: vec![0, 1],
+            }],
+            stages: vec![],
+        };
+
+        let json = serde_json::to_string(&settings).unwrap();
+        let restored: ChessClockSettings = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, settings);
+    }
+
+    #[test]
+    fn test_end_turn_charges_time_since_last_tick() {
+        let mut clock = clock_with_mode(ClockMode::Fischer);
+        let start = Instant::now();
+        clock.start_player_clock_at(0, start);
+
+        // One tick, then the move lands 600 ms before the next one
+        assert!(clock.tick_at(start + Duration::from_millis(1000)));
+        clock.end_turn_at(0, start + Duration::from_millis(1400));
+        assert_eq!(clock.get_remaining_time_ms(0), Some(60000 - 1400 + 5000));
+
+        // A move between flat one-second ticks is charged only beyond them
+        let next_turn = start + Duration::from_secs(10);
+        clock.start_player_clock_at(0, next_turn);
+        assert!(clock.tick());
+        clock.end_turn_at(0, next_turn + Duration::from_millis(1250));
+        assert_eq!(clock.get_remaining_time_ms(0), Some(63600 - 1250 + 5000));
+    }
+
+    #[test]
+    fn test_players_swapped_keeps_everything_but_the_seats() {
+        let settings = ChessClockSettings {
+            initial_times: HashMap::from([(0, 300), (1, 180)]),
+            move_increments: HashMap::from([(0, 2), (1, 5)]),
+            first_move_increment: Some(0),
+            mode: ClockMode::Bronstein,
+            triggers: vec![TimeIncrementTrigger {
+                trigger_type: TriggerType::TotalMoves,
+                threshold: 20.0,
+                increment: 60,
+                targets: vec![1],
+            }],
+            stages: vec![TimeStage {
+                moves: 40,
+                seconds: 1800,
+            }],
+        };
+
+        let swapped = settings.with_players_swapped();
+        assert_eq!(swapped.initial_times, HashMap::from([(0, 180), (1, 300)]));
+        assert_eq!(swapped.move_increments, HashMap::from([(0, 5), (1, 2)]));
+        assert_eq!(swapped.triggers[0].targets, vec![0]);
+        assert_eq!(swapped.first_move_increment, Some(0));
+        assert_eq!(swapped.mode, ClockMode::Bronstein);
+        assert_eq!(swapped.stages, settings.stages);
+        assert_eq!(swapped.with_players_swapped(), settings);
+    }
+
+    /// A one-player clock with 60s and a 5s increment in the given mode
+    fn clock_with_mode(mode: ClockMode) -> ChessClock {
+        ChessClock::new(ChessClockSettings {
+            initial_times: HashMap::from([(0, 60)]),
+            move_increments: HashMap::from([(0, 5)]),
+            first_move_increment: None,
+            mode,
+            triggers: vec![],
+            stages: vec![],
+        })
+    }
+
+    /// Play moves taking the given milliseconds each, returning the time left after each
+    fn remaining_after_moves(clock: &mut ChessClock, spent: &[u64]) -> Vec<i64> {
+        let mut now = Instant::now();
+        spent
+            .iter()
+            .map(|&ms| {
+                clock.start_player_clock_at(0, now);
+                now += Duration::from_millis(ms);
+                clock.end_turn_at(0, now);
+                clock.get_remaining_time_ms(0).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_clock_modes_credit_increment_differently() {
+        let spent = [2000, 8000, 5000];
+
+        // Fischer always adds the full increment
+        let mut fischer = clock_with_mode(ClockMode::Fischer);
+        assert_eq!(
+            remaining_after_moves(&mut fischer, &spent),
+            vec![63000, 60000, 60000]
+        );
+
+        // Bronstein gives back what was spent, up to the increment
+        let mut bronstein = clock_with_mode(ClockMode::Bronstein);
+        assert_eq!(
+            remaining_after_moves(&mut bronstein, &spent),
+            vec![60000, 57000, 57000]
+        );
+
+        // Simple delay charges only what was spent beyond the delay
+        let mut delay = clock_with_mode(ClockMode::SimpleDelay);
+        assert_eq!(
+            remaining_after_moves(&mut delay, &spent),
+            vec![60000, 57000, 57000]
+        );
+    }
+
+    #[test]
+    fn test_simple_delay_holds_clock_at_start_of_turn() {
+        let start = Instant::now();
+        let mut bronstein = clock_with_mode(ClockMode::Bronstein);
+        let mut delay = clock_with_mode(ClockMode::SimpleDelay);
+        bronstein.start_player_clock_at(0, start);
+        delay.start_player_clock_at(0, start);
+
+        // Bronstein runs down from the start of the turn; the delay clock waits
+        bronstein.tick_at(start + Duration::from_secs(3));
+        delay.tick_at(start + Duration::from_secs(3));
+        assert_eq!(bronstein.get_remaining_time_ms(0), Some(57000));
+        assert_eq!(delay.get_remaining_time_ms(0), Some(60000));
+
+        // A tick straddling the end of the delay only charges the part after it
+        delay.tick_at(start + Duration::from_secs(7));
+        assert_eq!(delay.get_remaining_time_ms(0), Some(58000));
+
+        // The delay starts over on the next turn
+        delay.end_turn_at(0, start + Duration::from_secs(7));
+        let next_turn = start + Duration::from_secs(10);
+        delay.start_player_clock_at(0, next_turn);
+        delay.tick_at(next_turn + Duration::from_secs(4));
+        assert_eq!(delay.get_remaining_time_ms(0), Some(58000));
+    }
+
+    /// Two players with 90 minutes each and the given stages, no increment
+    fn staged_clock(stages: Vec<TimeStage>) -> ChessClock {
+        ChessClock::new(ChessClockSettings {
+            initial_times: HashMap::from([(0, 5400), (1, 5400)]),
+            move_increments: HashMap::new(),
+            first_move_increment: None,
+            mode: ClockMode::Fischer,
+            triggers: vec![],
+            stages,
+        })
+    }
+
+    #[test]
+    fn test_forty_move_control_grants_each_player_their_stage() {
+        // 40/90 + 30: half an hour more for each player at their 40th move
+        let mut clock = staged_clock(vec![TimeStage {
+            moves: 40,
+            seconds: 1800,
+        }]);
+
+        for _ in 0..39 {
+            clock.end_turn(0);
+            clock.end_turn(1);
+        }
+        assert_eq!(clock.player_moves(0), 39);
+        assert_eq!(clock.get_remaining_time(0), Some(5400));
+
+        // White's 40th move grants White's stage, not Black's
+        clock.end_turn(0);
+        assert_eq!(clock.get_remaining_time(0), Some(7200));
+        assert_eq!(clock.get_remaining_time(1), Some(5400));
+
+        clock.end_turn(1);
+        assert_eq!(clock.get_remaining_time(1), Some(7200));
+
+        // Moves past the control add nothing more
+        for _ in 0..20 {
+            clock.end_turn(0);
+            clock.end_turn(1);
+        }
+        assert_eq!(clock.get_remaining_time(0), Some(7200));
+        assert_eq!(clock.get_remaining_time(1), Some(7200));
+    }
+
+    #[test]
+    fn test_successive_stages_and_flagged_player() {
+        let mut clock = staged_clock(vec![
+            TimeStage {
+                moves: 40,
+                seconds: 3600,
+            },
+            TimeStage {
+                moves: 60,
+                seconds: 900,
+            },
+        ]);
+
+        // Black flags before reaching the control, so the stage can't save them
+        clock.set_remaining_time_ms(1, 0);
+        for _ in 0..60 {
+            clock.end_turn(0);
+            clock.end_turn(1);
+        }
+        assert_eq!(clock.get_remaining_time(0), Some(5400 + 3600 + 900));
+        assert_eq!(clock.get_remaining_time_ms(1), Some(0));
+    }
+
+    #[test]
+    fn test_no_increment_after_flagging() {
+        let mut initial_times = HashMap::new();
+        initial_times.insert(0, 60);
+        initial_times.insert(1, 60);
+
+        let mut increments = HashMap::new();
+        increments.insert(0, 10);
+        increments.insert(1, 10);
+
+        let settings = ChessClockSettings {
+            initial_times,
+            move_increments: increments,
+            first_move_increment: None,
+            mode: ClockMode::Fischer,
+            triggers: vec![],
+            stages: vec![],
+        };
+
+        let mut clock = ChessClock::new(settings);
+        let start = Instant::now();
+        clock.set_remaining_time_ms(0, -500);
+        clock.start_player_clock_at(0, start);
+        clock.end_turn_at(0, start);
+
+        assert_eq!(clock.get_remaining_time_ms(0), Some(-500));
+        assert_eq!(clock.get_player_out_of_time(), Some(0));
+
+        // The opponent, still on time, gets the increment as usual
+        clock.end_turn(1);
+        assert_eq!(clock.get_remaining_time(1), Some(70));
+    }
 }
@@ -1,15 +1,176 @@
-use super::board::{Board, GameStatus};
-use super::chess_clock::ChessClockSettings;
+use super::board::{Board, FenError, GameStatus, HistoryEntry};
+use super::chess_clock::{ChessClockSettings, ClockMode, TriggerType};
+use super::moves::{san_to_move, to_san};
 use super::piece::{Color, Move, PieceType, Position};
-use super::rules::{generate_legal_moves, get_game_status};
+use super::rules::{
+    check_move_legality, generate_all_legal_moves, generate_legal_moves, get_game_status,
+    IllegalReason,
+};
+use crate::ai::evaluation::evaluate;
 use crate::ai::simple_opponent::select_weighted_move;
+use crate::ai::Difficulty;
+use crate::cards::effects::{CardEffect, EffectError};
 use std::collections::HashMap;
+use std::fmt;
+use std::time::Instant;
+
+/// Observer invoked after every applied move with the move and the resulting status
+pub type MoveCallback = Box<dyn FnMut(&Move, GameStatus) + Send + Sync>;
+
+/// Consecutive hopeless AI turns before the AI resigns
+pub const AI_RESIGN_PLIES: u32 = 3;
+
+/// Result of asking the AI to play
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AiMoveOutcome {
+    /// A move was made
+    Moved,
+    /// The AI resigned instead of moving (evaluation stayed below the threshold)
+    Resigned(Color),
+    /// No legal moves available
+    NoLegalMoves,
+}
+
+/// Result of a move applied through the stateless move API
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveOutcome {
+    /// The move as played (promotion resolved, defaulting to a queen)
+    pub mv: Move,
+    /// Game status after the move
+    pub status: GameStatus,
+}
 
 /// Pure Rust game state - no Godot dependencies
-#[derive(Debug)]
 pub struct ChessGame {
     board: Board,
     selected_position: Option<Position>,
+    on_move: Option<MoveCallback>,
+    ai_resign_threshold: Option<i32>, // Centipawns, None = never resign
+    hopeless_plies: [u32; 2],         // Consecutive hopeless AI turns per color (White, Black)
+    ponder_cache: Option<(u64, Move)>, // Precomputed AI move keyed by position hash
+    redo_stack: Vec<Move>,            // Moves taken back with undo_move, most recent last
+}
+
+/// Longest movetext line in exported PGN
+const PGN_LINE_WIDTH: usize = 80;
+
+/// Reasons `ChessGame::from_pgn` can reject a PGN
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PgnError {
+    /// A "{" comment is never closed
+    UnterminatedComment,
+    /// A ")" without its "(", or a variation left open
+    UnbalancedVariation,
+    /// The FEN tag doesn't describe a valid position
+    InvalidFen(FenError),
+    /// A SAN token does not resolve to exactly one legal move
+    InvalidMove {
+        ply: usize, // 1-based
+        san: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnError::UnterminatedComment => write!(f, "Unterminated comment"),
+            PgnError::UnbalancedVariation => write!(f, "Unbalanced variation parentheses"),
+            PgnError::InvalidFen(err) => write!(f, "Invalid FEN tag: {}", err),
+            PgnError::InvalidMove { ply, san, reason } => {
+                write!(f, "Invalid move {} at ply {}: {}", san, ply, reason)
+            }
+        }
+    }
+}
+
+/// Value of a tag pair such as `[FEN "..."]`, if the PGN has it
+fn pgn_tag<'a>(pgn: &'a str, name: &str) -> Option<&'a str> {
+    pgn.lines().find_map(|line| {
+        let pair = line.trim().strip_prefix('[')?.strip_suffix(']')?;
+        let (tag, value) = pair.split_once(char::is_whitespace)?;
+        (tag == name).then(|| value.trim().trim_matches('"'))
+    })
+}
+
+/// SAN tokens of the main line, stopping at the result token
+fn pgn_main_line(pgn: &str) -> Result<Vec<String>, PgnError> {
+    // Tag pairs sit on their own lines before the movetext
+    let movetext: String = pgn
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('['))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut depth = 0usize; // Variation nesting
+    let mut chars = movetext.chars();
+    while let Some(c) = chars.next() {
+        let separator = match c {
+            '{' => {
+                chars
+                    .find(|&c| c == '}')
+                    .ok_or(PgnError::UnterminatedComment)?;
+                true
+            }
+            ';' => {
+                chars.find(|&c| c == '\n');
+                true
+            }
+            '(' => {
+                depth += 1;
+                true
+            }
+            ')' => {
+                depth = depth.checked_sub(1).ok_or(PgnError::UnbalancedVariation)?;
+                true
+            }
+            c if c.is_whitespace() => true,
+            c => {
+                if depth == 0 {
+                    token.push(c);
+                }
+                false
+            }
+        };
+        if separator && !token.is_empty() {
+            tokens.push(std::mem::take(&mut token));
+        }
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    if depth > 0 {
+        return Err(PgnError::UnbalancedVariation);
+    }
+
+    let mut sans = Vec::new();
+    for token in tokens {
+        if matches!(token.as_str(), "1-0" | "0-1" | "1/2-1/2" | "*") {
+            break;
+        }
+        // Move numbers ("12." or "12...") may be glued to the move that follows
+        let san = token.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+        if !san.is_empty() && !san.starts_with('$') {
+            sans.push(san.to_string());
+        }
+    }
+    Ok(sans)
+}
+
+impl fmt::Debug for ChessGame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChessGame")
+            .field("board", &self.board)
+            .field("selected_position", &self.selected_position)
+            .field("on_move", &self.on_move.is_some())
+            .field("ai_resign_threshold", &self.ai_resign_threshold)
+            .field("hopeless_plies", &self.hopeless_plies)
+            .field("ponder_cache", &self.ponder_cache)
+            .field("redo_stack", &self.redo_stack)
+            .finish()
+    }
 }
 
 impl ChessGame {
@@ -18,34 +179,149 @@ impl ChessGame {
         Self {
             board: Board::new(),
             selected_position: None,
+            on_move: None,
+            ai_resign_threshold: None,
+            hopeless_plies: [0; 2],
+            ponder_cache: None,
+            redo_stack: Vec::new(),
         }
     }
+
+    /// Register a callback fired after each successful move (player or AI)
+    /// Replaces any previously registered callback
+    pub fn set_on_move(&mut self, callback: MoveCallback) {
+        self.on_move = Some(callback);
+    }
+
+    /// Remove the move callback
+    pub fn clear_on_move(&mut self) {
+        self.on_move = None;
+    }
+
+    /// Apply a new legal move to the board, clear the selection and notify the move observer
+    fn commit_move(&mut self, mv: Move) {
+        self.selected_position = None;
+        self.redo_stack.clear();
+        self.apply_to_board(mv, None);
+    }
+
+    /// Apply a legal move to the board and notify the move observer
+    /// The clock switches sides at `now`, or at the system time when not given
+    /// Callers playing a new move (rather than a redo) clear the redo history first
+    fn apply_to_board(&mut self, mv: Move, now: Option<Instant>) {
+        match now {
+            Some(now) => self.board.make_move_at(mv, now),
+            None => self.board.make_move(mv),
+        };
+
+        if self.on_move.is_some() {
+            let status = self.get_game_status();
+            if let Some(callback) = self.on_move.as_mut() {
+                callback(&mv, status);
+            }
+        }
+    }
+
+    /// Take back the last move, restoring captures, castling rights, en passant and the clock
+    /// Returns false if no move has been played
+    pub fn undo_move(&mut self) -> bool {
+        // A card teleport can't be replayed, nor can the moves undone before it
+        if self.board.last_entry_is_teleport() {
+            self.redo_stack.clear();
+        } else {
+            let Some(&mv) = self.board.move_history().last() else {
+                return false;
+            };
+            self.redo_stack.push(mv);
+        }
+        self.board.unmake_move();
+        self.selected_position = None;
+        true
+    }
+
+    /// Replay the most recently undone move
+    /// Returns false if there is nothing to redo (a new move discards the redo history)
+    pub fn redo_move(&mut self) -> bool {
+        let Some(mv) = self.redo_stack.pop() else {
+            return false;
+        };
+        // The position may have been edited since the undo
+        if !generate_all_legal_moves(&self.board).contains(&mv) {
+            self.redo_stack.clear();
+            return false;
+        }
+        self.apply_to_board(mv, None);
+        self.selected_position = None;
+        true
+    }
+
+    /// Whether `redo_move` has a move to replay
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
     /// Reset the game to initial position
     pub fn reset_game(&mut self) {
         self.board = Board::new();
         self.selected_position = None;
+        self.hopeless_plies = [0; 2];
+        self.ponder_cache = None;
+        self.redo_stack.clear();
+    }
+
+    /// Reset to the starting position without the given pieces, for teaching odds games
+    /// Each entry removes one piece, queenside first (e.g. `&[PieceType::Rook]` is a1-rook odds)
+    pub fn reset_with_handicap(&mut self, white_remove: &[PieceType], black_remove: &[PieceType]) {
+        self.reset_game();
+        for &piece_type in white_remove {
+            self.board.remove_handicap_piece(Color::White, piece_type);
+        }
+        for &piece_type in black_remove {
+            self.board.remove_handicap_piece(Color::Black, piece_type);
+        }
     }
 
     /// Reset the game with a chess clock
     /// initial_time_seconds: time for each player in seconds
     /// increment_seconds: time added after each move in seconds
     pub fn reset_game_with_clock(&mut self, initial_time_seconds: i32, increment_seconds: i32) {
+        self.reset_game_with_times(
+            initial_time_seconds,
+            initial_time_seconds,
+            increment_seconds,
+        );
+    }
+
+    /// Reset the game with a chess clock that may give each player a different starting time
+    pub fn reset_game_with_times(
+        &mut self,
+        white_time_seconds: i32,
+        black_time_seconds: i32,
+        increment_seconds: i32,
+    ) {
         let mut initial_times = HashMap::new();
-        initial_times.insert(0, initial_time_seconds); // White
-        initial_times.insert(1, initial_time_seconds); // Black
+        initial_times.insert(0, white_time_seconds); // White
+        initial_times.insert(1, black_time_seconds); // Black
 
         let mut increments = HashMap::new();
         increments.insert(0, increment_seconds); // White
         increments.insert(1, increment_seconds); // Black
 
-        let clock_settings = ChessClockSettings {
+        self.reset_game_with_clock_settings(ChessClockSettings {
             initial_times,
             move_increments: increments,
+            first_move_increment: None,
+            mode: ClockMode::Fischer,
             triggers: vec![],
-        };
+            stages: vec![],
+        });
+    }
 
+    /// Reset the game with a chess clock using the given settings
+    pub fn reset_game_with_clock_settings(&mut self, clock_settings: ChessClockSettings) {
         self.board = Board::new_with_clock(Some(clock_settings));
         self.selected_position = None;
+        self.redo_stack.clear();
     }
 
     /// Get the piece at a position (returns symbol as String, empty if no piece)
@@ -90,8 +366,12 @@ impl ChessGame {
     }
 
     /// Get legal moves for the currently selected piece
-    /// Returns a vector of move positions
+    /// Returns a vector of move positions (empty once the game is over)
     pub fn get_legal_moves_for_selected(&self) -> Vec<Position> {
+        if self.is_game_over() {
+            return Vec::new();
+        }
+
         if let Some(from) = self.selected_position {
             let moves = generate_legal_moves(&self.board, from);
             moves.into_iter().map(|mv| mv.to).collect()
@@ -100,6 +380,32 @@ impl ChessGame {
         }
     }
 
+    /// Get the legal moves of the piece on a square, including promotion choices
+    /// Empty if the square is empty, holds a piece of the side not to move, or the game is over
+    pub fn get_legal_moves_from(&self, from: Position) -> Vec<Move> {
+        if self.is_game_over() {
+            return Vec::new();
+        }
+
+        match self.board.get_piece(from) {
+            Some(piece) if piece.color == self.board.current_turn() => {
+                generate_legal_moves(&self.board, from)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Get the legal destinations for every movable piece of the side to move
+    /// Lets clients highlight all movable pieces without querying each square
+    pub fn get_legal_moves_map(&self) -> HashMap<Position, Vec<Position>> {
+        self.board.legal_moves_map()
+    }
+
+    /// Get the squares of this side's attacked and undefended pieces, for danger highlighting
+    pub fn get_threatened_pieces(&self, color: Color) -> Vec<Position> {
+        self.board.threatened_pieces(color)
+    }
+
     /// Check if moving the selected piece to the given position is a promotion
     /// Returns true if the move would be a pawn promotion
     pub fn is_promotion_move(&self, row: i8, col: i8) -> bool {
@@ -119,13 +425,18 @@ impl ChessGame {
     }
 
     /// Try to move the selected piece to the given position with a specific promotion piece
-    /// Returns true if the move was successful, false otherwise
+    /// Returns true if the move was successful, false otherwise (always false once the game is over)
     pub fn try_move_selected_with_promotion(
         &mut self,
         row: i8,
         col: i8,
         promotion_piece: PieceType,
     ) -> bool {
+        if self.is_game_over() {
+            self.selected_position = None;
+            return false;
+        }
+
         let to = Position::new(row, col);
 
         if let Some(from) = self.selected_position {
@@ -140,8 +451,7 @@ impl ChessGame {
                         mv
                     };
 
-                    self.board.make_move(final_move);
-                    self.selected_position = None;
+                    self.commit_move(final_move);
                     return true;
                 }
             }
@@ -151,9 +461,14 @@ impl ChessGame {
     }
 
     /// Try to move the selected piece to the given position
-    /// Returns true if the move was successful, false otherwise
+    /// Returns true if the move was successful, false otherwise (always false once the game is over)
     /// NOTE: This defaults to Queen for promotions - use try_move_selected_with_promotion for other pieces
     pub fn try_move_selected(&mut self, row: i8, col: i8) -> bool {
+        if self.is_game_over() {
+            self.selected_position = None;
+            return false;
+        }
+
         let to = Position::new(row, col);
 
         if let Some(from) = self.selected_position {
@@ -169,8 +484,7 @@ impl ChessGame {
                         mv
                     };
 
-                    self.board.make_move(final_move);
-                    self.selected_position = None;
+                    self.commit_move(final_move);
                     return true;
                 }
             }
@@ -182,6 +496,73 @@ impl ChessGame {
         false
     }
 
+    /// Explain whether a move is legal right now, and if not, why
+    pub fn explain_move(&self, from: Position, to: Position) -> Result<(), IllegalReason> {
+        if self.is_game_over() {
+            return Err(IllegalReason::GameOver);
+        }
+        check_move_legality(&self.board, Move::new(from, to))
+    }
+
+    /// Validate and apply a move in one call, without touching the piece selection
+    /// A missing promotion on a pawn reaching the last rank defaults to a queen
+    pub fn apply_move(
+        &mut self,
+        from: Position,
+        to: Position,
+        promotion: Option<PieceType>,
+    ) -> Result<MoveOutcome, IllegalReason> {
+        self.apply_move_with_clock(from, to, promotion, None)
+    }
+
+    /// Like `apply_move`, with the clock switching sides at `now`
+    pub fn apply_move_at(
+        &mut self,
+        from: Position,
+        to: Position,
+        promotion: Option<PieceType>,
+        now: Instant,
+    ) -> Result<MoveOutcome, IllegalReason> {
+        self.apply_move_with_clock(from, to, promotion, Some(now))
+    }
+
+    fn apply_move_with_clock(
+        &mut self,
+        from: Position,
+        to: Position,
+        promotion: Option<PieceType>,
+        now: Option<Instant>,
+    ) -> Result<MoveOutcome, IllegalReason> {
+        if self.is_game_over() {
+            return Err(IllegalReason::GameOver);
+        }
+
+        let requested = Move {
+            from,
+            to,
+            promotion,
+        };
+        check_move_legality(&self.board, requested)?;
+
+        let promotes = self
+            .board
+            .get_piece(from)
+            .is_some_and(|piece| piece.piece_type == PieceType::Pawn)
+            && (to.row == 0 || to.row == 7);
+        let mv = if promotes {
+            Move::with_promotion(from, to, promotion.unwrap_or(PieceType::Queen))
+        } else {
+            requested
+        };
+        self.redo_stack.clear();
+        self.apply_to_board(mv, now);
+
+        Ok(MoveOutcome {
+            mv,
+            status: self.get_game_status(),
+        })
+    }
+
     /// Deselect the currently selected piece
     pub fn deselect_piece(&mut self) {
         self.selected_position = None;
@@ -194,9 +575,9 @@ impl ChessGame {
 
     /// Get the current game status
     pub fn get_game_status(&self) -> GameStatus {
-        // First check for time loss
-        if let Some(color) = self.board.check_time_loss() {
-            return GameStatus::TimeLoss(color);
+        // First check for time loss (or a draw if both flagged)
+        if let Some(status) = self.board.check_time_outcome() {
+            return status;
         }
 
         // Then check regular game status
@@ -206,7 +587,7 @@ impl ChessGame {
     /// Check if game is over
     pub fn is_game_over(&self) -> bool {
         // Check time loss first
-        if self.board.check_time_loss().is_some() {
+        if self.board.check_time_outcome().is_some() {
             return true;
         }
 
@@ -214,34 +595,248 @@ impl ChessGame {
         !matches!(status, GameStatus::Ongoing | GameStatus::Check)
     }
 
+    /// Rebuild a game from PGN movetext, replaying each SAN move from the start position
+    /// Tags, comments, NAGs and variations are skipped; only the main line is played
+    pub fn from_pgn(pgn: &str) -> Result<ChessGame, PgnError> {
+        let mut game = ChessGame::new();
+        if let Some(fen) = pgn_tag(pgn, "FEN") {
+            game.board = Board::from_fen(fen).map_err(PgnError::InvalidFen)?;
+        }
+        for san in pgn_main_line(pgn)? {
+            let ply = game.board.move_count() + 1;
+            let invalid = |reason: String| PgnError::InvalidMove {
+                ply,
+                san: san.clone(),
+                reason,
+            };
+            let mv = san_to_move(&game.board, &san).map_err(invalid)?;
+            if !game.board.make_move(mv) {
+                return Err(invalid(format!("Move could not be played: {}", san)));
+            }
+        }
+        Ok(game)
+    }
+
+    /// PGN result token for the current status ("*" while the game is still going)
+    pub fn pgn_result(&self) -> &'static str {
+        match self.get_game_status() {
+            GameStatus::Checkmate(Color::White) | GameStatus::KingCaptured(Color::White) => "1-0",
+            GameStatus::Checkmate(Color::Black) | GameStatus::KingCaptured(Color::Black) => "0-1",
+            GameStatus::TimeLoss(Color::White) => "0-1",
+            GameStatus::TimeLoss(Color::Black) => "1-0",
+            GameStatus::Stalemate
+            | GameStatus::DrawInsufficientMaterial
+            | GameStatus::DrawRepetition
+            | GameStatus::DrawFiftyMove
+            | GameStatus::DrawTimeout => "1/2-1/2",
+            GameStatus::Ongoing | GameStatus::Check | GameStatus::Invalid => "*",
+        }
+    }
+
+    /// Export the game as PGN: the seven standard tags, then numbered SAN movetext
+    /// Games from another starting position add the SetUp and FEN tags
+    /// Card plays have no SAN and are written as comments ("{Teleport g8-e4}",
+    /// "{Extra move}"), which `from_pgn` skips, so a game with cards doesn't replay from it
+    pub fn to_pgn(&self) -> String {
+        let start_fen = self.board.start_fen();
+        let result = self.pgn_result();
+        let mut pgn = String::new();
+        for (tag, value) in [
+            ("Event", "Casual Game"),
+            ("Site", "?"),
+            ("Date", "????.??.??"),
+            ("Round", "?"),
+            ("White", "?"),
+            ("Black", "?"),
+            ("Result", result),
+        ] {
+            pgn.push_str(&format!("[{} \"{}\"]\n", tag, value));
+        }
+        if start_fen != Board::new().to_fen() {
+            pgn.push_str(&format!("[SetUp \"1\"]\n[FEN \"{}\"]\n", start_fen));
+        }
+        pgn.push('\n');
+
+        // Black's moves are numbered "N..." unless they directly follow White's, as in
+        // Black-first games
+        let mut tokens = Vec::new();
+        let mut after_white_move = false;
+        self.board.replay_history(|board, entry| match *entry {
+            HistoryEntry::Move(mv) => {
+                let white = board.current_turn() == Color::White;
+                if white {
+                    tokens.push(format!("{}.", board.fullmove_number()));
+                } else if !after_white_move {
+                    tokens.push(format!("{}...", board.fullmove_number()));
+                }
+                tokens.push(to_san(board, mv));
+                after_white_move = white;
+            }
+            HistoryEntry::ExtraMove => {
+                tokens.push("{Extra move}".to_string());
+                after_white_move = false;
+            }
+            HistoryEntry::Teleport { from, to } => {
+                tokens.push(format!(
+                    "{{Teleport {}-{}}}",
+                    from.to_algebraic(),
+                    to.to_algebraic()
+                ));
+                after_white_move = false;
+            }
+        });
+        tokens.push(result.to_string());
+
+        // Export format keeps movetext lines within 80 characters
+        let mut line_len = 0;
+        for token in tokens {
+            if line_len > 0 && line_len + 1 + token.len() > PGN_LINE_WIDTH {
+                pgn.push('\n');
+                line_len = 0;
+            } else if line_len > 0 {
+                pgn.push(' ');
+                line_len += 1;
+            }
+            line_len += token.len();
+            pgn.push_str(&token);
+        }
+        pgn.push('\n');
+        pgn
+    }
+
     /// Tick the chess clock (should be called every second)
     /// Returns false if the active player ran out of time
     pub fn tick_clock(&mut self) -> bool {
         self.board.tick_clock()
     }
 
+    /// Tick the chess clock by the real time elapsed since the last tick
+    /// Returns false if the active player ran out of time
+    pub fn tick_clock_at(&mut self, now: Instant) -> bool {
+        self.board.tick_clock_at(now)
+    }
+
+    /// Restart the running side's clock at `now`, so time before it isn't charged
+    pub fn restart_clock_at(&mut self, now: Instant) {
+        self.board.restart_clock_at(now);
+    }
+
+    /// Get remaining time for a color in milliseconds (None if no clock)
+    pub fn get_remaining_time_ms(&self, color: Color) -> Option<i64> {
+        self.board.get_remaining_time_ms(color)
+    }
+
     /// Get remaining time for a color in seconds (None if no clock)
     pub fn get_remaining_time(&self, color: Color) -> Option<i32> {
         self.board.get_remaining_time(color)
     }
 
+    /// Clock time bonuses still to come, as (type, threshold, increment in seconds)
+    pub fn get_pending_triggers(&self) -> Vec<(TriggerType, f32, i32)> {
+        self.board.pending_clock_triggers()
+    }
+
     /// Check if the game has a chess clock enabled
     pub fn has_clock(&self) -> bool {
         self.board.has_clock()
     }
 
     /// Make an AI move for the current player
-    /// Returns true if a move was made, false if no legal moves available
+    /// Returns true if a move was made, false if no legal moves available or the AI resigned
     pub fn make_ai_move(&mut self) -> bool {
-        if let Some(mv) = select_weighted_move(&self.board) {
-            self.board.make_move(mv);
-            self.selected_position = None;
-            true
+        self.make_ai_move_outcome() == AiMoveOutcome::Moved
+    }
+
+    /// Make an AI move at the given strength, see `make_ai_move`
+    pub fn make_ai_move_with_difficulty(&mut self, difficulty: Difficulty) -> bool {
+        self.make_ai_move_outcome_with_difficulty(difficulty) == AiMoveOutcome::Moved
+    }
+
+    /// Make an AI move for the current player, reporting resignation separately
+    pub fn make_ai_move_outcome(&mut self) -> AiMoveOutcome {
+        self.make_ai_move_outcome_with_difficulty(Difficulty::Random)
+    }
+
+    /// Make an AI move at the given strength, reporting resignation separately
+    pub fn make_ai_move_outcome_with_difficulty(
+        &mut self,
+        difficulty: Difficulty,
+    ) -> AiMoveOutcome {
+        let color = self.board.current_turn();
+
+        if let Some(threshold) = self.ai_resign_threshold {
+            let index = match color {
+                Color::White => 0,
+                Color::Black => 1,
+            };
+            let score = match color {
+                Color::White => evaluate(&self.board),
+                Color::Black => -evaluate(&self.board),
+            };
+            if score < threshold {
+                self.hopeless_plies[index] += 1;
+            } else {
+                self.hopeless_plies[index] = 0;
+            }
+
+            if self.hopeless_plies[index] >= AI_RESIGN_PLIES {
+                return AiMoveOutcome::Resigned(color);
+            }
+        }
+
+        // Pondering precomputes the weighted random opponent's move only
+        let pondered = self
+            .pondered_move()
+            .filter(|_| difficulty == Difficulty::Random);
+        self.ponder_cache = None;
+
+        if let Some(mv) = pondered.or_else(|| difficulty.choose_move(&self.board)) {
+            self.commit_move(mv);
+            AiMoveOutcome::Moved
+        } else {
+            AiMoveOutcome::NoLegalMoves
+        }
+    }
+
+    /// Precompute the AI's move for the current position (e.g. right after the human moves)
+    /// The result is reused by the next AI move if the position hasn't changed
+    pub fn start_ponder(&mut self) {
+        self.ponder_cache =
+            select_weighted_move(&self.board).map(|mv| (self.board.position_hash(), mv));
+    }
+
+    /// Get the pondered move if it is still valid for the current position
+    pub fn pondered_move(&self) -> Option<Move> {
+        let (hash, mv) = self.ponder_cache?;
+        if hash == self.board.position_hash() && generate_all_legal_moves(&self.board).contains(&mv)
+        {
+            Some(mv)
         } else {
-            false
+            None
         }
     }
 
+    /// Let the AI resign once its evaluation stays below `threshold_cp` centipawns
+    /// for AI_RESIGN_PLIES consecutive turns (None disables resignation)
+    pub fn set_ai_resign_threshold(&mut self, threshold_cp: Option<i32>) {
+        self.ai_resign_threshold = threshold_cp;
+        self.hopeless_plies = [0; 2];
+    }
+
+    /// Apply a card played by the side to move
+    /// The selection, pondered AI move and redo history are dropped since the position changed
+    pub fn apply_card_effect(&mut self, effect: &dyn CardEffect) -> Result<(), EffectError> {
+        if self.is_game_over() {
+            return Err(EffectError::GameOver);
+        }
+
+        effect.apply(&mut self.board)?;
+        self.selected_position = None;
+        self.ponder_cache = None;
+        self.redo_stack.clear();
+        Ok(())
+    }
+
     /// Get a reference to the internal board (for server/network use)
     pub fn board(&self) -> &Board {
         &self.board
@@ -290,3 +885,423 @@ impl ChessGame {
         self.board.set_remaining_time(Color::Black, seconds);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::effects::TeleportPiece;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_on_move_callback_fires_after_legal_move() {
+        let mut game = ChessGame::new();
+        let seen: Arc<Mutex<Vec<(Move, GameStatus)>>> = Arc::new(Mutex::new(Vec::new()));
+
+        let sink = Arc::clone(&seen);
+        game.set_on_move(Box::new(move |mv, status| {
+            sink.lock().unwrap().push((*mv, status));
+        }));
+
+        // Illegal move does not fire the callback
+        assert!(game.select_piece(1, 4));
+        assert!(!game.try_move_selected(4, 4));
+        assert!(seen.lock().unwrap().is_empty());
+
+        // e2-e4
+        assert!(game.select_piece(1, 4));
+        assert!(game.try_move_selected(3, 4));
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 1);
+        assert_eq!(
+            seen[0],
+            (
+                Move::new(Position::new(1, 4), Position::new(3, 4)),
+                GameStatus::Ongoing
+            )
+        );
+    }
+
+    #[test]
+    fn test_apply_move_is_stateless() {
+        let mut game = ChessGame::new();
+        let e2 = Position::from_algebraic("e2").unwrap();
+
+        assert_eq!(
+            game.apply_move(e2, Position::from_algebraic("e5").unwrap(), None),
+            Err(IllegalReason::IllegalPieceMovement)
+        );
+        assert_eq!(game.board().move_count(), 0);
+
+        let e4 = Position::from_algebraic("e4").unwrap();
+        let outcome = game.apply_move(e2, e4, None).unwrap();
+        assert_eq!(outcome.mv, Move::new(e2, e4));
+        assert_eq!(outcome.status, GameStatus::Ongoing);
+        assert_eq!(game.board().move_count(), 1);
+        assert_eq!(game.get_selected_position(), None);
+    }
+
+    #[test]
+    fn test_queen_odds_handicap() {
+        let mut game = ChessGame::new();
+        game.reset_with_handicap(&[PieceType::Queen], &[]);
+
+        let has_queen = |color: Color| {
+            game.board()
+                .get_pieces(color)
+                .iter()
+                .any(|(_, piece)| piece.piece_type == PieceType::Queen)
+        };
+        assert!(!has_queen(Color::White));
+        assert!(has_queen(Color::Black));
+        assert_eq!(game.get_game_status(), GameStatus::Ongoing);
+        assert_eq!(game.board().material_points(Color::White), 30);
+
+        // The game plays on normally, and the empty d1 square is usable
+        assert!(play(&mut game, "d2", "d4"));
+        assert!(play(&mut game, "e7", "e5"));
+        assert!(play(&mut game, "e1", "d2"));
+    }
+
+    #[test]
+    fn test_rook_odds_drop_castling_on_that_side() {
+        let mut game = ChessGame::new();
+        game.reset_with_handicap(&[], &[PieceType::Rook]);
+
+        let a8 = Position::from_algebraic("a8").unwrap();
+        assert_eq!(game.board().get_piece(a8), None);
+        assert!(!game.board().castling_rights().black_queenside);
+        assert!(game.board().castling_rights().black_kingside);
+    }
+
+    /// Select and move a piece using algebraic squares
+    fn play(game: &mut ChessGame, from: &str, to: &str) -> bool {
+        let from = Position::from_algebraic(from).unwrap();
+        let to = Position::from_algebraic(to).unwrap();
+        game.select_piece(from.row, from.col) && game.try_move_selected(to.row, to.col)
+    }
+
+    #[test]
+    fn test_no_moves_after_checkmate() {
+        let mut game = ChessGame::new();
+        // Fool's mate
+        assert!(play(&mut game, "f2", "f3"));
+        assert!(play(&mut game, "e7", "e5"));
+        assert!(play(&mut game, "g2", "g4"));
+        assert!(play(&mut game, "d8", "h4"));
+        assert!(game.is_game_over());
+
+        assert!(game.select_piece(1, 0)); // a2 pawn
+        assert!(game.get_legal_moves_for_selected().is_empty());
+        assert!(!game.try_move_selected(2, 0));
+        assert_eq!(game.board().move_count(), 4);
+    }
+
+    #[test]
+    fn test_no_moves_after_time_loss() {
+        let mut game = ChessGame::new();
+        game.reset_game_with_clock(60, 0);
+        game.set_white_time(0);
+
+        // White has moves on the board, but has already lost on time
+        assert!(game.select_piece(1, 4));
+        assert!(game.get_legal_moves_for_selected().is_empty());
+        assert!(!game.try_move_selected(3, 4));
+        assert!(game.select_piece(1, 4));
+        assert!(!game.try_move_selected_with_promotion(3, 4, PieceType::Queen));
+        assert_eq!(game.board().move_count(), 0);
+    }
+
+    #[test]
+    fn test_both_flagged_is_draw() {
+        let mut game = ChessGame::new();
+        game.reset_game_with_clock(60, 0);
+        game.set_white_time(0);
+        game.set_black_time(0);
+
+        for _ in 0..10 {
+            assert_eq!(game.get_game_status(), GameStatus::DrawTimeout);
+        }
+        assert!(game.is_game_over());
+        assert_eq!(game.board().check_time_loss(), None);
+
+        // A single flag is still a loss
+        game.set_black_time(30);
+        assert_eq!(game.get_game_status(), GameStatus::TimeLoss(Color::White));
+    }
+
+    #[test]
+    fn test_pondered_move_is_reused() {
+        // The AI picks randomly, so repeat to rule out a coincidental match
+        for _ in 0..20 {
+            let mut game = ChessGame::new();
+            game.start_ponder();
+            let pondered = game.pondered_move().expect("start position has moves");
+
+            assert!(game.make_ai_move());
+            assert_eq!(game.board().move_history().last(), Some(&pondered));
+            assert_eq!(game.pondered_move(), None);
+        }
+    }
+
+    #[test]
+    fn test_stale_ponder_is_ignored() {
+        let mut game = ChessGame::new();
+        game.start_ponder();
+
+        // The position changes before the AI gets to use the pondered move
+        assert!(game.select_piece(1, 4));
+        assert!(game.try_move_selected(3, 4));
+        assert_eq!(game.pondered_move(), None);
+        assert!(game.make_ai_move());
+    }
+
+    #[test]
+    fn test_ai_resigns_in_lost_position() {
+        let mut game = ChessGame::new();
+        for row in 0..8 {
+            for col in 0..8 {
+                game.clear_square(row, col);
+            }
+        }
+        game.place_piece(0, 0, PieceType::King, Color::White, 12);
+        game.place_piece(7, 7, PieceType::King, Color::Black, 28);
+        game.place_piece(7, 3, PieceType::Queen, Color::Black, 27);
+        game.set_ai_resign_threshold(Some(-500));
+
+        // White plays on until it has been hopeless for AI_RESIGN_PLIES turns
+        for _ in 1..AI_RESIGN_PLIES {
+            game.set_current_turn(Color::White);
+            assert_eq!(game.make_ai_move_outcome(), AiMoveOutcome::Moved);
+        }
+        game.set_current_turn(Color::White);
+        assert_eq!(
+            game.make_ai_move_outcome(),
+            AiMoveOutcome::Resigned(Color::White)
+        );
+    }
+
+    #[test]
+    fn test_ai_plays_on_in_even_position() {
+        let mut game = ChessGame::new();
+        game.set_ai_resign_threshold(Some(-900));
+
+        for _ in 0..(2 * AI_RESIGN_PLIES) {
+            assert_eq!(game.make_ai_move_outcome(), AiMoveOutcome::Moved);
+        }
+    }
+
+    #[test]
+    fn test_to_pgn_fools_mate() {
+        let mut game = ChessGame::new();
+        for (from, to) in [("f2", "f3"), ("e7", "e5"), ("g2", "g4"), ("d8", "h4")] {
+            assert!(play(&mut game, from, to));
+        }
+
+        let pgn = game.to_pgn();
+        assert!(pgn.starts_with("[Event \"Casual Game\"]\n[Site \"?\"]\n"));
+        assert!(pgn.contains("[Result \"0-1\"]\n\n"));
+        assert!(pgn.ends_with("\n\n1. f3 e5 2. g4 Qh4# 0-1\n"));
+    }
+
+    #[test]
+    fn test_to_pgn_unfinished_game_and_line_width() {
+        let mut game = ChessGame::new();
+        assert!(game.to_pgn().ends_with("[Result \"*\"]\n\n*\n"));
+
+        // Sixteen plies of knight shuffling wrap, and end in a fivefold repetition
+        let shuffle = [("g1", "f3"), ("g8", "f6"), ("f3", "g1"), ("f6", "g8")];
+        for _ in 0..4 {
+            for (from, to) in shuffle {
+                assert!(play(&mut game, from, to));
+            }
+        }
+        assert_eq!(game.pgn_result(), "1/2-1/2");
+
+        let pgn = game.to_pgn();
+        let movetext: Vec<&str> = pgn.split("\n\n").nth(1).unwrap().lines().collect();
+        assert!(movetext.len() > 1);
+        assert!(movetext.iter().all(|line| line.len() <= 80));
+        assert!(movetext[0].starts_with("1. Nf3 Nf6 2. Ng1 Ng8 3. Nf3"));
+        assert!(movetext.last().unwrap().ends_with("Ng8 1/2-1/2"));
+    }
+
+    #[test]
+    fn test_to_pgn_records_setup_position_and_black_first_numbering() {
+        let mut game = ChessGame::new();
+        game.reset_with_handicap(&[PieceType::Queen], &[]);
+        game.set_current_turn(Color::Black);
+        for (from, to) in [("e7", "e5"), ("e2", "e4"), ("g8", "f6")] {
+            assert!(play(&mut game, from, to));
+        }
+
+        let pgn = game.to_pgn();
+        assert!(pgn.contains(
+            "[SetUp \"1\"]\n[FEN \"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNB1KBNR b KQkq - 0 1\"]\n"
+        ));
+        assert!(pgn.ends_with("\n\n1... e5 2. e4 Nf6 *\n"));
+        assert_eq!(game.board().san_history(), ["e5", "e4", "Nf6"]);
+    }
+
+    #[test]
+    fn test_to_pgn_writes_card_plays_as_comments() {
+        let mut game = ChessGame::new();
+        assert!(play(&mut game, "e2", "e4"));
+        let teleport = TeleportPiece {
+            from: Position::from_algebraic("g8").unwrap(),
+            to: Position::from_algebraic("f6").unwrap(),
+        };
+        game.apply_card_effect(&teleport).unwrap();
+        assert!(play(&mut game, "e7", "e5"));
+
+        assert_eq!(game.board().move_count(), 2);
+        assert!(game
+            .to_pgn()
+            .ends_with("\n\n1. e4 {Teleport g8-f6} 1... e5 *\n"));
+    }
+
+    #[test]
+    fn test_from_pgn_round_trips_to_pgn() {
+        let mut game = ChessGame::new();
+        let moves = [
+            ("e2", "e4"),
+            ("d7", "d5"),
+            ("e4", "d5"),
+            ("g8", "f6"),
+            ("g1", "f3"),
+            ("f6", "d5"),
+            ("f1", "c4"),
+            ("b8", "c6"),
+            ("e1", "g1"),
+        ];
+        for (from, to) in moves {
+            assert!(play(&mut game, from, to));
+        }
+
+        let pgn = game.to_pgn();
+        let loaded = ChessGame::from_pgn(&pgn).unwrap();
+        assert_eq!(loaded.board().move_history(), game.board().move_history());
+        assert!(loaded.board().position_equals(game.board()));
+        assert_eq!(loaded.to_pgn(), pgn);
+    }
+
+    #[test]
+    fn test_from_pgn_starts_from_fen_tag() {
+        let mut game = ChessGame::new();
+        game.reset_with_handicap(&[], &[PieceType::Rook]);
+        game.set_current_turn(Color::Black);
+        for (from, to) in [("d7", "d5"), ("d2", "d4"), ("c8", "f5")] {
+            assert!(play(&mut game, from, to));
+        }
+
+        let pgn = game.to_pgn();
+        let loaded = ChessGame::from_pgn(&pgn).unwrap();
+        assert!(loaded.board().position_equals(game.board()));
+        assert_eq!(loaded.board().start_fen(), game.board().start_fen());
+        assert_eq!(loaded.to_pgn(), pgn);
+
+        assert_eq!(
+            ChessGame::from_pgn("[FEN \"8/8/8 w - - 0 1\"]\n\n*").unwrap_err(),
+            PgnError::InvalidFen(FenError::RankCount(3))
+        );
+    }
+
+    #[test]
+    fn test_from_pgn_skips_annotations_and_reports_bad_moves() {
+        let pgn = "[Event \"Lesson\"]\n\n1.e4 {King's pawn} e5 $1 (1... c5 2. Nf3) 2. Nf3 ; develop\n2... Nc6 *";
+        let game = ChessGame::from_pgn(pgn).unwrap();
+        assert_eq!(game.board().san_history(), ["e4", "e5", "Nf3", "Nc6"]);
+
+        assert_eq!(
+            ChessGame::from_pgn("1. e4 { never closed").unwrap_err(),
+            PgnError::UnterminatedComment
+        );
+        assert_eq!(
+            ChessGame::from_pgn("1. e4 (1. d4 e5").unwrap_err(),
+            PgnError::UnbalancedVariation
+        );
+        assert_eq!(
+            ChessGame::from_pgn("1. e4 e5 2. Ke3").unwrap_err(),
+            PgnError::InvalidMove {
+                ply: 3,
+                san: "Ke3".to_string(),
+                reason: "Illegal move: Ke3".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_undo_restores_captures_castling_and_en_passant() {
+        let mut game = ChessGame::new();
+        assert!(!game.undo_move());
+
+        for (from, to) in [("e2", "e4"), ("d7", "d5"), ("e4", "e5"), ("f7", "f5")] {
+            assert!(play(&mut game, from, to));
+        }
+        let before = game.board().clone();
+        let f6 = Position::from_algebraic("f6").unwrap();
+        assert_eq!(game.board().en_passant_target(), Some(f6));
+
+        // En passant capture, then the king steps off e1 and loses both castling rights
+        assert!(play(&mut game, "e5", "f6"));
+        assert!(play(&mut game, "g8", "f6"));
+        assert!(play(&mut game, "e1", "e2"));
+        assert!(!game.board().castling_rights().white_kingside);
+
+        for _ in 0..3 {
+            assert!(game.undo_move());
+        }
+        assert!(game.board().position_equals(&before));
+        assert_eq!(game.board().en_passant_target(), Some(f6));
+        assert!(game.board().castling_rights().white_kingside);
+        assert_eq!(game.board().move_count(), 4);
+    }
+
+    #[test]
+    fn test_redo_replays_until_a_new_move() {
+        let mut game = ChessGame::new();
+        game.reset_game_with_clock(300, 5);
+        assert!(play(&mut game, "e2", "e4"));
+        assert!(play(&mut game, "e7", "e5"));
+        let after = game.board().clone();
+
+        assert!(game.undo_move());
+        assert!(game.undo_move());
+        assert_eq!(game.get_remaining_time(Color::White), Some(300));
+        assert_eq!(game.get_current_turn(), Color::White);
+
+        // Redone moves are reported like any other
+        let redone = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&redone);
+        game.set_on_move(Box::new(move |mv, _| sink.lock().unwrap().push(*mv)));
+
+        assert!(game.redo_move());
+        assert!(game.redo_move());
+        assert!(!game.redo_move());
+        assert!(game.board().position_equals(&after));
+        assert_eq!(
+            *redone.lock().unwrap(),
+            game.board().move_history().to_vec()
+        );
+        game.clear_on_move();
+
+        // A fresh move after an undo discards what could have been redone
+        assert!(game.undo_move());
+        assert!(game.can_redo());
+        assert!(play(&mut game, "c7", "c5"));
+        assert!(!game.can_redo());
+        assert!(!game.redo_move());
+    }
+
+    #[test]
+    fn test_ai_difficulty_finds_mate() {
+        let mut game = ChessGame::new();
+        for (from, to) in [("f2", "f3"), ("e7", "e5"), ("g2", "g4")] {
+            assert!(play(&mut game, from, to));
+        }
+
+        assert!(game.make_ai_move_with_difficulty(Difficulty::Hard));
+        assert_eq!(game.get_game_status(), GameStatus::Checkmate(Color::Black));
+        assert!(!game.make_ai_move_with_difficulty(Difficulty::Easy));
+    }
+}
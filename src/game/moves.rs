@@ -1,5 +1,6 @@
 use super::board::Board;
 use super::piece::{Color, Move, PieceType, Position};
+use super::rules::{generate_all_legal_moves, is_in_check};
 
 /// Generates all pseudo-legal moves for a piece at the given position
 /// Pseudo-legal means the moves follow piece movement rules but may leave the king in check
@@ -62,7 +63,8 @@ fn generate_pawn_moves(board: &Board, from: Position, color: Color) -> Vec<Move>
             target.color != color
         } else {
             // En passant
-            Some(capture_pos) == board.en_passant_target()
+            board.rules_config().en_passant_enabled
+                && Some(capture_pos) == board.en_passant_target()
         };
 
         if can_capture {
@@ -205,7 +207,7 @@ fn generate_king_moves(board: &Board, from: Position, color: Color) -> Vec<Move>
     let rights = board.castling_rights();
     let back_row = if color == Color::White { 0 } else { 7 };
 
-    if from.row == back_row && from.col == 4 {
+    if board.rules_config().castling_enabled && from.row == back_row && from.col == 4 {
         // Kingside castling
         let can_castle_kingside = match color {
             Color::White => rights.white_kingside,
@@ -244,9 +246,190 @@ fn generate_king_moves(board: &Board, from: Position, color: Color) -> Vec<Move>
     moves
 }
 
+/// Resolve a SAN move (e.g. "Nf3", "exd5", "O-O", "e8=Q+") against the current position
+/// Returns an error if the SAN is malformed, illegal here, or ambiguous
+pub fn san_to_move(board: &Board, san: &str) -> Result<Move, String> {
+    let color = board.current_turn();
+    let trimmed = san.trim().trim_end_matches(['+', '#', '!', '?']);
+
+    // Castling is written from the king's point of view
+    let back_row = if color == Color::White { 0 } else { 7 };
+    let castle_target = match trimmed {
+        "O-O" | "0-0" => Some(6),
+        "O-O-O" | "0-0-0" => Some(2),
+        _ => None,
+    };
+    if let Some(target_col) = castle_target {
+        let mv = Move::new(
+            Position::new(back_row, 4),
+            Position::new(back_row, target_col),
+        );
+        let is_king = board
+            .get_piece(mv.from)
+            .is_some_and(|p| p.piece_type == PieceType::King);
+        return if is_king && generate_all_legal_moves(board).contains(&mv) {
+            Ok(mv)
+        } else {
+            Err(format!("Illegal move: {}", san))
+        };
+    }
+
+    let invalid = || format!("Invalid SAN: {}", san);
+
+    // Split off the promotion piece ("e8=Q" or "e8Q")
+    let (body, promotion) = match trimmed.char_indices().last() {
+        Some((idx, c)) if "QRBN".contains(c) && idx > 0 => {
+            let body = trimmed[..idx].trim_end_matches('=');
+            (body, Some(piece_type_from_san(c).ok_or_else(invalid)?))
+        }
+        _ => (trimmed, None),
+    };
+
+    // Leading piece letter, pawn if absent
+    let (piece_type, body) = match body.chars().next() {
+        Some(c) if c.is_ascii_uppercase() => {
+            (piece_type_from_san(c).ok_or_else(invalid)?, &body[1..])
+        }
+        _ => (PieceType::Pawn, body),
+    };
+
+    let body: String = body.chars().filter(|&c| c != 'x').collect();
+    if !body.is_ascii() || body.len() < 2 || body.len() > 4 {
+        return Err(invalid());
+    }
+    let (disambiguation, destination) = body.split_at(body.len() - 2);
+    let to = Position::from_algebraic(destination).ok_or_else(invalid)?;
+
+    // Disambiguation is a source file, a source rank, or both
+    let mut from_col = None;
+    let mut from_row = None;
+    for c in disambiguation.chars() {
+        match c {
+            'a'..='h' => from_col = Some(c as i8 - 'a' as i8),
+            '1'..='8' => from_row = Some(c as i8 - '1' as i8),
+            _ => return Err(invalid()),
+        }
+    }
+
+    let candidates: Vec<Move> = generate_all_legal_moves(board)
+        .into_iter()
+        .filter(|mv| {
+            mv.to == to
+                && mv.promotion == promotion
+                && from_col.is_none_or(|col| mv.from.col == col)
+                && from_row.is_none_or(|row| mv.from.row == row)
+                && board
+                    .get_piece(mv.from)
+                    .is_some_and(|p| p.piece_type == piece_type)
+        })
+        .collect();
+
+    match candidates.as_slice() {
+        [mv] => Ok(*mv),
+        [] => Err(format!("Illegal move: {}", san)),
+        _ => Err(format!("Ambiguous move: {}", san)),
+    }
+}
+
+/// Write a legal move in SAN (e.g. "Nf3", "exd5", "O-O", "e8=Q+") for the current position
+pub fn to_san(board: &Board, mv: Move) -> String {
+    let Some(piece) = board.get_piece(mv.from) else {
+        return mv.to_uci();
+    };
+
+    let is_castling = piece.piece_type == PieceType::King && (mv.to.col - mv.from.col).abs() == 2;
+    let mut san = if is_castling && mv.to.col > mv.from.col {
+        "O-O".to_string()
+    } else if is_castling {
+        "O-O-O".to_string()
+    } else {
+        let is_capture = board.get_piece(mv.to).is_some()
+            || (piece.piece_type == PieceType::Pawn && mv.from.col != mv.to.col);
+        let mut san = String::new();
+
+        if piece.piece_type == PieceType::Pawn {
+            if is_capture {
+                san.push((b'a' + mv.from.col as u8) as char);
+            }
+        } else {
+            san.push(san_letter(piece.piece_type));
+
+            // Disambiguate against other pieces of the same type reaching the same square
+            let rivals: Vec<Position> = generate_all_legal_moves(board)
+                .into_iter()
+                .filter(|other| {
+                    other.to == mv.to
+                        && other.from != mv.from
+                        && board
+                            .get_piece(other.from)
+                            .is_some_and(|p| p.piece_type == piece.piece_type)
+                })
+                .map(|other| other.from)
+                .collect();
+            if !rivals.is_empty() {
+                let square = mv.from.to_algebraic();
+                if rivals.iter().all(|r| r.col != mv.from.col) {
+                    san.push_str(&square[..1]);
+                } else if rivals.iter().all(|r| r.row != mv.from.row) {
+                    san.push_str(&square[1..]);
+                } else {
+                    san.push_str(&square);
+                }
+            }
+        }
+
+        if is_capture {
+            san.push('x');
+        }
+        san.push_str(&mv.to.to_algebraic());
+        if let Some(promotion) = mv.promotion {
+            san.push('=');
+            san.push(san_letter(promotion));
+        }
+        san
+    };
+
+    // Check and mate suffixes depend on the position after the move
+    let after = board.make_move_copy(mv);
+    if is_in_check(&after, after.current_turn()) {
+        san.push(if generate_all_legal_moves(&after).is_empty() {
+            '#'
+        } else {
+            '+'
+        });
+    }
+
+    san
+}
+
+/// SAN letter for a non-pawn piece type
+fn san_letter(piece_type: PieceType) -> char {
+    match piece_type {
+        PieceType::King => 'K',
+        PieceType::Queen => 'Q',
+        PieceType::Rook => 'R',
+        PieceType::Bishop => 'B',
+        PieceType::Knight => 'N',
+        PieceType::Pawn => 'P',
+    }
+}
+
+/// Map a SAN piece letter to its piece type
+fn piece_type_from_san(c: char) -> Option<PieceType> {
+    match c {
+        'K' => Some(PieceType::King),
+        'Q' => Some(PieceType::Queen),
+        'R' => Some(PieceType::Rook),
+        'B' => Some(PieceType::Bishop),
+        'N' => Some(PieceType::Knight),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::game::board::{BoardBuilder, RulesConfig};
 
     #[test]
     fn test_pawn_initial_moves() {
@@ -255,10 +438,219 @@ mod tests {
         assert_eq!(moves.len(), 2); // Can move 1 or 2 squares forward
     }
 
+    fn sq(square: &str) -> Position {
+        Position::from_algebraic(square).unwrap()
+    }
+
+    /// Destination squares of a piece's pseudo-legal moves, sorted
+    fn destinations(board: &Board, from: &str) -> Vec<String> {
+        let mut squares: Vec<String> = generate_pseudo_legal_moves(board, sq(from))
+            .iter()
+            .map(|mv| mv.to.to_algebraic())
+            .collect();
+        squares.sort();
+        squares
+    }
+
+    #[test]
+    fn test_pawn_captures_do_not_wrap_around_board_edges() {
+        let board = BoardBuilder::empty()
+            .piece("e1", PieceType::King, Color::White)
+            .piece("a4", PieceType::Pawn, Color::White)
+            .piece("h4", PieceType::Pawn, Color::White)
+            .piece("e8", PieceType::King, Color::Black)
+            .piece("b5", PieceType::Knight, Color::Black)
+            .piece("g5", PieceType::Knight, Color::Black)
+            .piece("a6", PieceType::Pawn, Color::Black)
+            .piece("h6", PieceType::Pawn, Color::Black)
+            .build()
+            .unwrap();
+
+        // A wrapped capture from a4 would land on h5, from h4 on a5
+        assert_eq!(destinations(&board, "a4"), ["a5", "b5"]);
+        assert_eq!(destinations(&board, "h4"), ["g5", "h5"]);
+        // Black pawns on the edges, capturing towards rank 1
+        assert_eq!(destinations(&board, "a6"), ["a5"]);
+        assert_eq!(destinations(&board, "h6"), ["h5"]);
+    }
+
+    #[test]
+    fn test_pawn_double_push_only_from_start_rank() {
+        let board = BoardBuilder::empty()
+            .piece("e1", PieceType::King, Color::White)
+            .piece("a3", PieceType::Pawn, Color::White)
+            .piece("b2", PieceType::Pawn, Color::White)
+            .piece("c2", PieceType::Pawn, Color::White)
+            .piece("c4", PieceType::Knight, Color::White)
+            .piece("d2", PieceType::Pawn, Color::White)
+            .piece("d3", PieceType::Knight, Color::White)
+            .piece("e8", PieceType::King, Color::Black)
+            .piece("a6", PieceType::Pawn, Color::Black)
+            .piece("b7", PieceType::Pawn, Color::Black)
+            .build()
+            .unwrap();
+
+        assert_eq!(destinations(&board, "a3"), ["a4"]);
+        assert_eq!(destinations(&board, "b2"), ["b3", "b4"]);
+        // Blocked on the double-push square, or in front
+        assert_eq!(destinations(&board, "c2"), ["c3"]);
+        assert!(destinations(&board, "d2").is_empty());
+
+        assert_eq!(destinations(&board, "a6"), ["a5"]);
+        assert_eq!(destinations(&board, "b7"), ["b5", "b6"]);
+    }
+
     #[test]
     fn test_knight_moves() {
         let board = Board::new();
         let moves = generate_pseudo_legal_moves(&board, Position::new(0, 1));
         assert_eq!(moves.len(), 2); // Knight on b1 can move to a3 or c3
     }
+
+    #[test]
+    fn test_san_to_move_piece_and_pawn() {
+        let board = Board::new();
+        assert_eq!(
+            san_to_move(&board, "Nf3"),
+            Ok(Move::new(Position::new(0, 6), Position::new(2, 5)))
+        );
+        assert_eq!(
+            san_to_move(&board, "e4"),
+            Ok(Move::new(Position::new(1, 4), Position::new(3, 4)))
+        );
+        assert!(san_to_move(&board, "Nf4").is_err());
+        assert!(san_to_move(&board, "Zz9").is_err());
+    }
+
+    #[test]
+    fn test_san_to_move_ambiguity() {
+        let mut board = Board::new();
+        // Move the g1 knight to f3 and clear d2 so both knights can reach d2
+        let knight = board.get_piece(Position::new(0, 6));
+        board.set_piece(Position::new(0, 6), None);
+        board.set_piece(Position::new(2, 5), knight);
+        board.set_piece(Position::new(1, 3), None);
+
+        assert_eq!(
+            san_to_move(&board, "Nd2"),
+            Err("Ambiguous move: Nd2".to_string())
+        );
+        assert_eq!(
+            san_to_move(&board, "Nbd2"),
+            Ok(Move::new(Position::new(0, 1), Position::new(1, 3)))
+        );
+    }
+
+    #[test]
+    fn test_to_san_round_trips() {
+        let mut board = Board::new();
+        // Same knight setup as the ambiguity test, plus a cleared f1-g1 for castling
+        let knight = board.get_piece(Position::new(0, 6));
+        board.set_piece(Position::new(0, 6), None);
+        board.set_piece(Position::new(0, 5), None);
+        board.set_piece(Position::new(2, 5), knight);
+        board.set_piece(Position::new(1, 3), None);
+
+        let san = |from: Position, to: Position| to_san(&board, Move::new(from, to));
+        assert_eq!(san(Position::new(0, 1), Position::new(1, 3)), "Nbd2");
+        assert_eq!(san(Position::new(0, 4), Position::new(0, 6)), "O-O");
+
+        for mv in generate_all_legal_moves(&board) {
+            assert_eq!(san_to_move(&board, &to_san(&board, mv)), Ok(mv));
+        }
+    }
+
+    #[test]
+    fn test_to_san_disambiguates_knights() {
+        // Knights on g1 and g5 share a file, so the rank tells them apart
+        let board = Board::from_fen("4k3/8/8/6N1/8/8/8/4K1N1 w - - 0 1").unwrap();
+        assert_eq!(to_san(&board, Move::new(sq("g1"), sq("f3"))), "N1f3");
+        assert_eq!(to_san(&board, Move::new(sq("g5"), sq("f3"))), "N5f3");
+        assert_eq!(to_san(&board, Move::new(sq("g5"), sq("e6"))), "Ne6");
+
+        // Knights on b1 and f1 share a rank, so the file tells them apart
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1").unwrap();
+        assert_eq!(to_san(&board, Move::new(sq("b1"), sq("d2"))), "Nbd2");
+        assert_eq!(to_san(&board, Move::new(sq("f1"), sq("d2"))), "Nfd2");
+    }
+
+    #[test]
+    fn test_to_san_pawn_captures_and_promotion() {
+        let mut board = Board::new();
+        for (from, to) in [("e2", "e4"), ("d7", "d5")] {
+            assert!(board.make_move(Move::new(sq(from), sq(to))));
+        }
+        assert_eq!(to_san(&board, Move::new(sq("e4"), sq("d5"))), "exd5");
+
+        // En passant lands on an empty square but is still a capture
+        for (from, to) in [("e4", "e5"), ("f7", "f5")] {
+            assert!(board.make_move(Move::new(sq(from), sq(to))));
+        }
+        assert_eq!(to_san(&board, Move::new(sq("e5"), sq("f6"))), "exf6");
+
+        let board = Board::from_fen("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let promote = |to: &str, piece_type| {
+            to_san(&board, Move::with_promotion(sq("a7"), sq(to), piece_type))
+        };
+        assert_eq!(promote("a8", PieceType::Queen), "a8=Q");
+        assert_eq!(promote("b8", PieceType::Knight), "axb8=N");
+        assert_eq!(promote("b8", PieceType::Rook), "axb8=R+");
+    }
+
+    #[test]
+    fn test_to_san_check_and_checkmate() {
+        let mut board = Board::new();
+        for (from, to) in [("f2", "f3"), ("e7", "e5"), ("g2", "g4")] {
+            assert!(board.make_move(Move::new(sq(from), sq(to))));
+        }
+        assert_eq!(to_san(&board, Move::new(sq("d8"), sq("h4"))), "Qh4#");
+
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        assert_eq!(to_san(&board, Move::new(sq("a1"), sq("a8"))), "Ra8+");
+        assert_eq!(to_san(&board, Move::new(sq("a1"), sq("a7"))), "Ra7");
+    }
+
+    #[test]
+    fn test_castling_disabled() {
+        let rules = RulesConfig {
+            castling_enabled: false,
+            ..RulesConfig::standard()
+        };
+        let mut board = Board::new_with_rules(rules);
+        board.set_piece(Position::new(0, 5), None);
+        board.set_piece(Position::new(0, 6), None);
+        assert!(board.castling_rights().white_kingside);
+
+        let king = Position::new(0, 4);
+        let moves = generate_pseudo_legal_moves(&board, king);
+        assert!(!moves.contains(&Move::new(king, Position::new(0, 6))));
+        assert!(moves.contains(&Move::new(king, Position::new(0, 5))));
+    }
+
+    #[test]
+    fn test_en_passant_disabled() {
+        let play = |board: &mut Board| {
+            for (from, to) in [("e2", "e4"), ("a7", "a6"), ("e4", "e5"), ("d7", "d5")] {
+                let mv = Move::new(
+                    Position::from_algebraic(from).unwrap(),
+                    Position::from_algebraic(to).unwrap(),
+                );
+                assert!(board.make_move(mv));
+            }
+        };
+        let pawn = Position::new(4, 4);
+        let en_passant = Move::new(pawn, Position::new(5, 3));
+
+        let mut standard = Board::new();
+        play(&mut standard);
+        assert!(generate_pseudo_legal_moves(&standard, pawn).contains(&en_passant));
+
+        let rules = RulesConfig {
+            en_passant_enabled: false,
+            ..RulesConfig::standard()
+        };
+        let mut board = Board::new_with_rules(rules);
+        play(&mut board);
+        assert!(!generate_pseudo_legal_moves(&board, pawn).contains(&en_passant));
+    }
 }
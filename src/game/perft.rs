@@ -0,0 +1,146 @@
+// Perft: counts the leaf nodes of the legal move tree to a fixed depth
+// Checked against published tables it validates move generation, and it doubles as a benchmark
+use super::board::Board;
+use super::piece::{Move, PieceType};
+use super::rules::{generate_all_legal_moves, is_in_check};
+
+/// Perft node counts with the standard breakdown of the moves reaching the leaves
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PerftCounts {
+    pub nodes: u64,
+    pub captures: u64, // En passant included
+    pub en_passant: u64,
+    pub castles: u64,
+    pub promotions: u64,
+    pub checks: u64,
+    pub checkmates: u64,
+}
+
+impl PerftCounts {
+    fn add(&mut self, other: PerftCounts) {
+        self.nodes += other.nodes;
+        self.captures += other.captures;
+        self.en_passant += other.en_passant;
+        self.castles += other.castles;
+        self.promotions += other.promotions;
+        self.checks += other.checks;
+        self.checkmates += other.checkmates;
+    }
+}
+
+/// Count the positions reachable in exactly `depth` legal half-moves
+pub fn perft(board: &mut Board, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = generate_all_legal_moves(board);
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    let mut nodes = 0;
+    for mv in moves {
+        board.make_move(mv);
+        nodes += perft(board, depth - 1);
+        board.unmake_move();
+    }
+    nodes
+}
+
+/// Perft with the capture/en passant/castle/promotion/check/checkmate breakdown
+pub fn perft_detailed(board: &mut Board, depth: u32) -> PerftCounts {
+    let mut counts = PerftCounts::default();
+    if depth == 0 {
+        counts.nodes = 1;
+        return counts;
+    }
+
+    for mv in generate_all_legal_moves(board) {
+        if depth == 1 {
+            counts.add(classify_leaf(board, mv));
+        } else {
+            board.make_move(mv);
+            counts.add(perft_detailed(board, depth - 1));
+            board.unmake_move();
+        }
+    }
+    counts
+}
+
+/// Node count below each legal root move, for tracking down generation bugs
+pub fn perft_divide(board: &mut Board, depth: u32) -> Vec<(Move, u64)> {
+    let mut divide = Vec::new();
+    for mv in generate_all_legal_moves(board) {
+        board.make_move(mv);
+        divide.push((mv, perft(board, depth.saturating_sub(1))));
+        board.unmake_move();
+    }
+    divide
+}
+
+/// The breakdown contributed by a single move into a leaf
+fn classify_leaf(board: &mut Board, mv: Move) -> PerftCounts {
+    let piece = board
+        .get_piece(mv.from)
+        .expect("legal move has a piece to move");
+    let is_en_passant = piece.piece_type == PieceType::Pawn
+        && mv.from.col != mv.to.col
+        && board.get_piece(mv.to).is_none();
+    let is_capture = board.get_piece(mv.to).is_some() || is_en_passant;
+    let is_castle = piece.piece_type == PieceType::King && (mv.to.col - mv.from.col).abs() == 2;
+
+    board.make_move(mv);
+    let opponent = board.current_turn();
+    let is_check = is_in_check(board, opponent);
+    let is_checkmate = is_check && generate_all_legal_moves(board).is_empty();
+    board.unmake_move();
+
+    PerftCounts {
+        nodes: 1,
+        captures: u64::from(is_capture),
+        en_passant: u64::from(is_en_passant),
+        castles: u64::from(is_castle),
+        promotions: u64::from(mv.promotion.is_some()),
+        checks: u64::from(is_check),
+        checkmates: u64::from(is_checkmate),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_perft_initial_position() {
+        let mut board = Board::new();
+        assert_eq!(perft(&mut board, 1), 20);
+        assert_eq!(perft(&mut board, 2), 400);
+
+        let divide = perft_divide(&mut board, 2);
+        assert_eq!(divide.len(), 20);
+        assert!(divide.iter().all(|&(_, nodes)| nodes == 20));
+    }
+
+    #[test]
+    fn test_perft_detailed_depth_3_matches_published_counts() {
+        let mut board = Board::new();
+        let counts = perft_detailed(&mut board, 3);
+
+        assert_eq!(
+            counts,
+            PerftCounts {
+                nodes: 8902,
+                captures: 34,
+                en_passant: 0,
+                castles: 0,
+                promotions: 0,
+                checks: 12,
+                checkmates: 0,
+            }
+        );
+        // The walk leaves the board as it found it
+        assert!(board.position_equals(&Board::new()));
+        assert_eq!(board.move_count(), 0);
+    }
+}
@@ -55,14 +55,33 @@ pub enum CastleSide {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum PieceType {
+    #[serde(alias = "pawn")]
     Pawn,
+    #[serde(alias = "knight")]
     Knight,
+    #[serde(alias = "bishop")]
     Bishop,
+    #[serde(alias = "rook")]
     Rook,
+    #[serde(alias = "queen")]
     Queen,
+    #[serde(alias = "king")]
     King,
 }
 
+impl PieceType {
+    /// Standard material points (pawn 1, minor 3, rook 5, queen 9, king 0)
+    pub fn material_points(&self) -> i32 {
+        match self {
+            PieceType::Pawn => 1,
+            PieceType::Knight | PieceType::Bishop => 3,
+            PieceType::Rook => 5,
+            PieceType::Queen => 9,
+            PieceType::King => 0,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Color {
     White,
@@ -105,6 +124,41 @@ impl Piece {
             PieceType::Pawn => '♟',
         }
     }
+
+    /// Parse a FEN letter (uppercase for White, lowercase for Black) into a piece with this ID
+    pub fn from_fen_char(c: char, id: u8) -> Option<Self> {
+        let piece_type = match c.to_ascii_lowercase() {
+            'k' => PieceType::King,
+            'q' => PieceType::Queen,
+            'r' => PieceType::Rook,
+            'b' => PieceType::Bishop,
+            'n' => PieceType::Knight,
+            'p' => PieceType::Pawn,
+            _ => return None,
+        };
+        let color = if c.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        Some(Self::new(piece_type, color, id))
+    }
+
+    /// Returns the FEN letter for this piece (uppercase for White, lowercase for Black)
+    pub fn to_fen_char(&self) -> char {
+        let c = match self.piece_type {
+            PieceType::King => 'k',
+            PieceType::Queen => 'q',
+            PieceType::Rook => 'r',
+            PieceType::Bishop => 'b',
+            PieceType::Knight => 'n',
+            PieceType::Pawn => 'p',
+        };
+        match self.color {
+            Color::White => c.to_ascii_uppercase(),
+            Color::Black => c,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -168,4 +222,83 @@ impl Move {
             promotion: Some(promotion),
         }
     }
+    /// Format the move in UCI notation (e.g. "e2e4", "e7e8q")
+    pub fn to_uci(&self) -> String {
+        let promotion = match self.promotion {
+            Some(PieceType::Queen) => "q",
+            Some(PieceType::Rook) => "r",
+            Some(PieceType::Bishop) => "b",
+            Some(PieceType::Knight) => "n",
+            _ => "",
+        };
+        format!(
+            "{}{}{}",
+            self.from.to_algebraic(),
+            self.to.to_algebraic(),
+            promotion
+        )
+    }
+
+    /// Parse a move in UCI notation (e.g. "e2e4", "e7e8q")
+    /// Returns None for malformed strings; legality is not checked
+    pub fn from_uci(uci: &str) -> Option<Self> {
+        if !uci.is_ascii() || (uci.len() != 4 && uci.len() != 5) {
+            return None;
+        }
+
+        let from = Position::from_algebraic(&uci[0..2])?;
+        let to = Position::from_algebraic(&uci[2..4])?;
+        let promotion = match &uci[4..] {
+            "" => None,
+            "q" => Some(PieceType::Queen),
+            "r" => Some(PieceType::Rook),
+            "b" => Some(PieceType::Bishop),
+            "n" => Some(PieceType::Knight),
+            _ => return None,
+        };
+
+        Some(Self {
+            from,
+            to,
+            promotion,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uci_round_trip_normal_move() {
+        let mv = Move::new(Position::new(1, 4), Position::new(3, 4));
+        assert_eq!(mv.to_uci(), "e2e4");
+        assert_eq!(Move::from_uci("e2e4"), Some(mv));
+    }
+
+    #[test]
+    fn test_uci_round_trip_promotions() {
+        let from = Position::new(6, 4);
+        let to = Position::new(7, 4);
+        for (suffix, piece_type) in [
+            ("q", PieceType::Queen),
+            ("r", PieceType::Rook),
+            ("b", PieceType::Bishop),
+            ("n", PieceType::Knight),
+        ] {
+            let mv = Move::with_promotion(from, to, piece_type);
+            let uci = format!("e7e8{}", suffix);
+            assert_eq!(mv.to_uci(), uci);
+            assert_eq!(Move::from_uci(&uci), Some(mv));
+        }
+    }
+
+    #[test]
+    fn test_uci_rejects_malformed() {
+        for bad in [
+            "", "e2", "e2e", "e2e9", "i2e4", "e2e4k", "e2e4qq", "e7e8Q", "é2e4",
+        ] {
+            assert_eq!(Move::from_uci(bad), None, "{:?} should be rejected", bad);
+        }
+    }
 }
@@ -2,6 +2,74 @@ use super::board::{Board, GameStatus};
 use super::moves::generate_pseudo_legal_moves;
 use super::piece::{Color, Move, PieceType, Position};
 use std::collections::HashMap;
+use std::fmt;
+
+/// Why a move was rejected, so clients can show a helpful message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IllegalReason {
+    /// The game has already ended
+    GameOver,
+    /// A square is off the board
+    InvalidSquare,
+    /// There is no piece on the source square
+    NoPieceAtSource,
+    /// The piece belongs to the side not on move
+    NotYourTurn,
+    /// The piece can't move that way (or the path is blocked)
+    IllegalPieceMovement,
+    /// Promotion on a non-pawn move or away from the last rank
+    InvalidPromotion,
+    /// Castling rights are gone, or the king is in or passes through check
+    CastlingNotAllowed,
+    /// The move would leave the mover's king attacked
+    LeavesKingInCheck,
+}
+
+impl IllegalReason {
+    /// Stable numeric code for the FFI (0 is reserved for "no error")
+    pub fn code(&self) -> u8 {
+        match self {
+            IllegalReason::GameOver => 1,
+            IllegalReason::InvalidSquare => 2,
+            IllegalReason::NoPieceAtSource => 3,
+            IllegalReason::NotYourTurn => 4,
+            IllegalReason::IllegalPieceMovement => 5,
+            IllegalReason::InvalidPromotion => 6,
+            IllegalReason::CastlingNotAllowed => 7,
+            IllegalReason::LeavesKingInCheck => 8,
+        }
+    }
+
+    /// Variant name, used as the machine-readable reason in JSON
+    pub fn name(&self) -> &'static str {
+        match self {
+            IllegalReason::GameOver => "GameOver",
+            IllegalReason::InvalidSquare => "InvalidSquare",
+            IllegalReason::NoPieceAtSource => "NoPieceAtSource",
+            IllegalReason::NotYourTurn => "NotYourTurn",
+            IllegalReason::IllegalPieceMovement => "IllegalPieceMovement",
+            IllegalReason::InvalidPromotion => "InvalidPromotion",
+            IllegalReason::CastlingNotAllowed => "CastlingNotAllowed",
+            IllegalReason::LeavesKingInCheck => "LeavesKingInCheck",
+        }
+    }
+}
+
+impl fmt::Display for IllegalReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            IllegalReason::GameOver => "The game is over",
+            IllegalReason::InvalidSquare => "Square is off the board",
+            IllegalReason::NoPieceAtSource => "No piece on that square",
+            IllegalReason::NotYourTurn => "Not your turn",
+            IllegalReason::IllegalPieceMovement => "That piece can't move there",
+            IllegalReason::InvalidPromotion => "Invalid promotion",
+            IllegalReason::CastlingNotAllowed => "Castling is not allowed",
+            IllegalReason::LeavesKingInCheck => "Move would leave the king in check",
+        };
+        write!(f, "{}", message)
+    }
+}
 
 /// Check if a square is under attack by the given color
 pub fn is_square_attacked(board: &Board, square: Position, by_color: Color) -> bool {
@@ -42,19 +110,49 @@ pub fn is_in_check(board: &Board, color: Color) -> bool {
     }
 }
 
+/// Find the squares of all pieces of the given color attacking a square
+pub fn attackers_of(board: &Board, square: Position, by_color: Color) -> Vec<Position> {
+    let mut attackers = Vec::new();
+    for row in 0..8 {
+        for col in 0..8 {
+            let from = Position::new(row, col);
+            if let Some(piece) = board.get_piece(from) {
+                if piece.color == by_color {
+                    // Pawns only attack diagonally, so skip their straight pushes
+                    let attacks = generate_pseudo_legal_moves(board, from)
+                        .into_iter()
+                        .any(|mv| {
+                            mv.to == square
+                                && (piece.piece_type != PieceType::Pawn || mv.from.col != mv.to.col)
+                        });
+                    if attacks {
+                        attackers.push(from);
+                    }
+                }
+            }
+        }
+    }
+    attackers
+}
+
+/// Find the enemy pieces giving check to the king of the given color
+pub fn checkers(board: &Board, color: Color) -> Vec<Position> {
+    match board.find_king(color) {
+        Some(king_pos) => attackers_of(board, king_pos, color.opposite()),
+        None => Vec::new(),
+    }
+}
+
 /// Check if a move is legal (doesn't leave the king in check)
-pub fn is_move_legal(board: &Board, mv: Move) -> bool {
+/// The move is tried in place and taken back, so `board` ends up as it was; callers that
+/// must not touch their board pass a scratch copy, cloned once for all their candidates
+pub fn is_move_legal(board: &mut Board, mv: Move) -> bool {
     let piece = match board.get_piece(mv.from) {
         Some(p) => p,
         None => return false,
     };
 
-    // Make the move on a copy of the board
-    let new_board = board.make_move_copy(mv);
-
-    // Check if the moving player's king is in check after the move
-    // Note: make_move_copy switches the turn, so we need to check the opposite color
-    !is_in_check(&new_board, piece.color)
+    !board.is_check_after(mv, piece.color)
 }
 
 /// Special validation for castling moves
@@ -95,10 +193,51 @@ pub fn is_castling_legal(board: &Board, mv: Move) -> bool {
     true
 }
 
+/// Explain whether a move is legal, and if not, why
+/// A missing promotion on a pawn reaching the last rank is accepted (callers default to a queen)
+pub fn check_move_legality(board: &Board, mv: Move) -> Result<(), IllegalReason> {
+    if !mv.from.is_valid() || !mv.to.is_valid() {
+        return Err(IllegalReason::InvalidSquare);
+    }
+
+    let piece = board
+        .get_piece(mv.from)
+        .ok_or(IllegalReason::NoPieceAtSource)?;
+    if piece.color != board.current_turn() {
+        return Err(IllegalReason::NotYourTurn);
+    }
+
+    if mv.promotion.is_some() {
+        let promotion_row = if piece.color == Color::White { 7 } else { 0 };
+        if piece.piece_type != PieceType::Pawn || mv.to.row != promotion_row {
+            return Err(IllegalReason::InvalidPromotion);
+        }
+    }
+
+    let candidate = generate_pseudo_legal_moves(board, mv.from)
+        .into_iter()
+        .find(|m| m.to == mv.to && (mv.promotion.is_none() || m.promotion == mv.promotion))
+        .ok_or(IllegalReason::IllegalPieceMovement)?;
+
+    if !is_castling_legal(board, candidate) {
+        return Err(IllegalReason::CastlingNotAllowed);
+    }
+    if !is_move_legal(&mut board.clone(), candidate) {
+        return Err(IllegalReason::LeavesKingInCheck);
+    }
+
+    Ok(())
+}
+
 /// Generate all legal moves for a piece
 pub fn generate_legal_moves(board: &Board, from: Position) -> Vec<Move> {
+    if board.get_piece(from).is_none() {
+        return Vec::new();
+    }
     let pseudo_legal_moves = generate_pseudo_legal_moves(board, from);
 
+    // One scratch board for all candidates: each is made and taken back in place
+    let mut scratch = board.clone();
     pseudo_legal_moves
         .into_iter()
         .filter(|&mv| {
@@ -107,7 +246,7 @@ pub fn generate_legal_moves(board: &Board, from: Position) -> Vec<Move> {
                 return false;
             }
             // Then check if the move leaves the king in check
-            is_move_legal(board, mv)
+            is_move_legal(&mut scratch, mv)
         })
         .collect()
 }
@@ -126,6 +265,270 @@ pub fn generate_all_legal_moves(board: &Board) -> Vec<Move> {
     all_moves
 }
 
+/// Generate all legal moves for the current player in one pass, grouped by moving piece
+/// Enemy attacks, checks and pins are worked out once up front, and every piece's
+/// pseudo-legal moves are filtered against them instead of being tried on the board
+/// Gives the same moves as calling `generate_legal_moves` for each piece
+pub fn generate_legal_moves_by_piece(board: &Board) -> Vec<(Position, Vec<Move>)> {
+    let color = board.current_turn();
+    let pieces = board.get_pieces(color);
+    let Some(safety) = KingSafety::compute(board, color) else {
+        // Without a king nothing can be left in check
+        return pieces
+            .into_iter()
+            .map(|(pos, _)| {
+                let moves = generate_pseudo_legal_moves(board, pos)
+                    .into_iter()
+                    .filter(|&mv| is_castling_legal(board, mv))
+                    .collect();
+                (pos, moves)
+            })
+            .collect();
+    };
+
+    // En passant can expose the king along the rank of both pawns, which pins don't see,
+    // so those rare moves are still tried on a scratch board
+    let mut scratch: Option<Board> = None;
+    pieces
+        .into_iter()
+        .map(|(pos, piece)| {
+            let moves = generate_pseudo_legal_moves(board, pos)
+                .into_iter()
+                .filter(|&mv| {
+                    let is_en_passant = piece.piece_type == PieceType::Pawn
+                        && mv.from.col != mv.to.col
+                        && board.get_piece(mv.to).is_none();
+                    if is_en_passant {
+                        let scratch = scratch.get_or_insert_with(|| board.clone());
+                        return is_move_legal(scratch, mv);
+                    }
+                    safety.allows(piece.piece_type, mv)
+                })
+                .collect();
+            (pos, moves)
+        })
+        .collect()
+}
+
+/// What the enemy does to one side's king, computed once per position
+struct KingSafety {
+    /// Squares the enemy attacks, found with the king lifted off the board so that it
+    /// can't step back along a slider's line
+    attacked: [[bool; 8]; 8],
+    /// Enemy pieces giving check
+    checkers: Vec<Position>,
+    /// Where a piece other than the king may go to answer a single check: onto the
+    /// checker, or between it and the king
+    evasions: Vec<Position>,
+    /// Pinned pieces, with the squares they may still move to along the pin
+    pins: HashMap<Position, Vec<Position>>,
+}
+
+const ORTHOGONALS: [(i8, i8); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+const DIAGONALS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+impl KingSafety {
+    /// None if the side has no king on the board
+    fn compute(board: &Board, color: Color) -> Option<Self> {
+        let king = board.find_king(color)?;
+        let enemy = color.opposite();
+
+        let mut attacked = [[false; 8]; 8];
+        let mut checkers = Vec::new();
+        for (from, piece) in board.get_pieces(enemy) {
+            let targets = attacked_squares(board, from, piece.piece_type, enemy, king);
+            if targets.contains(&king) {
+                checkers.push(from);
+            }
+            for to in targets {
+                attacked[to.row as usize][to.col as usize] = true;
+            }
+        }
+
+        let mut evasions = Vec::new();
+        if let [checker] = checkers[..] {
+            evasions.push(checker);
+            if is_slider(board, checker) {
+                evasions.extend(squares_between(king, checker));
+            }
+        }
+
+        let mut pins = HashMap::new();
+        for (directions, pinner_type) in [
+            (ORTHOGONALS, PieceType::Rook),
+            (DIAGONALS, PieceType::Bishop),
+        ] {
+            for (row_step, col_step) in directions {
+                let mut line = Vec::new();
+                let mut pinned = None;
+                let mut square = Position::new(king.row + row_step, king.col + col_step);
+                while square.is_valid() {
+                    line.push(square);
+                    if let Some(piece) = board.get_piece(square) {
+                        match pinned {
+                            None if piece.color == color => pinned = Some(square),
+                            Some(pinned_pos)
+                                if piece.color == enemy
+                                    && (piece.piece_type == pinner_type
+                                        || piece.piece_type == PieceType::Queen) =>
+                            {
+                                pins.insert(pinned_pos, line);
+                                break;
+                            }
+                            _ => break,
+                        }
+                    }
+                    square = Position::new(square.row + row_step, square.col + col_step);
+                }
+            }
+        }
+
+        Some(Self {
+            attacked,
+            checkers,
+            evasions,
+            pins,
+        })
+    }
+
+    fn is_attacked(&self, square: Position) -> bool {
+        self.attacked[square.row as usize][square.col as usize]
+    }
+
+    /// Whether a pseudo-legal move (other than en passant) keeps the king safe
+    fn allows(&self, piece_type: PieceType, mv: Move) -> bool {
+        if piece_type == PieceType::King {
+            if (mv.to.col - mv.from.col).abs() == 2 {
+                // Castling: not out of, through or into check
+                let passed = Position::new(mv.from.row, (mv.from.col + mv.to.col) / 2);
+                return self.checkers.is_empty()
+                    && !self.is_attacked(passed)
+                    && !self.is_attacked(mv.to);
+            }
+            return !self.is_attacked(mv.to);
+        }
+
+        // Only the king can answer a double check
+        if self.checkers.len() > 1 {
+            return false;
+        }
+        if !self.checkers.is_empty() && !self.evasions.contains(&mv.to) {
+            return false;
+        }
+        self.pins
+            .get(&mv.from)
+            .is_none_or(|line| line.contains(&mv.to))
+    }
+}
+
+/// Squares a piece attacks, treating `transparent` as empty
+fn attacked_squares(
+    board: &Board,
+    from: Position,
+    piece_type: PieceType,
+    color: Color,
+    transparent: Position,
+) -> Vec<Position> {
+    let steps = |offsets: &[(i8, i8)]| -> Vec<Position> {
+        offsets
+            .iter()
+            .map(|&(row, col)| Position::new(from.row + row, from.col + col))
+            .filter(Position::is_valid)
+            .collect()
+    };
+    let slides = |directions: &[(i8, i8)]| -> Vec<Position> {
+        let mut squares = Vec::new();
+        for &(row_step, col_step) in directions {
+            let mut square = Position::new(from.row + row_step, from.col + col_step);
+            while square.is_valid() {
+                squares.push(square);
+                if square != transparent && board.get_piece(square).is_some() {
+                    break;
+                }
+                square = Position::new(square.row + row_step, square.col + col_step);
+            }
+        }
+        squares
+    };
+
+    match piece_type {
+        PieceType::Pawn => {
+            let direction = if color == Color::White { 1 } else { -1 };
+            steps(&[(direction, -1), (direction, 1)])
+        }
+        PieceType::Knight => steps(&[
+            (2, 1),
+            (2, -1),
+            (-2, 1),
+            (-2, -1),
+            (1, 2),
+            (1, -2),
+            (-1, 2),
+            (-1, -2),
+        ]),
+        PieceType::Bishop => slides(&DIAGONALS),
+        PieceType::Rook => slides(&ORTHOGONALS),
+        PieceType::Queen => {
+            let mut squares = slides(&ORTHOGONALS);
+            squares.extend(slides(&DIAGONALS));
+            squares
+        }
+        PieceType::King => {
+            let mut offsets = ORTHOGONALS.to_vec();
+            offsets.extend(DIAGONALS);
+            steps(&offsets)
+        }
+    }
+}
+
+fn is_slider(board: &Board, square: Position) -> bool {
+    board.get_piece(square).is_some_and(|piece| {
+        matches!(
+            piece.piece_type,
+            PieceType::Bishop | PieceType::Rook | PieceType::Queen
+        )
+    })
+}
+
+/// Squares strictly between two squares on the same line (empty if not on a line)
+fn squares_between(a: Position, b: Position) -> Vec<Position> {
+    let (row_diff, col_diff) = (b.row - a.row, b.col - a.col);
+    if row_diff != 0 && col_diff != 0 && row_diff.abs() != col_diff.abs() {
+        return Vec::new();
+    }
+    let (row_step, col_step) = (row_diff.signum(), col_diff.signum());
+    let mut squares = Vec::new();
+    let mut square = Position::new(a.row + row_step, a.col + col_step);
+    while square != b {
+        squares.push(square);
+        square = Position::new(square.row + row_step, square.col + col_step);
+    }
+    squares
+}
+
+/// Generate all pseudo-legal moves for the current player (fast path for search)
+/// Castling rules are still checked, but moves may leave the mover's king attacked:
+/// callers make the move and reject it if `is_in_check(after, mover)`
+pub fn generate_pseudo_legal_all(board: &Board) -> Vec<Move> {
+    board
+        .get_pieces(board.current_turn())
+        .into_iter()
+        .flat_map(|(pos, _)| generate_pseudo_legal_moves(board, pos))
+        .filter(|&mv| is_castling_legal(board, mv))
+        .collect()
+}
+
+/// Generate all legal moves for the current player that give check to the opponent
+/// Used by puzzle modes ("find all checking moves")
+pub fn generate_checking_moves(board: &Board) -> Vec<Move> {
+    let opponent = board.current_turn().opposite();
+
+    generate_all_legal_moves(board)
+        .into_iter()
+        .filter(|&mv| is_in_check(&board.make_move_copy(mv), opponent))
+        .collect()
+}
+
 /// Check for insufficient material draw conditions
 pub fn has_insufficient_material(board: &Board) -> bool {
     let mut piece_counts: HashMap<(Color, PieceType), u32> = HashMap::new();
@@ -206,6 +609,14 @@ pub fn has_insufficient_material(board: &Board) -> bool {
 
 /// Determine the current game status
 pub fn get_game_status(board: &Board) -> GameStatus {
+    // A missing king (possible with custom positions or card effects) decides the game outright
+    match (board.find_king(Color::White), board.find_king(Color::Black)) {
+        (Some(_), None) => return GameStatus::KingCaptured(Color::White),
+        (None, Some(_)) => return GameStatus::KingCaptured(Color::Black),
+        (None, None) => return GameStatus::Invalid,
+        (Some(_), Some(_)) => {}
+    }
+
     let current_color = board.current_turn();
     let legal_moves = generate_all_legal_moves(board);
     let in_check = is_in_check(board, current_color);
@@ -225,6 +636,16 @@ pub fn get_game_status(board: &Board) -> GameStatus {
         return GameStatus::DrawInsufficientMaterial;
     }
 
+    // Fivefold repetition ends the game automatically
+    if board.repetition_count() >= 5 {
+        return GameStatus::DrawRepetition;
+    }
+
+    // Fifty moves by each side without a capture or pawn move
+    if board.halfmove_clock() >= 100 {
+        return GameStatus::DrawFiftyMove;
+    }
+
     // Check if in check (but not checkmate)
     if in_check {
         return GameStatus::Check;
@@ -236,6 +657,7 @@ pub fn get_game_status(board: &Board) -> GameStatus {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::game::piece::Piece;
 
     #[test]
     fn test_initial_position_not_in_check() {
@@ -244,6 +666,68 @@ mod tests {
         assert!(!is_in_check(&board, Color::Black));
     }
 
+    /// Create a board with no pieces on it
+    fn empty_board() -> Board {
+        let mut board = Board::new();
+        for row in 0..8 {
+            for col in 0..8 {
+                board.set_piece(Position::new(row, col), None);
+            }
+        }
+        board
+    }
+
+    /// Place a piece on the given algebraic square
+    fn put(board: &mut Board, square: &str, piece_type: PieceType, color: Color, id: u8) {
+        let pos = Position::from_algebraic(square).unwrap();
+        board.set_piece(pos, Some(Piece::new(piece_type, color, id)));
+    }
+
+    #[test]
+    fn test_generate_checking_moves() {
+        let mut board = empty_board();
+        put(&mut board, "a1", PieceType::King, Color::White, 12);
+        put(&mut board, "d1", PieceType::Rook, Color::White, 8);
+        put(&mut board, "e8", PieceType::King, Color::Black, 28);
+
+        // Only Re1 and Rd8 give check
+        let checking_moves = generate_checking_moves(&board);
+        assert_eq!(checking_moves.len(), 2);
+        assert!(checking_moves.contains(&Move::new(Position::new(0, 3), Position::new(0, 4))));
+        assert!(checking_moves.contains(&Move::new(Position::new(0, 3), Position::new(7, 3))));
+
+        // Quiet moves are still legal but not included
+        assert!(generate_all_legal_moves(&board).len() > checking_moves.len());
+    }
+
+    #[test]
+    fn test_checkmate_reports_winner_not_side_to_move() {
+        let mut board = empty_board();
+        put(&mut board, "g1", PieceType::King, Color::White, 12);
+        put(&mut board, "a8", PieceType::Rook, Color::White, 8);
+        put(&mut board, "h8", PieceType::King, Color::Black, 28);
+        put(&mut board, "g7", PieceType::Pawn, Color::Black, 22);
+        put(&mut board, "h7", PieceType::Pawn, Color::Black, 23);
+        board.set_current_turn(Color::Black);
+
+        // Black is mated on the back rank, so White is the winner
+        assert_eq!(get_game_status(&board), GameStatus::Checkmate(Color::White));
+    }
+
+    #[test]
+    fn test_checkers_reports_rook_square() {
+        let mut board = empty_board();
+        put(&mut board, "e1", PieceType::King, Color::White, 12);
+        put(&mut board, "a8", PieceType::King, Color::Black, 28);
+        put(&mut board, "e7", PieceType::Rook, Color::Black, 24);
+
+        assert_eq!(
+            checkers(&board, Color::White),
+            vec![Position::from_algebraic("e7").unwrap()]
+        );
+        assert!(checkers(&board, Color::Black).is_empty());
+    }
+
     #[test]
     fn test_insufficient_material_king_vs_king() {
         let mut board = Board::new();
@@ -260,4 +744,142 @@ mod tests {
         }
         assert!(has_insufficient_material(&board));
     }
+
+    #[test]
+    fn test_missing_king_is_not_stalemate() {
+        let mut board = empty_board();
+        board.set_piece(
+            Position::new(0, 4),
+            Some(Piece::new(PieceType::King, Color::White, 0)),
+        );
+        board.set_piece(
+            Position::new(0, 0),
+            Some(Piece::new(PieceType::Rook, Color::White, 1)),
+        );
+        board.set_current_turn(Color::Black);
+
+        // Black has no king and no moves, but that is a captured king, not a stalemate
+        assert_eq!(
+            get_game_status(&board),
+            GameStatus::KingCaptured(Color::White)
+        );
+
+        board.set_piece(Position::new(0, 4), None);
+        assert_eq!(get_game_status(&board), GameStatus::Invalid);
+    }
+
+    #[test]
+    fn test_fivefold_repetition_is_draw() {
+        let mut board = Board::new();
+        let shuffle = [
+            Move::new(Position::new(0, 6), Position::new(2, 5)),
+            Move::new(Position::new(7, 6), Position::new(5, 5)),
+            Move::new(Position::new(2, 5), Position::new(0, 6)),
+            Move::new(Position::new(5, 5), Position::new(7, 6)),
+        ];
+
+        for _ in 0..3 {
+            for mv in shuffle {
+                board.make_move(mv);
+            }
+        }
+        assert_eq!(get_game_status(&board), GameStatus::Ongoing);
+
+        for mv in shuffle {
+            board.make_move(mv);
+        }
+        assert_eq!(get_game_status(&board), GameStatus::DrawRepetition);
+    }
+
+    #[test]
+    fn test_fifty_move_rule_is_draw() {
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 96 80").unwrap();
+        let quiet_moves = [
+            Move::new(Position::new(0, 0), Position::new(1, 0)),
+            Move::new(Position::new(7, 4), Position::new(6, 4)),
+            Move::new(Position::new(1, 0), Position::new(2, 0)),
+        ];
+
+        for mv in quiet_moves {
+            board.make_move(mv);
+            assert_eq!(get_game_status(&board), GameStatus::Ongoing);
+        }
+        assert_eq!(board.halfmove_clock(), 99);
+
+        board.make_move(Move::new(Position::new(6, 4), Position::new(7, 4)));
+        assert_eq!(board.halfmove_clock(), 100);
+        assert_eq!(get_game_status(&board), GameStatus::DrawFiftyMove);
+    }
+
+    #[test]
+    fn test_pseudo_legal_all_filtered_matches_legal() {
+        let mut board = Board::new();
+        // 1. e4 d5 2. e5 f5 (exf6 en passant available) 3. Qh5+
+        let moves = [
+            ("e2", "e4"),
+            ("d7", "d5"),
+            ("e4", "e5"),
+            ("f7", "f5"),
+            ("d1", "h5"),
+        ];
+
+        for (from, to) in moves {
+            let color = board.current_turn();
+            let survivors: Vec<Move> = generate_pseudo_legal_all(&board)
+                .into_iter()
+                .filter(|&mv| !is_in_check(&board.make_move_copy(mv), color))
+                .collect();
+            let legal = generate_all_legal_moves(&board);
+
+            assert_eq!(survivors.len(), legal.len());
+            assert!(survivors.iter().all(|mv| legal.contains(mv)));
+
+            let mv = Move::new(
+                Position::from_algebraic(from).unwrap(),
+                Position::from_algebraic(to).unwrap(),
+            );
+            assert!(board.make_move(mv));
+        }
+
+        // Black is in check from h5: only a few pseudo-legal moves survive
+        let survivors = generate_pseudo_legal_all(&board)
+            .into_iter()
+            .filter(|&mv| !is_in_check(&board.make_move_copy(mv), Color::Black))
+            .count();
+        assert_eq!(survivors, generate_all_legal_moves(&board).len());
+        assert!(survivors < generate_pseudo_legal_all(&board).len());
+    }
+
+    #[test]
+    fn test_check_move_legality_reasons() {
+        let mut board = Board::new();
+        // 1. d4 e6 2. c4 Bb4+ 3. Nc3 e5: the c3 knight is pinned to the king
+        for (from, to) in [
+            ("d2", "d4"),
+            ("e7", "e6"),
+            ("c2", "c4"),
+            ("f8", "b4"),
+            ("b1", "c3"),
+            ("e6", "e5"),
+        ] {
+            let mv = Move::new(
+                Position::from_algebraic(from).unwrap(),
+                Position::from_algebraic(to).unwrap(),
+            );
+            assert!(board.make_move(mv));
+        }
+
+        let check = |from: &str, to: &str| {
+            let mv = Move::new(
+                Position::from_algebraic(from).unwrap(),
+                Position::from_algebraic(to).unwrap(),
+            );
+            check_move_legality(&board, mv)
+        };
+        assert_eq!(check("c3", "d5"), Err(IllegalReason::LeavesKingInCheck));
+        assert_eq!(check("g1", "f3"), Ok(()));
+        assert_eq!(check("e3", "e4"), Err(IllegalReason::NoPieceAtSource));
+        assert_eq!(check("e5", "e4"), Err(IllegalReason::NotYourTurn));
+        assert_eq!(check("a1", "a3"), Err(IllegalReason::IllegalPieceMovement));
+    }
 }
@@ -0,0 +1,136 @@
+// Zobrist hashing for position identity (repetition detection)
+// Keys are generated at compile time so hashes are stable across runs and processes
+use super::board::Board;
+use super::piece::{Color, PieceType, Position};
+
+const PIECE_KEYS: [[u64; 64]; 12] = piece_keys();
+const CASTLING_KEYS: [u64; 4] = [key(768), key(769), key(770), key(771)];
+const EN_PASSANT_KEYS: [u64; 8] = en_passant_keys();
+const BLACK_TO_MOVE_KEY: u64 = key(780);
+
+/// SplitMix64 mixing step, used to derive each key from its index
+const fn key(index: u64) -> u64 {
+    let mut z = (index + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+const fn piece_keys() -> [[u64; 64]; 12] {
+    let mut keys = [[0; 64]; 12];
+    let mut piece = 0;
+    while piece < 12 {
+        let mut square = 0;
+        while square < 64 {
+            keys[piece][square] = key((piece * 64 + square) as u64);
+            square += 1;
+        }
+        piece += 1;
+    }
+    keys
+}
+
+const fn en_passant_keys() -> [u64; 8] {
+    let mut keys = [0; 8];
+    let mut file = 0;
+    while file < 8 {
+        keys[file] = key(772 + file as u64);
+        file += 1;
+    }
+    keys
+}
+
+fn piece_index(piece_type: PieceType, color: Color) -> usize {
+    let type_index = match piece_type {
+        PieceType::Pawn => 0,
+        PieceType::Knight => 1,
+        PieceType::Bishop => 2,
+        PieceType::Rook => 3,
+        PieceType::Queen => 4,
+        PieceType::King => 5,
+    };
+    match color {
+        Color::White => type_index,
+        Color::Black => type_index + 6,
+    }
+}
+
+/// Compute the Zobrist hash of a position
+/// Covers piece placement, side to move, castling rights and a capturable en passant square;
+/// piece IDs and move counters are ignored so identical positions hash identically
+pub fn hash_position(board: &Board) -> u64 {
+    let mut hash = 0;
+
+    for row in 0..8 {
+        for col in 0..8 {
+            if let Some(piece) = board.get_piece(Position::new(row, col)) {
+                let square = (row * 8 + col) as usize;
+                hash ^= PIECE_KEYS[piece_index(piece.piece_type, piece.color)][square];
+            }
+        }
+    }
+
+    if board.current_turn() == Color::Black {
+        hash ^= BLACK_TO_MOVE_KEY;
+    }
+
+    let rights = board.castling_rights();
+    let flags = [
+        rights.white_kingside,
+        rights.white_queenside,
+        rights.black_kingside,
+        rights.black_queenside,
+    ];
+    for (flag, key) in flags.iter().zip(CASTLING_KEYS) {
+        if *flag {
+            hash ^= key;
+        }
+    }
+
+    if let Some(target) = board.en_passant_target() {
+        if en_passant_capturable(board, target) {
+            hash ^= EN_PASSANT_KEYS[target.col as usize];
+        }
+    }
+
+    hash
+}
+
+/// An en passant square only changes the position if a pawn can actually capture there
+pub(crate) fn en_passant_capturable(board: &Board, target: Position) -> bool {
+    let color = board.current_turn();
+    let pawn_row = if color == Color::White {
+        target.row - 1
+    } else {
+        target.row + 1
+    };
+
+    [-1, 1].iter().any(|offset| {
+        board
+            .get_piece(Position::new(pawn_row, target.col + offset))
+            .is_some_and(|p| p.piece_type == PieceType::Pawn && p.color == color)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::piece::Move;
+
+    #[test]
+    fn test_transposition_hashes_equal() {
+        // 1. Nf3 Nf6 2. Nc3 and 1. Nc3 Nf6 2. Nf3 reach the same position
+        let mut a = Board::new();
+        a.make_move(Move::new(Position::new(0, 6), Position::new(2, 5)));
+        a.make_move(Move::new(Position::new(7, 6), Position::new(5, 5)));
+        a.make_move(Move::new(Position::new(0, 1), Position::new(2, 2)));
+
+        let mut b = Board::new();
+        b.make_move(Move::new(Position::new(0, 1), Position::new(2, 2)));
+        b.make_move(Move::new(Position::new(7, 6), Position::new(5, 5)));
+        b.make_move(Move::new(Position::new(0, 6), Position::new(2, 5)));
+
+        assert_eq!(hash_position(&a), hash_position(&b));
+        assert_ne!(hash_position(&a), hash_position(&Board::new()));
+    }
+}
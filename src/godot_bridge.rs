@@ -1,7 +1,7 @@
 // Godot-specific bridge - wraps the pure Rust game logic for Godot
 // This is the ONLY file that should have Godot dependencies
 
-use crate::game::board::GameStatus;
+use crate::ai::Difficulty;
 use crate::game::game_state::ChessGame as RustChessGame;
 use crate::game::piece::{Color, PieceType};
 use godot::prelude::*;
@@ -40,6 +40,16 @@ impl ChessGame {
         self.game.reset_game();
     }
 
+    /// Reset to a queen-odds game, the given side ("white" or "black") plays without its queen
+    #[func]
+    pub fn reset_game_with_queen_odds(&mut self, odds_color: GString) {
+        match odds_color.to_string().to_lowercase().as_str() {
+            "white" => self.game.reset_with_handicap(&[PieceType::Queen], &[]),
+            "black" => self.game.reset_with_handicap(&[], &[PieceType::Queen]),
+            _ => {}
+        }
+    }
+
     /// Reset the game with a chess clock
     #[func]
     pub fn reset_game_with_clock(&mut self, initial_time_seconds: i32, increment_seconds: i32) {
@@ -101,6 +111,22 @@ impl ChessGame {
         result
     }
 
+    /// Get this side's attacked and undefended pieces as flattened row/col pairs
+    #[func]
+    pub fn get_threatened_pieces(&self, color: GString) -> PackedInt32Array {
+        let mut result = PackedInt32Array::new();
+        let side = match color.to_string().to_lowercase().as_str() {
+            "white" => Color::White,
+            "black" => Color::Black,
+            _ => return result,
+        };
+        for pos in self.game.get_threatened_pieces(side) {
+            result.push(pos.row as i32);
+            result.push(pos.col as i32);
+        }
+        result
+    }
+
     /// Check if moving the selected piece to the given position is a promotion
     #[func]
     pub fn is_promotion_move(&self, row: i32, col: i32) -> bool {
@@ -152,16 +178,7 @@ impl ChessGame {
     /// Get the current game status
     #[func]
     pub fn get_game_status(&self) -> GString {
-        match self.game.get_game_status() {
-            GameStatus::Ongoing => "ongoing".into(),
-            GameStatus::Check => "check".into(),
-            GameStatus::Checkmate(Color::White) => "checkmate_white".into(),
-            GameStatus::Checkmate(Color::Black) => "checkmate_black".into(),
-            GameStatus::Stalemate => "stalemate".into(),
-            GameStatus::DrawInsufficientMaterial => "draw".into(),
-            GameStatus::TimeLoss(Color::White) => "timeloss_white".into(),
-            GameStatus::TimeLoss(Color::Black) => "timeloss_black".into(),
-        }
+        self.game.get_game_status().name().into()
     }
 
     /// Check if game is over
@@ -200,6 +217,15 @@ impl ChessGame {
         self.game.make_ai_move()
     }
 
+    /// Make an AI move at a difficulty ("random", "easy", "medium" or "hard")
+    #[func]
+    pub fn make_ai_move_with_difficulty(&mut self, difficulty: GString) -> bool {
+        match Difficulty::from_name(&difficulty.to_string()) {
+            Some(difficulty) => self.game.make_ai_move_with_difficulty(difficulty),
+            None => false,
+        }
+    }
+
     /// Clear a square on the board (set to empty)
     #[func]
     pub fn clear_square(&mut self, row: i32, col: i32) {
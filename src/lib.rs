@@ -1,7 +1,12 @@
 // Pure Rust game logic modules - NO Godot dependencies
+// game, ai, cards, rating and ffi are always available, even with `--no-default-features`
 pub mod ai;
-mod cards;
+pub mod cards;
 pub mod game;
+pub mod rating;
+
+// Online multiplayer (tokio/axum/sqlx) - only compiled when networking feature is enabled
+#[cfg(feature = "networking")]
 pub mod networking; // Public for server binary
 
 // FFI layer for external clients (Godot, web, etc.)
@@ -17,4 +22,12 @@ mod tests {
     fn test_basic() {
         assert_eq!(2 + 2, 4);
     }
+
+    // Runs with `cargo test --lib --no-default-features` to check the lean build
+    #[test]
+    fn test_game_logic_standalone() {
+        let mut game = crate::game::game_state::ChessGame::new();
+        assert!(game.make_ai_move());
+        assert_eq!(game.get_current_turn(), crate::game::piece::Color::Black);
+    }
 }
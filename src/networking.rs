@@ -1,11 +1,13 @@
 // Online multiplayer networking module
 // Handles client-server communication and matchmaking
 
+pub mod auth;
 pub mod client;
+pub mod connection;
 pub mod matchmaking;
 pub mod protocol;
 pub mod server;
 pub mod types;
 
-// websocket module is deprecated - WebSocket handling is now in src/bin/server.rs using Axum
+// websocket module is deprecated - WebSocket handling is now in connection.rs using Axum
 // pub mod websocket;
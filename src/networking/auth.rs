@@ -0,0 +1,99 @@
+// Signed player tokens, so a connection can only act as the player it proves to be
+// A token is "<player_id>.<hex HMAC-SHA256 of player_id>" keyed with the server secret
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Environment variable holding the server's token signing secret
+pub const AUTH_SECRET_ENV: &str = "CHESSMATE_AUTH_SECRET";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Issues and verifies player tokens with a server secret
+#[derive(Clone)]
+pub struct TokenVerifier {
+    secret: Vec<u8>,
+}
+
+impl TokenVerifier {
+    pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            secret: secret.into(),
+        }
+    }
+
+    /// Read the secret from `CHESSMATE_AUTH_SECRET`, None if it is unset or empty
+    pub fn from_env() -> Option<Self> {
+        std::env::var(AUTH_SECRET_ENV)
+            .ok()
+            .filter(|secret| !secret.is_empty())
+            .map(Self::new)
+    }
+
+    /// Issue a token for the given player
+    pub fn sign(&self, player_id: &str) -> String {
+        let signature = self.mac(player_id).finalize().into_bytes();
+        let hex: String = signature.iter().map(|b| format!("{:02x}", b)).collect();
+        format!("{}.{}", player_id, hex)
+    }
+
+    /// Check a token's signature and return the player ID it was issued for
+    pub fn verify(&self, token: &str) -> Result<String, String> {
+        let (player_id, hex) = token
+            .rsplit_once('.')
+            .ok_or_else(|| "Malformed token".to_string())?;
+        if player_id.is_empty() {
+            return Err("Malformed token".to_string());
+        }
+
+        let signature = decode_hex(hex).ok_or_else(|| "Malformed token".to_string())?;
+        self.mac(player_id)
+            .verify_slice(&signature)
+            .map_err(|_| "Invalid token signature".to_string())?;
+
+        Ok(player_id.to_string())
+    }
+
+    fn mac(&self, player_id: &str) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts any key length");
+        mac.update(player_id.as_bytes());
+        mac
+    }
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    let pairs = hex.as_bytes().chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return None;
+    }
+    pairs
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair).ok()?;
+            u8::from_str_radix(pair, 16).ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_token_round_trips() {
+        let verifier = TokenVerifier::new("secret");
+        let token = verifier.sign("alice");
+        assert_eq!(verifier.verify(&token), Ok("alice".to_string()));
+    }
+
+    #[test]
+    fn test_token_from_other_secret_or_player_is_rejected() {
+        let verifier = TokenVerifier::new("secret");
+        let foreign = TokenVerifier::new("other").sign("alice");
+        assert!(verifier.verify(&foreign).is_err());
+
+        let token = verifier.sign("alice");
+        let (_, signature) = token.split_once('.').unwrap();
+        assert!(verifier.verify(&format!("mallory.{}", signature)).is_err());
+        assert!(verifier.verify("alice").is_err());
+    }
+}
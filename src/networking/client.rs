@@ -2,10 +2,23 @@
 use futures_util::{SinkExt, StreamExt};
 use std::error::Error;
 use tokio::sync::mpsc;
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::{protocol::CloseFrame, Message};
 
 use crate::networking::protocol::{ClientMessage, GameAction, ServerMessage};
-use crate::networking::types::SerializableGameState;
+use crate::networking::types::{SerializableGameState, TimeControl};
+
+/// Error to hand the app for a close frame from the server
+/// Application close codes (4000 and up) mean the server rejected us; others pass silently
+fn close_frame_error(frame: &CloseFrame) -> Option<ServerMessage> {
+    if u16::from(frame.code) < 4000 {
+        return None;
+    }
+    Some(ServerMessage::error(format!(
+        "Connection closed by server: {}",
+        frame.reason
+    )))
+}
 
 /// Network client for connecting to the game server
 pub struct NetworkClient {
@@ -35,6 +48,9 @@ impl NetworkClient {
         let (tx_to_server, mut rx_from_app) = mpsc::unbounded_channel::<ClientMessage>();
         let (tx_to_app, rx_to_app) = mpsc::unbounded_channel::<ServerMessage>();
 
+        // Tell the server which protocol we speak before anything else
+        tx_to_server.send(ClientMessage::hello())?;
+
         // Store channels
         self.tx = Some(tx_to_server);
         self.rx = Some(rx_to_app);
@@ -61,7 +77,12 @@ impl NetworkClient {
                             }
                         }
                     }
-                    Ok(Message::Close(_)) => break,
+                    Ok(Message::Close(frame)) => {
+                        if let Some(msg) = frame.as_ref().and_then(close_frame_error) {
+                            let _ = tx_to_app.send(msg);
+                        }
+                        break;
+                    }
                     Err(_) => break,
                     _ => {}
                 }
@@ -71,6 +92,15 @@ impl NetworkClient {
         Ok(())
     }
 
+    /// Prove this client's player identity with a server-signed token
+    pub async fn authenticate(&self, token: &str) -> Result<(), Box<dyn Error>> {
+        if let Some(tx) = &self.tx {
+            let msg = ClientMessage::authenticate(token.to_string());
+            tx.send(msg)?;
+        }
+        Ok(())
+    }
+
     /// Join the matchmaking queue
     pub async fn join_matchmaking(&self) -> Result<(), Box<dyn Error>> {
         if let Some(tx) = &self.tx {
@@ -80,6 +110,54 @@ impl NetworkClient {
         Ok(())
     }
 
+    /// Resume a game after the previous connection dropped
+    pub async fn reconnect(&self, game_id: &str) -> Result<(), Box<dyn Error>> {
+        if let Some(tx) = &self.tx {
+            let msg = ClientMessage::reconnect(game_id.to_string(), self.player_id.clone());
+            tx.send(msg)?;
+        }
+        Ok(())
+    }
+
+    /// Leave the matchmaking queue
+    pub async fn leave_matchmaking(&self) -> Result<(), Box<dyn Error>> {
+        if let Some(tx) = &self.tx {
+            let msg = ClientMessage::leave_matchmaking(self.player_id.clone());
+            tx.send(msg)?;
+        }
+        Ok(())
+    }
+
+    /// Start a game against the server's AI
+    pub async fn play_vs_computer(&self, difficulty: u8) -> Result<(), Box<dyn Error>> {
+        if let Some(tx) = &self.tx {
+            let msg = ClientMessage::play_vs_computer(self.player_id.clone(), difficulty);
+            tx.send(msg)?;
+        }
+        Ok(())
+    }
+
+    /// Open a private room for a friend to join, the code arrives as RoomCreated
+    pub async fn create_room(
+        &self,
+        time_control: Option<TimeControl>,
+    ) -> Result<(), Box<dyn Error>> {
+        if let Some(tx) = &self.tx {
+            let msg = ClientMessage::create_room(self.player_id.clone(), time_control);
+            tx.send(msg)?;
+        }
+        Ok(())
+    }
+
+    /// Join a friend's room by its code
+    pub async fn join_room(&self, code: &str) -> Result<(), Box<dyn Error>> {
+        if let Some(tx) = &self.tx {
+            let msg = ClientMessage::join_room(self.player_id.clone(), code.to_string());
+            tx.send(msg)?;
+        }
+        Ok(())
+    }
+
     /// Submit a game action
     pub async fn submit_action(
         &self,
@@ -111,6 +189,33 @@ impl NetworkClient {
         Ok(())
     }
 
+    /// Send a chat message to the opponent of a game
+    pub async fn send_chat(&self, game_id: &str, text: &str) -> Result<(), Box<dyn Error>> {
+        if let Some(tx) = &self.tx {
+            let msg = ClientMessage::send_chat(game_id.to_string(), text.to_string());
+            tx.send(msg)?;
+        }
+        Ok(())
+    }
+
+    /// Watch an active game
+    pub async fn spectate_game(&self, game_id: &str) -> Result<(), Box<dyn Error>> {
+        if let Some(tx) = &self.tx {
+            let msg = ClientMessage::spectate_game(game_id.to_string());
+            tx.send(msg)?;
+        }
+        Ok(())
+    }
+
+    /// Ask to play a finished game's opponent again
+    pub async fn request_rematch(&self, game_id: &str) -> Result<(), Box<dyn Error>> {
+        if let Some(tx) = &self.tx {
+            let msg = ClientMessage::request_rematch(game_id.to_string());
+            tx.send(msg)?;
+        }
+        Ok(())
+    }
+
     /// Try to receive a message from the server (non-blocking)
     pub async fn try_recv(&mut self) -> Option<ServerMessage> {
         if let Some(rx) = &mut self.rx {
@@ -145,6 +250,7 @@ pub struct SimpleGameClient {
     client: NetworkClient,
     current_game_id: Option<String>,
     current_state: Option<SerializableGameState>,
+    last_game_id: Option<String>, // Most recently finished game, for rematches
 }
 
 impl SimpleGameClient {
@@ -154,6 +260,7 @@ impl SimpleGameClient {
             client: NetworkClient::new(player_id, server_url),
             current_game_id: None,
             current_state: None,
+            last_game_id: None,
         }
     }
 
@@ -170,13 +277,26 @@ impl SimpleGameClient {
 
         while let Some(msg) = self.client.try_recv().await {
             match msg {
+                ServerMessage::Authenticated { player_id } => {
+                    events.push(format!("Authenticated as {}", player_id));
+                }
                 ServerMessage::MatchmakingJoined => {
                     events.push("Joined matchmaking queue".to_string());
                 }
+                ServerMessage::MatchmakingLeft => {
+                    events.push("Left matchmaking queue".to_string());
+                }
+                ServerMessage::RoomCreated { code } => {
+                    events.push(format!("Room created, share the code: {}", code));
+                }
+                ServerMessage::MatchmakingStatus { queue_position, .. } => {
+                    events.push(format!("Queue position: {}", queue_position));
+                }
                 ServerMessage::MatchFound {
                     game_id,
                     opponent_id,
                     your_color,
+                    ..
                 } => {
                     self.current_game_id = Some(game_id.clone());
                     events.push(format!(
@@ -185,23 +305,48 @@ impl SimpleGameClient {
                     ));
                 }
                 ServerMessage::GameStateUpdate { state } => {
-                    self.current_state = Some(state.clone());
                     events.push(format!(
                         "Game state updated. Next player: {}",
                         state.next_player_id
                     ));
+                    self.current_state = Some(*state);
+                }
+                ServerMessage::StateDelta { delta } => {
+                    events.push(format!(
+                        "Game state delta. Next player: {}",
+                        delta.next_player_id
+                    ));
+                    if let Some(state) = self.current_state.as_mut() {
+                        state.apply_delta(&delta);
+                    }
                 }
                 ServerMessage::OpponentAction { action } => {
                     events.push(format!("Opponent action: {:?}", action));
                 }
-                ServerMessage::GameOver { winner, reason } => {
+                ServerMessage::DrawOffered => {
+                    events.push("Opponent offers a draw".to_string());
+                }
+                ServerMessage::DrawDeclined => {
+                    events.push("Opponent declined the draw offer".to_string());
+                }
+                ServerMessage::GameOver { winner, reason, .. } => {
                     events.push(format!(
                         "Game over! Winner: {:?}, Reason: {}",
                         winner, reason
                     ));
-                    self.current_game_id = None;
+                    self.last_game_id = self.current_game_id.take();
                     self.current_state = None;
                 }
+                ServerMessage::ChatMessage { from, text } => {
+                    events.push(format!("{}: {}", from, text));
+                }
+                ServerMessage::RematchOffered { .. } => {
+                    events.push("Opponent wants a rematch".to_string());
+                }
+                ServerMessage::RematchStarted { game_id, .. } => {
+                    events.push(format!("Rematch starting! Game ID: {}", game_id));
+                    self.last_game_id = None;
+                }
                 ServerMessage::InvalidAction { reason } => {
                     events.push(format!("Invalid action: {}", reason));
                 }
@@ -262,6 +407,89 @@ impl SimpleGameClient {
         Ok(())
     }
 
+    /// Offer the opponent a draw
+    pub async fn offer_draw(&self) -> Result<(), Box<dyn Error>> {
+        if let Some(game_id) = &self.current_game_id {
+            let action = GameAction::offer_draw();
+            self.client.submit_action(game_id, action).await?;
+        }
+        Ok(())
+    }
+
+    /// Accept the opponent's draw offer
+    pub async fn accept_draw(&self) -> Result<(), Box<dyn Error>> {
+        if let Some(game_id) = &self.current_game_id {
+            let action = GameAction::accept_draw();
+            self.client.submit_action(game_id, action).await?;
+        }
+        Ok(())
+    }
+
+    /// Decline the opponent's draw offer
+    pub async fn decline_draw(&self) -> Result<(), Box<dyn Error>> {
+        if let Some(game_id) = &self.current_game_id {
+            let action = GameAction::decline_draw();
+            self.client.submit_action(game_id, action).await?;
+        }
+        Ok(())
+    }
+
+    /// Say something to the opponent of the current game
+    pub async fn send_chat(&self, text: &str) -> Result<(), Box<dyn Error>> {
+        if let Some(game_id) = &self.current_game_id {
+            self.client.send_chat(game_id, text).await?;
+        }
+        Ok(())
+    }
+
+    /// Ask the opponent of the last finished game for a rematch, or accept theirs
+    pub async fn request_rematch(&self) -> Result<(), Box<dyn Error>> {
+        if let Some(game_id) = &self.last_game_id {
+            self.client.request_rematch(game_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Ask the opponent to take back our last move (refused in rated games)
+    pub async fn request_takeback(&self) -> Result<(), Box<dyn Error>> {
+        if let Some(game_id) = &self.current_game_id {
+            let action = GameAction::request_takeback();
+            self.client.submit_action(game_id, action).await?;
+        }
+        Ok(())
+    }
+
+    /// Accept the opponent's takeback request
+    pub async fn accept_takeback(&self) -> Result<(), Box<dyn Error>> {
+        if let Some(game_id) = &self.current_game_id {
+            let action = GameAction::accept_takeback();
+            self.client.submit_action(game_id, action).await?;
+        }
+        Ok(())
+    }
+
+    /// Decline the opponent's takeback request
+    pub async fn decline_takeback(&self) -> Result<(), Box<dyn Error>> {
+        if let Some(game_id) = &self.current_game_id {
+            let action = GameAction::decline_takeback();
+            self.client.submit_action(game_id, action).await?;
+        }
+        Ok(())
+    }
+
+    /// Play a card from our hand, aimed at `target` if the card takes a square
+    pub async fn play_card(
+        &self,
+        card_id: &str,
+        target: Option<crate::game::piece::Position>,
+    ) -> Result<(), Box<dyn Error>> {
+        if let Some(game_id) = &self.current_game_id {
+            let action = GameAction::play_card(card_id.to_string(), target);
+            self.client.submit_action(game_id, action).await?;
+        }
+        Ok(())
+    }
+
     /// Get current game state
     pub fn current_state(&self) -> Option<&SerializableGameState> {
         self.current_state.as_ref()
@@ -277,3 +505,38 @@ impl SimpleGameClient {
         self.current_game_id.is_some()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::networking::protocol::CloseReason;
+    use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+
+    #[test]
+    fn test_application_close_code_is_reported_to_the_app() {
+        let reason = CloseReason::AuthenticationFailed;
+        let frame = CloseFrame {
+            code: CloseCode::from(reason.code()),
+            reason: reason.reason().into(),
+        };
+
+        match close_frame_error(&frame) {
+            Some(ServerMessage::Error { message }) => {
+                assert_eq!(
+                    message,
+                    "Connection closed by server: Authentication failed"
+                )
+            }
+            other => panic!("Expected Error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_normal_close_is_not_an_error() {
+        let frame = CloseFrame {
+            code: CloseCode::Normal,
+            reason: "".into(),
+        };
+        assert!(close_frame_error(&frame).is_none());
+    }
+}
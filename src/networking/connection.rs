@@ -0,0 +1,296 @@
+// WebSocket connection handling for the game server, shared by the server binary and tests
+use axum::extract::ws::{CloseFrame, Message, WebSocket};
+use futures_util::{SinkExt, StreamExt};
+use tokio::sync::mpsc;
+
+use crate::networking::matchmaking::WaitingPlayer;
+use crate::networking::protocol::{ClientMessage, CloseReason, ServerMessage, PROTOCOL_VERSION};
+use crate::networking::server::GameServer;
+
+/// Serve one client connection until it closes, or until the server closes it with a
+/// `CloseReason` (the caller wraps this in the connection's tracing span)
+pub async fn handle_websocket(socket: WebSocket, server: GameServer) {
+    let (mut ws_tx, mut ws_rx) = socket.split();
+
+    // Create a channel for sending messages to this client
+    let (tx, mut rx) = mpsc::unbounded_channel::<ServerMessage>();
+    // Rejecting the connection goes through the same task, so it follows any queued messages
+    let (close_tx, mut close_rx) = mpsc::unbounded_channel::<CloseReason>();
+
+    // Spawn a task to forward messages from the channel to the WebSocket
+    let send_task = tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                biased;
+                Some(msg) = rx.recv() => {
+                    if let Ok(json) = serde_json::to_string(&msg) {
+                        if ws_tx
+                            .send(Message::Text(json))
+                            .await
+                            .is_err()
+                        {
+                            break;
+                        }
+                    }
+                }
+                Some(reason) = close_rx.recv() => {
+                    let frame = CloseFrame {
+                        code: reason.code(),
+                        reason: reason.reason().into(),
+                    };
+                    let _ = ws_tx
+                        .send(Message::Close(Some(frame)))
+                        .await;
+                    break;
+                }
+                else => break,
+            }
+        }
+    });
+
+    // Variable to store player ID once they join matchmaking
+    let mut player_id: Option<String> = None;
+    // Player ID proven by an Authenticate token, required when the server has a secret
+    let mut verified_id: Option<String> = None;
+    // Game this connection is watching, if any
+    let mut spectating: Option<String> = None;
+    // Set once we've asked the send task to close the connection
+    let mut closing = false;
+
+    // Process incoming messages from the WebSocket
+    while let Some(result) = ws_rx.next().await {
+        match result {
+            Ok(msg) => {
+                if let Message::Text(text) = msg {
+                    // Try to deserialize the message
+                    match serde_json::from_str::<ClientMessage>(&text) {
+                        Ok(client_msg) => {
+                            // A client speaking another protocol version can't be served
+                            if let ClientMessage::Hello { protocol_version } = client_msg {
+                                if protocol_version != PROTOCOL_VERSION {
+                                    tracing::warn!(
+                                        "Closing connection speaking protocol version {}",
+                                        protocol_version
+                                    );
+                                    let _ = close_tx.send(CloseReason::VersionMismatch);
+                                    closing = true;
+                                    break;
+                                }
+                                continue;
+                            }
+
+                            // Anyone may watch a game, whether or not they play one,
+                            // once authenticated on a server that requires it
+                            if let ClientMessage::SpectateGame { ref game_id } = client_msg {
+                                if server.requires_auth() && verified_id.is_none() {
+                                    let _ = tx.send(ServerMessage::error(
+                                        "Must authenticate first".to_string(),
+                                    ));
+                                    continue;
+                                }
+                                match server.add_spectator(game_id, tx.clone()).await {
+                                    Ok(()) => {
+                                        tracing::info!("Connection is spectating {}", game_id);
+                                        spectating = Some(game_id.clone());
+                                    }
+                                    Err(e) => {
+                                        let _ = tx.send(ServerMessage::error(e));
+                                    }
+                                }
+                                continue;
+                            }
+
+                            // Extract player_id from the message if we don't have it yet
+                            if player_id.is_none() {
+                                if let ClientMessage::Authenticate { ref token } = client_msg {
+                                    verified_id = server.authenticate(token, &tx);
+                                    // A bad token on a server that requires one ends the connection
+                                    if verified_id.is_none() && server.requires_auth() {
+                                        tracing::warn!(
+                                            "Closing connection after failed authentication"
+                                        );
+                                        let _ = close_tx.send(CloseReason::AuthenticationFailed);
+                                        closing = true;
+                                        break;
+                                    }
+                                    continue;
+                                }
+
+                                // Only the authenticated player may join or resume as themselves
+                                if let Some(pid) = client_msg.claimed_player_id() {
+                                    if let Err(e) =
+                                        server.check_acting_as(verified_id.as_deref(), pid)
+                                    {
+                                        let _ = tx.send(ServerMessage::error(e));
+                                        continue;
+                                    }
+                                }
+
+                                if let ClientMessage::JoinMatchmaking {
+                                    player_id: ref pid,
+                                    rating,
+                                    time_odds,
+                                } = client_msg
+                                {
+                                    // Add player to matchmaking queue
+                                    let player = WaitingPlayer::new(pid.clone(), tx.clone())
+                                        .with_rating(rating)
+                                        .with_time_odds(time_odds);
+                                    if let Err(e) = server.add_to_matchmaking(player).await {
+                                        tracing::error!(
+                                            "Failed to add player to matchmaking: {}",
+                                            e
+                                        );
+                                        let _ = tx.send(ServerMessage::error(e));
+                                        continue;
+                                    }
+
+                                    // Only a connection that joined may act as this player
+                                    player_id = Some(pid.clone());
+                                    tracing::Span::current().record("player_id", pid.as_str());
+
+                                    // Send acknowledgment
+                                    let _ = tx.send(ServerMessage::matchmaking_joined());
+                                    if let Some(status) = server.matchmaking_status(pid).await {
+                                        let _ = tx.send(status);
+                                    }
+                                    tracing::info!("Player {} joined matchmaking", pid);
+                                }
+
+                                if let ClientMessage::PlayVsComputer {
+                                    player_id: ref pid,
+                                    difficulty,
+                                } = client_msg
+                                {
+                                    if let Err(e) = server
+                                        .start_computer_game(pid, difficulty, tx.clone())
+                                        .await
+                                    {
+                                        tracing::error!("Failed to start computer game: {}", e);
+                                        let _ = tx.send(ServerMessage::error(e));
+                                        continue;
+                                    }
+
+                                    player_id = Some(pid.clone());
+                                    tracing::Span::current().record("player_id", pid.as_str());
+                                    tracing::info!("Player {} started a computer game", pid);
+                                }
+
+                                if let ClientMessage::CreateRoom {
+                                    player_id: ref pid,
+                                    time_control,
+                                } = client_msg
+                                {
+                                    match server.create_room(pid, time_control, tx.clone()).await {
+                                        Ok(code) => {
+                                            tracing::info!("Player {} opened room {}", pid, code)
+                                        }
+                                        Err(e) => {
+                                            tracing::error!("Failed to create room: {}", e);
+                                            let _ = tx.send(ServerMessage::error(e));
+                                            continue;
+                                        }
+                                    }
+
+                                    player_id = Some(pid.clone());
+                                    tracing::Span::current().record("player_id", pid.as_str());
+                                }
+
+                                if let ClientMessage::Reconnect {
+                                    player_id: ref pid,
+                                    ref game_id,
+                                } = client_msg
+                                {
+                                    if let Err(e) = server.reconnect(game_id, pid, tx.clone()).await
+                                    {
+                                        tracing::error!("Failed to reconnect: {}", e);
+                                        let _ = tx.send(ServerMessage::error(e));
+                                        continue;
+                                    }
+
+                                    player_id = Some(pid.clone());
+                                    tracing::Span::current().record("player_id", pid.as_str());
+                                    continue;
+                                }
+
+                                if let ClientMessage::JoinRoom {
+                                    player_id: ref pid,
+                                    ref code,
+                                } = client_msg
+                                {
+                                    if let Err(e) = server.join_room(pid, code, tx.clone()).await {
+                                        tracing::error!("Failed to join room: {}", e);
+                                        let _ = tx.send(ServerMessage::error(e));
+                                        continue;
+                                    }
+
+                                    player_id = Some(pid.clone());
+                                    tracing::Span::current().record("player_id", pid.as_str());
+                                    tracing::info!("Player {} joined room {}", pid, code);
+                                }
+                            }
+
+                            // Handle the message
+                            if let Some(ref pid) = player_id {
+                                let leaving_queue =
+                                    matches!(client_msg, ClientMessage::LeaveMatchmaking { .. });
+                                if let Err(e) = server.handle_message(pid, client_msg).await {
+                                    tracing::error!("Error handling message from {}: {}", pid, e);
+                                    let _ = tx.send(ServerMessage::error(e));
+                                } else if leaving_queue {
+                                    // Back to a fresh connection, free to join again
+                                    tracing::info!("Player {} left matchmaking", pid);
+                                    player_id = None;
+                                }
+                            } else if spectating.is_some() {
+                                let _ = tx.send(ServerMessage::error(
+                                    "Spectators can't act in the game".to_string(),
+                                ));
+                            } else if server.requires_auth() && verified_id.is_none() {
+                                let _ = tx.send(ServerMessage::error(
+                                    "Must authenticate first".to_string(),
+                                ));
+                            } else {
+                                let _ = tx.send(ServerMessage::error(
+                                    "Must join matchmaking first".to_string(),
+                                ));
+                            }
+                        }
+                        Err(e) => {
+                            tracing::error!("Failed to deserialize message: {}", e);
+                            let _ = tx.send(ServerMessage::error(format!(
+                                "Invalid message format: {}",
+                                e
+                            )));
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::error!("WebSocket error: {}", e);
+                break;
+            }
+        }
+    }
+
+    if closing {
+        // Let the send task flush what's queued and deliver the close frame
+        let _ = send_task.await;
+    } else {
+        // Client disconnected: stop forwarding, so the game sees the player's connection as
+        // gone and holds their seat for a Reconnect until DISCONNECT_GRACE runs out
+        send_task.abort();
+    }
+    if let Some(game_id) = spectating {
+        let _ = server.remove_spectator(&game_id, &tx).await;
+    }
+    if let Some(pid) = player_id {
+        // A player still waiting for an opponent can't be matched any more
+        if server.find_player_game(&pid).await.is_none()
+            && server.leave_matchmaking(&pid).await.is_ok()
+        {
+            tracing::info!("Removed disconnected player {} from matchmaking", pid);
+        }
+        tracing::info!("Player {} disconnected", pid);
+    }
+}
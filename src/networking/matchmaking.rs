@@ -1,9 +1,20 @@
 // Matchmaking queue for pairing players
-use std::time::Instant;
+use rand::Rng;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use uuid::Uuid;
 
 use crate::networking::protocol::ServerMessage;
+use crate::networking::types::TimeControl;
+
+/// Starting time per player in a time-odds game, before the stronger player's reduction
+pub const TIME_ODDS_BASE_SECONDS: i32 = 300;
+
+/// Seconds taken from the stronger player per 100 rating points of difference
+pub const TIME_ODDS_SECONDS_PER_100_RATING: i32 = 45;
+
+/// Least time the stronger player is left with in a time-odds game
+pub const TIME_ODDS_MIN_SECONDS: i32 = 60;
 
 /// A player waiting in the matchmaking queue
 #[derive(Debug, Clone)]
@@ -11,6 +22,9 @@ pub struct WaitingPlayer {
     pub player_id: String,
     pub joined_at: Instant,
     pub sender: mpsc::UnboundedSender<ServerMessage>,
+    pub rating: Option<u32>,
+    /// Player asked for time odds against a differently rated opponent
+    pub wants_time_odds: bool,
 }
 
 impl WaitingPlayer {
@@ -19,8 +33,22 @@ impl WaitingPlayer {
             player_id,
             joined_at: Instant::now(),
             sender,
+            rating: None,
+            wants_time_odds: false,
         }
     }
+
+    /// Set the player's rating
+    pub fn with_rating(mut self, rating: Option<u32>) -> Self {
+        self.rating = rating;
+        self
+    }
+
+    /// Set whether the player wants time odds
+    pub fn with_time_odds(mut self, wants_time_odds: bool) -> Self {
+        self.wants_time_odds = wants_time_odds;
+        self
+    }
 }
 
 /// A matched pair of players ready to start a game
@@ -29,6 +57,8 @@ pub struct Match {
     pub game_id: String,
     pub white_player: WaitingPlayer,
     pub black_player: WaitingPlayer,
+    /// Clock the players agreed on, None for an untimed game
+    pub time_control: Option<TimeControl>,
 }
 
 impl Match {
@@ -37,6 +67,38 @@ impl Match {
             game_id: Uuid::new_v4().to_string(),
             white_player,
             black_player,
+            time_control: None,
+        }
+    }
+
+    /// Whether the game counts for ratings: both players were matched with a rating
+    pub fn is_rated(&self) -> bool {
+        self.white_player.rating.is_some() && self.black_player.rating.is_some()
+    }
+
+    /// Set the clock the game will be played with
+    pub fn with_time_control(mut self, time_control: Option<TimeControl>) -> Self {
+        self.time_control = time_control;
+        self
+    }
+
+    /// Starting times (white, black) in seconds when both players asked for time odds
+    /// and both are rated; the stronger player gets less time. None means no odds
+    pub fn time_odds(&self) -> Option<(i32, i32)> {
+        if !(self.white_player.wants_time_odds && self.black_player.wants_time_odds) {
+            return None;
+        }
+        let white_rating = self.white_player.rating?;
+        let black_rating = self.black_player.rating?;
+
+        let gap = white_rating.abs_diff(black_rating) as i32;
+        let stronger_time = (TIME_ODDS_BASE_SECONDS - gap * TIME_ODDS_SECONDS_PER_100_RATING / 100)
+            .max(TIME_ODDS_MIN_SECONDS);
+
+        if white_rating > black_rating {
+            Some((stronger_time, TIME_ODDS_BASE_SECONDS))
+        } else {
+            Some((TIME_ODDS_BASE_SECONDS, stronger_time))
         }
     }
 }
@@ -61,22 +123,27 @@ impl MatchmakingQueue {
 
     /// Remove a player from the queue by player_id
     pub fn remove_player(&mut self, player_id: &str) -> bool {
-        if let Some(index) = self
+        self.take_player(player_id).is_some()
+    }
+
+    /// Remove a player from the queue by player_id, returning their entry
+    pub fn take_player(&mut self, player_id: &str) -> Option<WaitingPlayer> {
+        let index = self
             .waiting_players
             .iter()
-            .position(|p| p.player_id == player_id)
-        {
-            self.waiting_players.remove(index);
-            true
-        } else {
-            false
-        }
+            .position(|p| p.player_id == player_id)?;
+        Some(self.waiting_players.remove(index))
     }
 
     /// Try to create matches from waiting players
     /// Simple algorithm: pair the first two players in the queue
     /// Returns a vector of matched pairs
     pub fn try_create_matches(&mut self) -> Vec<Match> {
+        self.try_create_matches_with_rng(&mut rand::thread_rng())
+    }
+
+    /// Try to create matches, using the given rng for color assignment
+    pub fn try_create_matches_with_rng<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Vec<Match> {
         let mut matches = Vec::new();
 
         while self.waiting_players.len() >= 2 {
@@ -85,7 +152,7 @@ impl MatchmakingQueue {
             let player2 = self.waiting_players.remove(0);
 
             // Randomly assign colors (50/50)
-            let (white_player, black_player) = if rand::random::<bool>() {
+            let (white_player, black_player) = if rng.gen::<bool>() {
                 (player1, player2)
             } else {
                 (player2, player1)
@@ -97,11 +164,55 @@ impl MatchmakingQueue {
         matches
     }
 
+    /// Remove players whose connection is gone, or who have waited at least `timeout` as of `now`
+    /// (None = no time limit); returns the removed players
+    pub fn prune_stale(&mut self, now: Instant, timeout: Option<Duration>) -> Vec<WaitingPlayer> {
+        let (stale, waiting): (Vec<_>, Vec<_>) = self.waiting_players.drain(..).partition(|p| {
+            p.sender.is_closed()
+                || timeout
+                    .is_some_and(|timeout| now.saturating_duration_since(p.joined_at) >= timeout)
+        });
+        self.waiting_players = waiting;
+        stale
+    }
+
     /// Get the number of players waiting
     pub fn player_count(&self) -> usize {
         self.waiting_players.len()
     }
 
+    /// Get a player's 1-based position in the queue
+    pub fn queue_position(&self, player_id: &str) -> Option<usize> {
+        self.waiting_players
+            .iter()
+            .position(|p| p.player_id == player_id)
+            .map(|index| index + 1)
+    }
+
+    /// Get a queued player's rating, if they gave one
+    pub fn player_rating(&self, player_id: &str) -> Option<u32> {
+        self.waiting_players
+            .iter()
+            .find(|p| p.player_id == player_id)
+            .and_then(|p| p.rating)
+    }
+
+    /// Change a queued player's rating and time odds, keeping their place in the queue
+    pub fn update_player(
+        &mut self,
+        player_id: &str,
+        rating: Option<u32>,
+        wants_time_odds: bool,
+    ) -> Option<&WaitingPlayer> {
+        let player = self
+            .waiting_players
+            .iter_mut()
+            .find(|p| p.player_id == player_id)?;
+        player.rating = rating;
+        player.wants_time_odds = wants_time_odds;
+        Some(player)
+    }
+
     /// Check if a player is in the queue
     pub fn contains_player(&self, player_id: &str) -> bool {
         self.waiting_players
@@ -173,6 +284,37 @@ mod tests {
         assert_ne!(m.white_player.player_id, m.black_player.player_id);
     }
 
+    #[test]
+    fn test_prune_stale_players() {
+        let mut queue = MatchmakingQueue::new();
+        let (tx1, _rx1) = mpsc::unbounded_channel();
+        let (tx2, _rx2) = mpsc::unbounded_channel();
+        let (tx3, rx3) = mpsc::unbounded_channel();
+        let start = Instant::now();
+        let timeout = Duration::from_secs(300);
+
+        let mut old = WaitingPlayer::new("old".to_string(), tx1);
+        old.joined_at = start;
+        let mut fresh = WaitingPlayer::new("fresh".to_string(), tx2);
+        fresh.joined_at = start + Duration::from_secs(60);
+        let mut gone = WaitingPlayer::new("gone".to_string(), tx3);
+        gone.joined_at = start + Duration::from_secs(60);
+        queue.add_player(old);
+        queue.add_player(fresh);
+        queue.add_player(gone);
+        drop(rx3);
+
+        // Without a timeout only disconnected players are pruned
+        let pruned = queue.prune_stale(start + timeout, None);
+        assert_eq!(pruned.len(), 1);
+        assert_eq!(pruned[0].player_id, "gone");
+
+        let pruned = queue.prune_stale(start + timeout, Some(timeout));
+        assert_eq!(pruned.len(), 1);
+        assert_eq!(pruned[0].player_id, "old");
+        assert!(queue.contains_player("fresh"));
+    }
+
     #[test]
     fn test_no_match_with_single_player() {
         let mut queue = MatchmakingQueue::new();
@@ -185,4 +327,29 @@ mod tests {
         assert_eq!(matches.len(), 0);
         assert_eq!(queue.player_count(), 1);
     }
+
+    #[test]
+    fn test_time_odds_favor_weaker_player() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let player = |id: &str, rating: u32| {
+            WaitingPlayer::new(id.to_string(), tx.clone())
+                .with_rating(Some(rating))
+                .with_time_odds(true)
+        };
+
+        let m = Match::new(player("white", 2000), player("black", 1600));
+        assert_eq!(m.time_odds(), Some((120, TIME_ODDS_BASE_SECONDS)));
+
+        let m = Match::new(player("white", 1200), player("black", 2400));
+        assert_eq!(
+            m.time_odds(),
+            Some((TIME_ODDS_BASE_SECONDS, TIME_ODDS_MIN_SECONDS))
+        );
+
+        let m = Match::new(
+            player("white", 2000),
+            player("black", 1600).with_time_odds(false),
+        );
+        assert_eq!(m.time_odds(), None);
+    }
 }
@@ -1,14 +1,52 @@
 // Network protocol message types
 use crate::game::piece::{Color, PieceType, Position};
-use crate::networking::types::SerializableGameState;
+use crate::networking::types::{SerializableGameState, StateDelta, TimeControl};
 use serde::{Deserialize, Serialize};
 
+/// Version of this protocol; clients announce theirs with Hello and are closed on a mismatch
+pub const PROTOCOL_VERSION: u32 = 1;
+
 /// Messages sent from client to server
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum ClientMessage {
+    /// Announce the protocol version the client speaks, before anything else
+    Hello { protocol_version: u32 },
+
+    /// Prove the connection's player identity with a server-signed token
+    Authenticate { token: String },
+
     /// Join the matchmaking queue
-    JoinMatchmaking { player_id: String },
+    JoinMatchmaking {
+        player_id: String,
+        #[serde(default)]
+        rating: Option<u32>,
+        #[serde(default)]
+        time_odds: bool, // Ask for time odds against a differently rated opponent
+    },
+
+    /// Leave the matchmaking queue before being matched
+    LeaveMatchmaking { player_id: String },
+
+    /// Start a game against the server's AI instead of waiting for a human opponent
+    PlayVsComputer {
+        player_id: String,
+        #[serde(default)]
+        difficulty: u8, // 0 = casual weighted AI, higher = deeper search (capped)
+    },
+
+    /// Open a private room for a friend to join by code, bypassing matchmaking
+    CreateRoom {
+        player_id: String,
+        #[serde(default)]
+        time_control: Option<TimeControl>, // None = untimed
+    },
+
+    /// Join a friend's room with the code they were given
+    JoinRoom { player_id: String, code: String },
+
+    /// Resume a game from a new connection after the previous one dropped
+    Reconnect { game_id: String, player_id: String },
 
     /// Submit a game action (move, resign, etc.)
     SubmitAction { game_id: String, action: GameAction },
@@ -18,6 +56,15 @@ pub enum ClientMessage {
 
     /// Request current game state
     RequestState { game_id: String },
+
+    /// Ask to play the opponent of a finished game again, with colors swapped
+    RequestRematch { game_id: String },
+
+    /// Say something to the opponent (and spectators) of a game
+    SendChat { game_id: String, text: String },
+
+    /// Watch an active game; spectators receive its state updates but can't act
+    SpectateGame { game_id: String },
 }
 
 /// Messages sent from server to client
@@ -29,18 +76,42 @@ pub enum ServerMessage {
         game_id: String,
         opponent_id: String,
         your_color: Color,
+        #[serde(default)]
+        seed: u64, // Seed of the game's RNG, so clients can reproduce random effects
     },
 
     /// Full game state update
-    GameStateUpdate { state: SerializableGameState },
+    GameStateUpdate { state: Box<SerializableGameState> },
+
+    /// Incremental update relative to the last state sent to this client
+    StateDelta { delta: Box<StateDelta> },
 
     /// Opponent performed an action
     OpponentAction { action: GameAction },
 
+    /// Opponent offered a draw, answer with AcceptDraw or DeclineDraw
+    DrawOffered,
+
+    /// Opponent declined our draw offer
+    DrawDeclined,
+
     /// Game ended
     GameOver {
         winner: Option<Color>,
-        reason: String,
+        reason_code: EndReasonCode,
+        reason: String, // Human-readable, clients should localize from reason_code
+    },
+
+    /// Chat line from a player in the game
+    ChatMessage { from: String, text: String },
+
+    /// Opponent asked for a rematch, answer with RequestRematch to accept
+    RematchOffered { game_id: String },
+
+    /// Both players agreed to a rematch; MatchFound for the new game follows
+    RematchStarted {
+        previous_game_id: String,
+        game_id: String,
     },
 
     /// Action was invalid
@@ -49,9 +120,25 @@ pub enum ServerMessage {
     /// Generic error
     Error { message: String },
 
+    /// Token was verified, the connection now acts as this player
+    Authenticated { player_id: String },
+
     /// Acknowledgment that player joined matchmaking queue
     MatchmakingJoined,
 
+    /// Acknowledgment that player left the matchmaking queue
+    MatchmakingLeft,
+
+    /// Room opened; the code is passed to the friend who should join it
+    RoomCreated { code: String },
+
+    /// Player's place in the matchmaking queue, for the waiting screen
+    MatchmakingStatus {
+        rating: Option<u32>,
+        queue_position: usize, // 1-based
+        estimated_wait_secs: Option<u32>,
+    },
+
     /// Specific error: Invalid move attempted
     InvalidMove { from: Position, to: Position },
 
@@ -71,6 +158,54 @@ pub enum ServerMessage {
     InvalidMessageFormat { details: String },
 }
 
+/// Why the server closed a connection, sent as the WebSocket Close frame's code and reason
+/// Codes are in the 4000-4999 application range so clients can tell them apart from
+/// transport-level closes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseReason {
+    /// The connection presented a token that failed verification
+    AuthenticationFailed,
+    /// The client speaks a different protocol version than the server
+    VersionMismatch,
+}
+
+impl CloseReason {
+    /// WebSocket close code
+    pub fn code(self) -> u16 {
+        match self {
+            CloseReason::AuthenticationFailed => 4001,
+            CloseReason::VersionMismatch => 4002,
+        }
+    }
+
+    /// Human-readable reason for the close frame
+    pub fn reason(self) -> &'static str {
+        match self {
+            CloseReason::AuthenticationFailed => "Authentication failed",
+            CloseReason::VersionMismatch => "Unsupported protocol version",
+        }
+    }
+}
+
+/// Structured reason a game ended, sent alongside the human-readable reason
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EndReasonCode {
+    Checkmate,
+    Stalemate,
+    InsufficientMaterial,
+    TimeOut,
+    Resignation,
+    OpponentLeft,
+    DrawAgreement,
+    Repetition,
+    FiftyMoveRule,
+    MoveLimit,
+    DurationLimit,
+    KingCaptured,
+    BothAbandoned,
+    Other,
+}
+
 /// Actions that can be performed during a game
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "action_type")]
@@ -80,25 +215,100 @@ pub enum GameAction {
         from: Position,
         to: Position,
         promotion: Option<PieceType>,
+        /// Offer a draw along with the move
+        #[serde(default)]
+        offer_draw: bool,
     },
 
+    /// Move given in SAN (e.g. "Nf3", "exd5", "O-O"), resolved against the server's board
+    MoveSan { san: String },
+
     /// Resign from the game
     Resign,
 
-    /// Offer a draw (future)
+    /// Offer a draw; the opponent receives DrawOffered
     OfferDraw,
 
-    /// Accept a draw offer (future)
+    /// Accept the opponent's pending draw offer, ending the game
     AcceptDraw,
 
-    /// Decline a draw offer (future)
+    /// Decline the opponent's pending draw offer; the offerer receives DrawDeclined
     DeclineDraw,
+
+    /// Claim a draw by threefold repetition or the fifty-move rule
+    ClaimDraw,
+
+    /// Ask the opponent to take back the last move (casual games only)
+    RequestTakeback,
+
+    /// Accept the opponent's pending takeback request, undoing their last move
+    AcceptTakeback,
+
+    /// Decline the opponent's pending takeback request; the requester receives the answer
+    DeclineTakeback,
+
+    /// Claim a draw by declaring a move that would create the repetition or reach the
+    /// fifty-move limit; the move itself is not played
+    ClaimDrawWithMove {
+        from: Position,
+        to: Position,
+        promotion: Option<PieceType>,
+    },
+
+    /// Play a card from the player's hand; `target` aims cards that take a square
+    PlayCard {
+        card_id: String,
+        #[serde(default)]
+        target: Option<Position>,
+    },
 }
 
 impl ClientMessage {
+    /// Create a hello message announcing this build's protocol version
+    pub fn hello() -> Self {
+        ClientMessage::Hello {
+            protocol_version: PROTOCOL_VERSION,
+        }
+    }
+
+    /// Create an authenticate message
+    pub fn authenticate(token: String) -> Self {
+        ClientMessage::Authenticate { token }
+    }
+
     /// Create a join matchmaking message
     pub fn join_matchmaking(player_id: String) -> Self {
-        ClientMessage::JoinMatchmaking { player_id }
+        ClientMessage::JoinMatchmaking {
+            player_id,
+            rating: None,
+            time_odds: false,
+        }
+    }
+
+    /// Create a leave matchmaking message
+    pub fn leave_matchmaking(player_id: String) -> Self {
+        ClientMessage::LeaveMatchmaking { player_id }
+    }
+
+    /// Create a play vs computer message
+    pub fn play_vs_computer(player_id: String, difficulty: u8) -> Self {
+        ClientMessage::PlayVsComputer {
+            player_id,
+            difficulty,
+        }
+    }
+
+    /// Create a create room message
+    pub fn create_room(player_id: String, time_control: Option<TimeControl>) -> Self {
+        ClientMessage::CreateRoom {
+            player_id,
+            time_control,
+        }
+    }
+
+    /// Create a join room message
+    pub fn join_room(player_id: String, code: String) -> Self {
+        ClientMessage::JoinRoom { player_id, code }
     }
 
     /// Create a submit action message
@@ -115,21 +325,64 @@ impl ClientMessage {
     pub fn request_state(game_id: String) -> Self {
         ClientMessage::RequestState { game_id }
     }
+
+    /// Create a rematch request message
+    pub fn request_rematch(game_id: String) -> Self {
+        ClientMessage::RequestRematch { game_id }
+    }
+
+    /// Create a reconnect message
+    pub fn reconnect(game_id: String, player_id: String) -> Self {
+        ClientMessage::Reconnect { game_id, player_id }
+    }
+
+    /// Create a chat message
+    pub fn send_chat(game_id: String, text: String) -> Self {
+        ClientMessage::SendChat { game_id, text }
+    }
+
+    /// Create a spectate game message
+    pub fn spectate_game(game_id: String) -> Self {
+        ClientMessage::SpectateGame { game_id }
+    }
+
+    /// Player the message claims to act as, for messages that name one
+    pub fn claimed_player_id(&self) -> Option<&str> {
+        match self {
+            ClientMessage::JoinMatchmaking { player_id, .. }
+            | ClientMessage::LeaveMatchmaking { player_id }
+            | ClientMessage::PlayVsComputer { player_id, .. }
+            | ClientMessage::CreateRoom { player_id, .. }
+            | ClientMessage::JoinRoom { player_id, .. }
+            | ClientMessage::Reconnect { player_id, .. } => Some(player_id),
+            _ => None,
+        }
+    }
 }
 
 impl ServerMessage {
     /// Create a match found message
-    pub fn match_found(game_id: String, opponent_id: String, your_color: Color) -> Self {
+    pub fn match_found(game_id: String, opponent_id: String, your_color: Color, seed: u64) -> Self {
         ServerMessage::MatchFound {
             game_id,
             opponent_id,
             your_color,
+            seed,
         }
     }
 
     /// Create a game state update message
     pub fn game_state_update(state: SerializableGameState) -> Self {
-        ServerMessage::GameStateUpdate { state }
+        ServerMessage::GameStateUpdate {
+            state: Box::new(state),
+        }
+    }
+
+    /// Create a state delta message
+    pub fn state_delta(delta: StateDelta) -> Self {
+        ServerMessage::StateDelta {
+            delta: Box::new(delta),
+        }
     }
 
     /// Create an opponent action message
@@ -138,8 +391,30 @@ impl ServerMessage {
     }
 
     /// Create a game over message
-    pub fn game_over(winner: Option<Color>, reason: String) -> Self {
-        ServerMessage::GameOver { winner, reason }
+    pub fn game_over(winner: Option<Color>, reason_code: EndReasonCode, reason: String) -> Self {
+        ServerMessage::GameOver {
+            winner,
+            reason_code,
+            reason,
+        }
+    }
+
+    /// Create a chat line notification
+    pub fn chat_message(from: String, text: String) -> Self {
+        ServerMessage::ChatMessage { from, text }
+    }
+
+    /// Create a rematch offered notification
+    pub fn rematch_offered(game_id: String) -> Self {
+        ServerMessage::RematchOffered { game_id }
+    }
+
+    /// Create a rematch started notification
+    pub fn rematch_started(previous_game_id: String, game_id: String) -> Self {
+        ServerMessage::RematchStarted {
+            previous_game_id,
+            game_id,
+        }
     }
 
     /// Create an invalid action message
@@ -152,11 +427,49 @@ impl ServerMessage {
         ServerMessage::Error { message }
     }
 
+    /// Create an authenticated acknowledgment
+    pub fn authenticated(player_id: String) -> Self {
+        ServerMessage::Authenticated { player_id }
+    }
+
+    /// Create a draw offered notification
+    pub fn draw_offered() -> Self {
+        ServerMessage::DrawOffered
+    }
+
+    /// Create a draw declined notification
+    pub fn draw_declined() -> Self {
+        ServerMessage::DrawDeclined
+    }
+
     /// Create a matchmaking joined acknowledgment
     pub fn matchmaking_joined() -> Self {
         ServerMessage::MatchmakingJoined
     }
 
+    /// Create a matchmaking left acknowledgment
+    pub fn matchmaking_left() -> Self {
+        ServerMessage::MatchmakingLeft
+    }
+
+    /// Create a room created message
+    pub fn room_created(code: String) -> Self {
+        ServerMessage::RoomCreated { code }
+    }
+
+    /// Create a matchmaking status message
+    pub fn matchmaking_status(
+        rating: Option<u32>,
+        queue_position: usize,
+        estimated_wait_secs: Option<u32>,
+    ) -> Self {
+        ServerMessage::MatchmakingStatus {
+            rating,
+            queue_position,
+            estimated_wait_secs,
+        }
+    }
+
     /// Create an invalid move error message
     pub fn invalid_move(from: Position, to: Position) -> Self {
         ServerMessage::InvalidMove { from, to }
@@ -195,11 +508,84 @@ impl GameAction {
             from,
             to,
             promotion,
+            offer_draw: false,
+        }
+    }
+
+    /// Create a move piece action that also offers a draw
+    pub fn move_piece_offering_draw(
+        from: Position,
+        to: Position,
+        promotion: Option<PieceType>,
+    ) -> Self {
+        GameAction::MovePiece {
+            from,
+            to,
+            promotion,
+            offer_draw: true,
         }
     }
 
+    /// Create a SAN move action
+    pub fn move_san(san: String) -> Self {
+        GameAction::MoveSan { san }
+    }
+
     /// Create a resign action
     pub fn resign() -> Self {
         GameAction::Resign
     }
+
+    /// Create a claim draw action
+    pub fn claim_draw() -> Self {
+        GameAction::ClaimDraw
+    }
+
+    /// Create an offer draw action
+    pub fn offer_draw() -> Self {
+        GameAction::OfferDraw
+    }
+
+    /// Create an accept draw action
+    pub fn accept_draw() -> Self {
+        GameAction::AcceptDraw
+    }
+
+    /// Create a decline draw action
+    pub fn decline_draw() -> Self {
+        GameAction::DeclineDraw
+    }
+
+    /// Create a request takeback action
+    pub fn request_takeback() -> Self {
+        GameAction::RequestTakeback
+    }
+
+    /// Create an accept takeback action
+    pub fn accept_takeback() -> Self {
+        GameAction::AcceptTakeback
+    }
+
+    /// Create a decline takeback action
+    pub fn decline_takeback() -> Self {
+        GameAction::DeclineTakeback
+    }
+
+    /// Create a claim draw action declaring the move that creates the draw
+    pub fn claim_draw_with_move(
+        from: Position,
+        to: Position,
+        promotion: Option<PieceType>,
+    ) -> Self {
+        GameAction::ClaimDrawWithMove {
+            from,
+            to,
+            promotion,
+        }
+    }
+
+    /// Create a play card action
+    pub fn play_card(card_id: String, target: Option<Position>) -> Self {
+        GameAction::PlayCard { card_id, target }
+    }
 }
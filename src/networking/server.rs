@@ -1,15 +1,60 @@
 // Game server that manages active games and player connections
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, RwLock};
+use tracing::Instrument;
+use uuid::Uuid;
 
-use crate::game::board::GameStatus;
+use crate::ai::Difficulty;
+use crate::cards::deck::Card;
+
+use crate::game::board::{Board, GameStatus};
+use crate::game::chess_clock::ChessClockSettings;
 use crate::game::game_state::ChessGame;
-use crate::game::piece::{Color, Position};
-use crate::game::rules;
+use crate::game::piece::{Color, Move, Position};
+use crate::game::{moves, rules};
+use crate::networking::auth::TokenVerifier;
 use crate::networking::matchmaking::{Match, MatchmakingQueue, WaitingPlayer};
-use crate::networking::protocol::{ClientMessage, GameAction, ServerMessage};
-use crate::networking::types::SerializableGameState;
+use crate::networking::protocol::{ClientMessage, EndReasonCode, GameAction, ServerMessage};
+use crate::networking::types::{
+    BoardState, GameResult, GameSummary, SerializableGameState, StateDelta, TimeControl,
+};
+use crate::rating::DEFAULT_RATING;
+
+/// How long a player whose connection has dropped may stay silent before forfeiting
+pub const DISCONNECT_GRACE: Duration = Duration::from_secs(60);
+
+/// Longest chat message, in characters
+pub const MAX_CHAT_LENGTH: usize = 500;
+
+/// Most chat messages a player may send within CHAT_RATE_WINDOW
+pub const CHAT_RATE_LIMIT: usize = 5;
+
+/// Window over which CHAT_RATE_LIMIT applies
+pub const CHAT_RATE_WINDOW: Duration = Duration::from_secs(10);
+
+/// Player ID of the server's AI in games against the computer
+pub const COMPUTER_PLAYER_ID: &str = "computer";
+
+/// The side of a game played by the server's AI
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComputerOpponent {
+    pub color: Color,
+    /// 0 plays the weighted casual AI, higher values search deeper (see `Difficulty::from_level`)
+    pub difficulty: u8,
+}
+
+impl ComputerOpponent {
+    /// Pick the computer's move, None if it has no legal move
+    pub fn choose_move(&self, board: &Board) -> Option<Move> {
+        Difficulty::from_level(self.difficulty).choose_move(board)
+    }
+}
 
 /// A game session on the server
 #[derive(Debug)]
@@ -20,6 +65,51 @@ pub struct ServerGame {
     pub black_player_id: String,
     pub white_sender: mpsc::UnboundedSender<ServerMessage>,
     pub black_sender: mpsc::UnboundedSender<ServerMessage>,
+    /// Include the legal moves map in state updates (for thin clients)
+    pub include_legal_moves: bool,
+    /// Color of the player with an outstanding draw offer
+    pub pending_draw_offer: Option<Color>,
+    /// Color that may not offer again until their opponent has moved
+    pub draw_offer_cooldown: Option<Color>,
+    /// Number of draw offers made per player ID
+    pub draw_offer_counts: HashMap<String, u32>,
+    /// Maximum draw offers per player per game (None = unlimited)
+    pub max_draw_offers: Option<u32>,
+    /// Color of the player with an outstanding takeback request
+    pub pending_takeback: Option<Color>,
+    /// When the game started (server clock)
+    pub started_at: Instant,
+    /// Maximum number of half-moves before the game is drawn (None = unlimited)
+    pub max_moves: Option<usize>,
+    /// Maximum wall-clock duration before the game is drawn (None = unlimited)
+    pub max_duration: Option<Duration>,
+    /// Connections watching the game (they receive state updates only)
+    pub spectators: Vec<mpsc::UnboundedSender<ServerMessage>>,
+    /// Send StateDelta messages after moves instead of full state updates
+    pub send_state_deltas: bool,
+    /// Board last sent to each connection (matched by channel), the baseline for its next delta
+    pub sent_boards: Vec<(mpsc::UnboundedSender<ServerMessage>, BoardState)>,
+    /// Seed of `rng`, derived from the game ID and sent to clients in MatchFound
+    pub rng_seed: u64,
+    /// Randomness for card and variant effects; seeded so clients can reproduce outcomes
+    /// ChaCha8 rather than StdRng, whose algorithm may change between rand versions
+    pub rng: ChaCha8Rng,
+    /// When a message was last received from each player (server clock)
+    pub last_seen: HashMap<Color, Instant>,
+    /// The side played by the server's AI, in games against the computer
+    pub computer: Option<ComputerOpponent>,
+    /// Whether the result counts for ratings; rated games don't allow takebacks
+    pub rated: bool,
+    /// Set by the first game-over, so racing terminal events can't end the game twice
+    pub finalized: bool,
+    /// Color that asked for a rematch after the game ended, awaiting the opponent
+    pub rematch_requested: Option<Color>,
+    /// Where the result goes when a rated game ends (None = not recorded)
+    pub result_log: Option<ResultLog>,
+    /// When each player sent their chat messages within the last CHAT_RATE_WINDOW
+    pub recent_chat: HashMap<Color, Vec<Instant>>,
+    /// Cards each player holds and may play on their turn
+    pub hands: HashMap<Color, Vec<Card>>,
 }
 
 impl ServerGame {
@@ -30,6 +120,8 @@ impl ServerGame {
         white_sender: mpsc::UnboundedSender<ServerMessage>,
         black_sender: mpsc::UnboundedSender<ServerMessage>,
     ) -> Self {
+        let rng_seed = seed_from_game_id(&game_id);
+        let now = Instant::now();
         Self {
             game_id,
             game: ChessGame::new(),
@@ -37,7 +129,131 @@ impl ServerGame {
             black_player_id,
             white_sender,
             black_sender,
+            include_legal_moves: false,
+            pending_draw_offer: None,
+            draw_offer_cooldown: None,
+            draw_offer_counts: HashMap::new(),
+            max_draw_offers: None,
+            pending_takeback: None,
+            started_at: now,
+            max_moves: None,
+            max_duration: None,
+            spectators: Vec::new(),
+            send_state_deltas: false,
+            sent_boards: Vec::new(),
+            rng_seed,
+            rng: ChaCha8Rng::seed_from_u64(rng_seed),
+            last_seen: HashMap::from([(Color::White, now), (Color::Black, now)]),
+            computer: None,
+            rated: false,
+            finalized: false,
+            rematch_requested: None,
+            result_log: None,
+            recent_chat: HashMap::new(),
+            hands: HashMap::new(),
+        }
+    }
+
+    /// The side that has disconnected and stayed silent past DISCONNECT_GRACE as of `now`
+    pub fn gone_player(&self, now: Instant) -> Option<Color> {
+        [Color::White, Color::Black].into_iter().find(|&color| {
+            let silent_for = self
+                .last_seen
+                .get(&color)
+                .map_or(Duration::MAX, |&seen| now.saturating_duration_since(seen));
+            self.is_disconnected(color) && silent_for >= DISCONNECT_GRACE
+        })
+    }
+
+    /// Whether the given side's connection has gone away (never true for the computer)
+    pub fn is_disconnected(&self, color: Color) -> bool {
+        if self
+            .computer
+            .is_some_and(|computer| computer.color == color)
+        {
+            return false;
+        }
+        match color {
+            Color::White => self.white_sender.is_closed(),
+            Color::Black => self.black_sender.is_closed(),
+        }
+    }
+
+    /// How the game ends if a player has abandoned it as of `now`: the opponent wins,
+    /// unless the opponent has disconnected too, in which case it's a draw
+    pub fn abandonment(
+        &self,
+        now: Instant,
+    ) -> Option<(Option<Color>, EndReasonCode, &'static str)> {
+        let gone = self.gone_player(now)?;
+        if self.is_disconnected(gone.opposite()) {
+            Some((None, EndReasonCode::BothAbandoned, "Both abandoned"))
+        } else {
+            Some((
+                Some(gone.opposite()),
+                EndReasonCode::OpponentLeft,
+                "Opponent disconnected",
+            ))
+        }
+    }
+
+    /// Check whether the game has run past its move or duration limit as of `now`
+    pub fn exceeded_limit(&self, now: Instant) -> Option<(EndReasonCode, &'static str)> {
+        if self
+            .max_moves
+            .is_some_and(|max| self.game.board().move_count() >= max)
+        {
+            return Some((EndReasonCode::MoveLimit, "Move limit reached"));
+        }
+        if self
+            .max_duration
+            .is_some_and(|max| now.saturating_duration_since(self.started_at) >= max)
+        {
+            return Some((EndReasonCode::DurationLimit, "Game duration limit reached"));
+        }
+        None
+    }
+
+    /// Charge the running side's clock for the time since its last tick, once the first
+    /// move has been played and until the game is over
+    /// Returns how the game ends if that flagged a player
+    pub fn tick_clock(
+        &mut self,
+        now: Instant,
+    ) -> Option<(Option<Color>, EndReasonCode, &'static str)> {
+        if self.finalized || !self.game.has_clock() || self.game.board().move_count() == 0 {
+            return None;
+        }
+
+        self.game.tick_clock_at(now);
+        match self.game.board().check_time_outcome()? {
+            GameStatus::TimeLoss(color) => {
+                Some((Some(color.opposite()), EndReasonCode::TimeOut, "Time out"))
+            }
+            _ => Some((None, EndReasonCode::TimeOut, "Both players out of time")),
+        }
+    }
+
+    /// Clock settings for a rematch: the same settings, with each player keeping their own
+    /// times and increments after the colors swap
+    pub fn rematch_clock(&self) -> Option<ChessClockSettings> {
+        let settings = self.game.board().clock_settings()?;
+        Some(settings.with_players_swapped())
+    }
+
+    /// Let a player waiting on a rematch know it's off because `leaving` has gone
+    pub fn cancel_rematch(&self, leaving: Color) {
+        if self.rematch_requested != Some(leaving.opposite()) {
+            return;
         }
+        let waiting_id = match leaving {
+            Color::White => &self.black_player_id,
+            Color::Black => &self.white_player_id,
+        };
+        self.send_to_player(
+            waiting_id,
+            ServerMessage::error("Rematch cancelled: opponent left".to_string()),
+        );
     }
 
     /// Get the color for a given player ID
@@ -65,7 +281,7 @@ impl ServerGame {
         let board = self.game.board();
         let status = rules::get_game_status(board);
 
-        SerializableGameState::new(
+        let state = SerializableGameState::new(
             self.game_id.clone(),
             self.white_player_id.clone(),
             self.black_player_id.clone(),
@@ -76,17 +292,186 @@ impl ServerGame {
             &self.game.board_squares(),
             board.last_action(),
         )
+        .with_checking_squares(&board.pieces_giving_check())
+        .with_last_move(board.move_history().last())
+        .with_spectator_count(self.spectators.len())
+        .with_material(
+            board.material_points(Color::White),
+            board.material_points(Color::Black),
+        )
+        .with_clock_settings(board.clock_settings())
+        .with_pending_triggers(board.pending_clock_triggers())
+        .with_cards_remaining(self.cards_remaining());
+
+        if self.include_legal_moves {
+            state.with_legal_moves(&self.game.get_legal_moves_map())
+        } else {
+            state
+        }
+    }
+
+    /// Number of cards each dealt player still holds, keyed by player ID
+    fn cards_remaining(&self) -> HashMap<String, usize> {
+        self.hands
+            .iter()
+            .map(|(&color, hand)| {
+                let player_id = match color {
+                    Color::White => self.white_player_id.clone(),
+                    Color::Black => self.black_player_id.clone(),
+                };
+                (player_id, hand.len())
+            })
+            .collect()
     }
 
-    /// Broadcast game state to both players
-    pub fn broadcast_state(&self) {
+    /// Short description of this game for game lists
+    pub fn summary(&self) -> GameSummary {
+        let board = self.game.board();
+        GameSummary {
+            game_id: self.game_id.clone(),
+            white_player_id: self.white_player_id.clone(),
+            black_player_id: self.black_player_id.clone(),
+            current_turn: board.current_turn(),
+            move_count: board.move_count(),
+            spectator_count: self.spectators.len(),
+        }
+    }
+
+    /// Whether the player is one of the two sides of this game
+    pub fn has_player(&self, player_id: &str) -> bool {
+        self.white_player_id == player_id || self.black_player_id == player_id
+    }
+
+    /// Broadcast game state to both players and any spectators
+    /// Spectators whose connection has gone away are dropped first, so the count stays accurate
+    pub fn broadcast_state(&mut self) {
+        self.spectators.retain(|spectator| !spectator.is_closed());
+
         let state = self.to_serializable_state();
-        let msg = ServerMessage::game_state_update(state);
+        self.sent_boards = self
+            .recipients()
+            .into_iter()
+            .map(|recipient| (recipient, state.board_state.clone()))
+            .collect();
+        self.send_to_all(ServerMessage::game_state_update(state));
+    }
+
+    /// Broadcast the state after a move: when deltas are enabled, each recipient gets a delta
+    /// against the board last sent to it, or a full update if it has none
+    pub fn broadcast_update(&mut self) {
+        if !self.send_state_deltas {
+            self.broadcast_state();
+            return;
+        }
+
+        self.spectators.retain(|spectator| !spectator.is_closed());
+
+        let state = self.to_serializable_state();
+        let mut sent_boards = Vec::new();
+        for recipient in self.recipients() {
+            let msg = match self.sent_board(&recipient) {
+                Some(previous) => ServerMessage::state_delta(StateDelta::new(previous, &state)),
+                None => ServerMessage::game_state_update(state.clone()),
+            };
+            let _ = recipient.send(msg);
+            sent_boards.push((recipient, state.board_state.clone()));
+        }
+        self.sent_boards = sent_boards;
+    }
+
+    /// Both players' connections followed by every spectator's
+    fn recipients(&self) -> Vec<mpsc::UnboundedSender<ServerMessage>> {
+        let mut recipients = vec![self.white_sender.clone(), self.black_sender.clone()];
+        recipients.extend(self.spectators.iter().cloned());
+        recipients
+    }
+
+    /// Board last sent to a connection, if it has one to apply a delta to
+    fn sent_board(&self, recipient: &mpsc::UnboundedSender<ServerMessage>) -> Option<&BoardState> {
+        self.sent_boards
+            .iter()
+            .find(|(sender, _)| sender.same_channel(recipient))
+            .map(|(_, board)| board)
+    }
+
+    /// Note the board sent to a connection outside a broadcast
+    fn remember_sent_board(
+        &mut self,
+        recipient: &mpsc::UnboundedSender<ServerMessage>,
+        board: BoardState,
+    ) {
+        self.sent_boards
+            .retain(|(sender, _)| !sender.same_channel(recipient));
+        self.sent_boards.push((recipient.clone(), board));
+    }
 
+    /// Send a message to both players and every spectator, dropping spectators that fail
+    fn send_to_all(&mut self, msg: ServerMessage) {
+        self.spectators
+            .retain(|spectator| spectator.send(msg.clone()).is_ok());
         let _ = self.white_sender.send(msg.clone());
         let _ = self.black_sender.send(msg);
     }
 
+    /// Mark the game as over; false if it already was, in which case the caller
+    /// must not announce or record the result again
+    pub fn finalize(&mut self) -> bool {
+        !std::mem::replace(&mut self.finalized, true)
+    }
+
+    /// End the game and announce the result to both players and any spectators, unless it
+    /// has already ended; spectators are let go once told
+    /// Returns whether this call ended the game
+    pub fn end_game(
+        &mut self,
+        winner: Option<Color>,
+        reason_code: EndReasonCode,
+        reason: &str,
+    ) -> bool {
+        if !self.finalize() {
+            return false;
+        }
+
+        tracing::info!("Game ended: {}", reason);
+        self.record_result(winner);
+        self.send_to_all(ServerMessage::game_over(
+            winner,
+            reason_code,
+            reason.to_string(),
+        ));
+        self.spectators.clear();
+        true
+    }
+
+    /// Queue the result for rating updates, if the game is rated
+    pub fn record_result(&self, winner: Option<Color>) {
+        let Some(log) = self.result_log.as_ref().filter(|_| self.rated) else {
+            return;
+        };
+        log.lock().unwrap().push(GameResult {
+            game_id: self.game_id.clone(),
+            white_player_id: self.white_player_id.clone(),
+            black_player_id: self.black_player_id.clone(),
+            winner,
+        });
+    }
+
+    /// The game's seeded RNG, to be used by any randomized card or variant effect
+    pub fn effect_rng(&mut self) -> &mut ChaCha8Rng {
+        &mut self.rng
+    }
+
+    /// Send message to the given player
+    pub fn send_to_player(&self, player_id: &str, msg: ServerMessage) {
+        let sender = if player_id == self.white_player_id {
+            &self.white_sender
+        } else {
+            &self.black_sender
+        };
+
+        let _ = sender.send(msg);
+    }
+
     /// Send message to opponent
     pub fn send_to_opponent(&self, player_id: &str, msg: ServerMessage) {
         let sender = if player_id == self.white_player_id {
@@ -99,20 +484,162 @@ impl ServerGame {
     }
 }
 
+/// Derive a game's RNG seed from its ID (64-bit FNV-1a, stable across platforms and builds)
+pub fn seed_from_game_id(game_id: &str) -> u64 {
+    game_id.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Span grouping everything logged about one game
+fn game_span(game_id: &str) -> tracing::Span {
+    tracing::info_span!("game", game_id = %game_id)
+}
+
+/// Length of the codes players share to join a room
+pub const ROOM_CODE_LENGTH: usize = 6;
+
+/// How long a room's code stays valid without the friend joining
+pub const ROOM_TIMEOUT: Duration = Duration::from_secs(600);
+
+// No 0/O or 1/I, so codes read out loud aren't mistyped
+const ROOM_CODE_CHARS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+/// A room waiting for the host's friend to join
+#[derive(Debug, Clone)]
+pub struct PendingRoom {
+    pub host: WaitingPlayer,
+    pub time_control: Option<TimeControl>,
+}
+
+impl PendingRoom {
+    /// Whether the code has outlived ROOM_TIMEOUT as of `now`, or the host has gone
+    pub fn is_expired(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.host.joined_at) >= ROOM_TIMEOUT
+            || self.host.sender.is_closed()
+    }
+}
+
+/// Results of finished rated games, waiting to be applied to the players' ratings
+pub type ResultLog = Arc<Mutex<Vec<GameResult>>>;
+
+/// Time source for the server, injectable so tests can control time
+pub type ClockFn = Arc<dyn Fn() -> Instant + Send + Sync>;
+
 /// Main game server managing all games and matchmaking
 #[derive(Clone)]
 pub struct GameServer {
     active_games: Arc<RwLock<HashMap<String, ServerGame>>>,
     matchmaking: Arc<RwLock<MatchmakingQueue>>,
     player_to_game: Arc<RwLock<HashMap<String, String>>>, // player_id -> game_id
+    rooms: Arc<RwLock<HashMap<String, PendingRoom>>>,     // join code -> room
+    color_history: Arc<RwLock<HashMap<String, ColorHistory>>>, // player_id -> colors played
+    balance_colors: Arc<AtomicBool>,
+    clock: ClockFn,
+    rng: Arc<Mutex<StdRng>>, // Used for color assignment
+    // None when player IDs are self-declared
+    token_verifier: Option<Arc<TokenVerifier>>,
+    rated_results: ResultLog,
+    player_ratings: Arc<RwLock<HashMap<String, u32>>>, // player_id -> stored rating
+    // None when connected players may wait in the queue indefinitely
+    queue_timeout: Option<Duration>,
+}
+
+/// How many games a player has been assigned each color this session
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ColorHistory {
+    pub white: u32,
+    pub black: u32,
+}
+
+impl ColorHistory {
+    /// Games as White minus games as Black
+    pub fn balance(&self) -> i64 {
+        self.white as i64 - self.black as i64
+    }
 }
 
 impl GameServer {
     pub fn new() -> Self {
+        Self::with_sources(Arc::new(Instant::now), StdRng::from_entropy())
+    }
+
+    /// Create a server with a controllable clock and a seeded rng (for deterministic tests)
+    pub fn new_for_test(clock: ClockFn, seed: u64) -> Self {
+        Self::with_sources(clock, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_sources(clock: ClockFn, rng: StdRng) -> Self {
         Self {
             active_games: Arc::new(RwLock::new(HashMap::new())),
             matchmaking: Arc::new(RwLock::new(MatchmakingQueue::new())),
             player_to_game: Arc::new(RwLock::new(HashMap::new())),
+            rooms: Arc::new(RwLock::new(HashMap::new())),
+            color_history: Arc::new(RwLock::new(HashMap::new())),
+            balance_colors: Arc::new(AtomicBool::new(true)),
+            clock,
+            rng: Arc::new(Mutex::new(rng)),
+            token_verifier: None,
+            rated_results: Arc::new(Mutex::new(Vec::new())),
+            player_ratings: Arc::new(RwLock::new(HashMap::new())),
+            queue_timeout: None,
+        }
+    }
+
+    /// Require connections to authenticate with tokens signed by this verifier
+    pub fn with_token_verifier(mut self, verifier: TokenVerifier) -> Self {
+        self.token_verifier = Some(Arc::new(verifier));
+        self
+    }
+
+    /// Remove players from the matchmaking queue once they've waited this long
+    pub fn with_queue_timeout(mut self, timeout: Duration) -> Self {
+        self.queue_timeout = Some(timeout);
+        self
+    }
+
+    /// Whether connections must authenticate before joining matchmaking
+    pub fn requires_auth(&self) -> bool {
+        self.token_verifier.is_some()
+    }
+
+    /// Player ID a token was signed for, without replying to anyone
+    pub fn verify_token(&self, token: &str) -> Result<String, String> {
+        match &self.token_verifier {
+            Some(verifier) => verifier.verify(token),
+            None => Err("Authentication is not enabled on this server".to_string()),
+        }
+    }
+
+    /// Check that a connection verified as `verified_id` may act as `player_id`
+    /// Always passes on a server that doesn't require authentication
+    pub fn check_acting_as(
+        &self,
+        verified_id: Option<&str>,
+        player_id: &str,
+    ) -> Result<(), String> {
+        if self.requires_auth() && verified_id != Some(player_id) {
+            return Err("Must authenticate as this player first".to_string());
+        }
+        Ok(())
+    }
+
+    /// Verify a connection's token, replying with Authenticated or Error
+    /// Returns the verified player ID to map onto the connection
+    pub fn authenticate(
+        &self,
+        token: &str,
+        sender: &mpsc::UnboundedSender<ServerMessage>,
+    ) -> Option<String> {
+        match self.verify_token(token) {
+            Ok(player_id) => {
+                let _ = sender.send(ServerMessage::authenticated(player_id.clone()));
+                Some(player_id)
+            }
+            Err(e) => {
+                let _ = sender.send(ServerMessage::error(e));
+                None
+            }
         }
     }
 
@@ -122,84 +649,798 @@ impl GameServer {
             active_games: Arc::clone(&self.active_games),
             matchmaking: Arc::clone(&self.matchmaking),
             player_to_game: Arc::clone(&self.player_to_game),
+            rooms: Arc::clone(&self.rooms),
+            color_history: Arc::clone(&self.color_history),
+            balance_colors: Arc::clone(&self.balance_colors),
+            clock: Arc::clone(&self.clock),
+            rng: Arc::clone(&self.rng),
+            token_verifier: self.token_verifier.clone(),
+            rated_results: Arc::clone(&self.rated_results),
+            player_ratings: Arc::clone(&self.player_ratings),
+            queue_timeout: self.queue_timeout,
         }
     }
 
-    /// Handle a client message
+    /// Current time according to the server clock
+    fn now(&self) -> Instant {
+        (self.clock)()
+    }
+
+    /// Handle a client message, traced in a span carrying the player ID
     pub async fn handle_message(
         &self,
         player_id: &str,
         message: ClientMessage,
     ) -> Result<(), String> {
+        let span = tracing::info_span!("client", player_id = %player_id);
+        self.dispatch_message(player_id, message)
+            .instrument(span)
+            .await
+    }
+
+    async fn dispatch_message(
+        &self,
+        player_id: &str,
+        message: ClientMessage,
+    ) -> Result<(), String> {
+        self.mark_seen(player_id).await;
+
         match message {
-            ClientMessage::JoinMatchmaking { player_id } => {
-                self.handle_join_matchmaking(player_id).await
+            ClientMessage::Authenticate { .. } => Err("Already authenticated".to_string()),
+            ClientMessage::JoinMatchmaking {
+                player_id: joining_id,
+                rating,
+                time_odds,
+            } => {
+                if joining_id != player_id {
+                    return Err("Can only join matchmaking as yourself".to_string());
+                }
+                self.handle_join_matchmaking(player_id, rating, time_odds)
+                    .await
+            }
+            ClientMessage::LeaveMatchmaking {
+                player_id: queued_id,
+            } => {
+                if queued_id != player_id {
+                    return Err("Can only leave matchmaking as yourself".to_string());
+                }
+                self.leave_matchmaking(player_id).await
+            }
+            ClientMessage::Hello { .. }
+            | ClientMessage::PlayVsComputer { .. }
+            | ClientMessage::CreateRoom { .. }
+            | ClientMessage::JoinRoom { .. }
+            | ClientMessage::Reconnect { .. }
+            | ClientMessage::SpectateGame { .. } => {
+                // Like joining matchmaking, these are handled in the WebSocket handler
+                Ok(())
             }
             ClientMessage::SubmitAction { game_id, action } => {
-                self.handle_submit_action(player_id, &game_id, action).await
+                self.handle_submit_action(player_id, &game_id, action)
+                    .instrument(game_span(&game_id))
+                    .await
             }
             ClientMessage::LeaveGame { game_id } => {
-                self.handle_leave_game(player_id, &game_id).await
+                self.handle_leave_game(player_id, &game_id)
+                    .instrument(game_span(&game_id))
+                    .await
             }
             ClientMessage::RequestState { game_id } => {
-                self.handle_request_state(player_id, &game_id).await
+                self.handle_request_state(player_id, &game_id)
+                    .instrument(game_span(&game_id))
+                    .await
+            }
+            ClientMessage::RequestRematch { game_id } => {
+                self.handle_request_rematch(player_id, &game_id)
+                    .instrument(game_span(&game_id))
+                    .await
+            }
+            ClientMessage::SendChat { game_id, text } => {
+                self.handle_send_chat(player_id, &game_id, text)
+                    .instrument(game_span(&game_id))
+                    .await
+            }
+        }
+    }
+
+    /// Record that a message was just received from a player, if they are in a game
+    async fn mark_seen(&self, player_id: &str) {
+        let Some(game_id) = self.find_player_game(player_id).await else {
+            return;
+        };
+        let now = self.now();
+
+        let mut games = self.active_games.write().await;
+        if let Some(game) = games.get_mut(&game_id) {
+            if let Some(color) = game.get_player_color(player_id) {
+                game.last_seen.insert(color, now);
+            }
+        }
+    }
+
+    /// When a message was last received from the given side of a game
+    pub async fn player_last_seen(&self, game_id: &str, color: Color) -> Option<Instant> {
+        let games = self.active_games.read().await;
+        games.get(game_id)?.last_seen.get(&color).copied()
+    }
+
+    /// Handle an already queued player joining again with a new rating or time odds
+    /// Joining the queue in the first place happens in the WebSocket handler
+    async fn handle_join_matchmaking(
+        &self,
+        player_id: &str,
+        rating: Option<u32>,
+        time_odds: bool,
+    ) -> Result<(), String> {
+        if self.find_player_game(player_id).await.is_some() {
+            return Err("Already in a game".to_string());
+        }
+
+        // As in add_to_matchmaking, a claimed rating only asks for rated play
+        let rating = match rating {
+            Some(_) => Some(self.stored_rating(player_id).await),
+            None => None,
+        };
+        let mut queue = self.matchmaking.write().await;
+        let player = queue
+            .update_player(player_id, rating, time_odds)
+            .ok_or_else(|| "Not in the matchmaking queue".to_string())?;
+        let sender = player.sender.clone();
+        let position = queue.queue_position(player_id).unwrap_or(1);
+        let _ = sender.send(ServerMessage::matchmaking_status(rating, position, None));
+        Ok(())
+    }
+
+    /// Add a player to the matchmaking queue (called from WebSocket handler)
+    /// A player already in an active game is refused, leaving the game untouched
+    /// A rating sent by the client only asks for rated play: it is replaced by the player's
+    /// stored rating, or the default rating for a player without one
+    pub async fn add_to_matchmaking(&self, mut player: WaitingPlayer) -> Result<(), String> {
+        let in_game = self
+            .player_to_game
+            .read()
+            .await
+            .contains_key(&player.player_id);
+        if in_game {
+            return Err("Already connected elsewhere".to_string());
+        }
+
+        if player.rating.is_some() {
+            player.rating = Some(self.stored_rating(&player.player_id).await);
+        }
+        player.joined_at = self.now();
+        let mut queue = self.matchmaking.write().await;
+        queue.add_player(player);
+        Ok(())
+    }
+
+    /// Take a player out of the matchmaking queue, replying with MatchmakingLeft
+    pub async fn leave_matchmaking(&self, player_id: &str) -> Result<(), String> {
+        let player = self
+            .matchmaking
+            .write()
+            .await
+            .take_player(player_id)
+            .ok_or_else(|| "Not in matchmaking".to_string())?;
+
+        let _ = player.sender.send(ServerMessage::matchmaking_left());
+        Ok(())
+    }
+
+    /// Open a room that a friend can join by code, replying with RoomCreated
+    /// Returns the join code
+    pub async fn create_room(
+        &self,
+        player_id: &str,
+        time_control: Option<TimeControl>,
+        sender: mpsc::UnboundedSender<ServerMessage>,
+    ) -> Result<String, String> {
+        if self.player_to_game.read().await.contains_key(player_id) {
+            return Err("Already connected elsewhere".to_string());
+        }
+        if let Some(tc) = time_control {
+            if tc.initial_seconds <= 0 || tc.increment_seconds < 0 {
+                return Err("Invalid time control".to_string());
+            }
+        }
+
+        let mut rooms = self.rooms.write().await;
+        if rooms.values().any(|room| room.host.player_id == player_id) {
+            return Err("Already hosting a room".to_string());
+        }
+
+        let code = loop {
+            let code = self.generate_room_code();
+            if !rooms.contains_key(&code) {
+                break code;
+            }
+        };
+
+        let mut host = WaitingPlayer::new(player_id.to_string(), sender.clone());
+        host.joined_at = self.now();
+        rooms.insert(code.clone(), PendingRoom { host, time_control });
+
+        let _ = sender.send(ServerMessage::room_created(code.clone()));
+        Ok(code)
+    }
+
+    /// Join a friend's room, starting the game between the two right away
+    /// Returns the new game's ID
+    pub async fn join_room(
+        &self,
+        player_id: &str,
+        code: &str,
+        sender: mpsc::UnboundedSender<ServerMessage>,
+    ) -> Result<String, String> {
+        if self.player_to_game.read().await.contains_key(player_id) {
+            return Err("Already connected elsewhere".to_string());
+        }
+
+        let code = code.trim().to_ascii_uppercase();
+        let now = self.now();
+        let room = {
+            let mut rooms = self.rooms.write().await;
+            match rooms.get(&code) {
+                None => return Err(format!("Room not found: {}", code)),
+                Some(room) if room.is_expired(now) => {
+                    rooms.remove(&code);
+                    return Err(format!("Room expired: {}", code));
+                }
+                Some(room) if room.host.player_id == player_id => {
+                    return Err("Cannot join your own room".to_string())
+                }
+                Some(_) => rooms.remove(&code).expect("room was just found"),
+            }
+        };
+
+        let mut guest = WaitingPlayer::new(player_id.to_string(), sender);
+        guest.joined_at = self.now();
+        let host_is_white = self.rng.lock().unwrap().gen_bool(0.5);
+        let (white, black) = if host_is_white {
+            (room.host, guest)
+        } else {
+            (guest, room.host)
+        };
+
+        let m = Match::new(white, black).with_time_control(room.time_control);
+        let game_id = m.game_id.clone();
+        self.create_game_from_match(m).await;
+        Ok(game_id)
+    }
+
+    /// Random join code, not checked against open rooms
+    fn generate_room_code(&self) -> String {
+        let mut rng = self.rng.lock().unwrap();
+        (0..ROOM_CODE_LENGTH)
+            .map(|_| ROOM_CODE_CHARS[rng.gen_range(0..ROOM_CODE_CHARS.len())] as char)
+            .collect()
+    }
+
+    /// Start a game between a player and the server's AI, skipping matchmaking
+    /// Colors are assigned at random; if the computer has White it moves right away
+    /// Returns the new game's ID
+    pub async fn start_computer_game(
+        &self,
+        player_id: &str,
+        difficulty: u8,
+        sender: mpsc::UnboundedSender<ServerMessage>,
+    ) -> Result<String, String> {
+        if player_id == COMPUTER_PLAYER_ID {
+            return Err("Player ID is reserved".to_string());
+        }
+        if self.player_to_game.read().await.contains_key(player_id) {
+            return Err("Already connected elsewhere".to_string());
+        }
+
+        let game_id = Uuid::new_v4().to_string();
+        let player_color = if self.rng.lock().unwrap().gen_bool(0.5) {
+            Color::White
+        } else {
+            Color::Black
+        };
+        // The computer's messages have nowhere to go
+        let (computer_sender, _) = mpsc::unbounded_channel();
+        let human = (player_id.to_string(), sender.clone());
+        let computer = (COMPUTER_PLAYER_ID.to_string(), computer_sender);
+        let ((white_id, white_sender), (black_id, black_sender)) = match player_color {
+            Color::White => (human, computer),
+            Color::Black => (computer, human),
+        };
+
+        let mut game = ServerGame::new(
+            game_id.clone(),
+            white_id,
+            black_id,
+            white_sender,
+            black_sender,
+        );
+        game.started_at = self.now();
+        game.last_seen = HashMap::from([
+            (Color::White, game.started_at),
+            (Color::Black, game.started_at),
+        ]);
+        game.computer = Some(ComputerOpponent {
+            color: player_color.opposite(),
+            difficulty,
+        });
+
+        let _ = sender.send(ServerMessage::match_found(
+            game_id.clone(),
+            COMPUTER_PLAYER_ID.to_string(),
+            player_color,
+            game.rng_seed,
+        ));
+        game.broadcast_state();
+        game_span(&game_id).in_scope(|| {
+            tracing::info!(
+                "Computer game created for {} at difficulty {}",
+                player_id,
+                difficulty
+            )
+        });
+
+        {
+            let mut games = self.active_games.write().await;
+            let mut player_map = self.player_to_game.write().await;
+            player_map.insert(player_id.to_string(), game_id.clone());
+            games.insert(game_id.clone(), game);
+        }
+
+        self.play_computer_turn(&game_id).await?;
+        Ok(game_id)
+    }
+
+    /// Make the server AI's move if it is the computer's turn in a game against it
+    /// The search runs on a blocking thread without holding the games lock, and its move is
+    /// dropped if the game changed in the meantime
+    async fn play_computer_turn(&self, game_id: &str) -> Result<(), String> {
+        let (computer, board) = {
+            let games = self.active_games.read().await;
+            let Some(game) = games.get(game_id) else {
+                return Ok(());
+            };
+            let Some(computer) = game.computer else {
+                return Ok(());
+            };
+            if game.game.is_game_over() || game.game.get_current_turn() != computer.color {
+                return Ok(());
+            }
+            (computer, game.game.board().clone())
+        };
+
+        let searched = (board.position_hash(), board.move_count());
+        let chosen = tokio::task::spawn_blocking(move || computer.choose_move(&board))
+            .await
+            .map_err(|_| "Computer search failed".to_string())?;
+        let Some(mv) = chosen else {
+            return Ok(());
+        };
+
+        let mut games = self.active_games.write().await;
+        let Some(game) = games.get_mut(game_id) else {
+            return Ok(());
+        };
+        let board = game.game.board();
+        if game.game.is_game_over() || (board.position_hash(), board.move_count()) != searched {
+            return Ok(());
+        }
+        self.process_move(game, COMPUTER_PLAYER_ID, mv.from, mv.to, mv.promotion)
+            .await
+    }
+
+    /// Build the matchmaking status for a queued player
+    /// Wait estimates aren't tracked yet, so that field is None
+    pub async fn matchmaking_status(&self, player_id: &str) -> Option<ServerMessage> {
+        let queue = self.matchmaking.read().await;
+        let position = queue.queue_position(player_id)?;
+        let rating = queue.player_rating(player_id);
+        Some(ServerMessage::matchmaking_status(rating, position, None))
+    }
+
+    /// Try to create matches from the queue
+    pub async fn try_matchmaking(&self) -> Vec<Match> {
+        let mut queue = self.matchmaking.write().await;
+        let mut rng = self.rng.lock().unwrap();
+        queue.try_create_matches_with_rng(&mut *rng)
+    }
+
+    /// Remove players who disconnected from the queue, or waited out the queue timeout if set
+    /// Returns the IDs of the pruned players
+    pub async fn prune_stale_players(&self) -> Vec<String> {
+        let mut queue = self.matchmaking.write().await;
+        let stale = queue.prune_stale(self.now(), self.queue_timeout);
+
+        stale
+            .into_iter()
+            .map(|player| {
+                let _ = player
+                    .sender
+                    .send(ServerMessage::error("Matchmaking timed out".to_string()));
+                player.player_id
+            })
+            .collect()
+    }
+
+    /// Close rooms nobody joined within ROOM_TIMEOUT, or whose host has gone
+    /// Hosts still connected are told; returns the closed rooms' codes
+    pub async fn prune_expired_rooms(&self) -> Vec<String> {
+        let mut rooms = self.rooms.write().await;
+        let now = self.now();
+
+        let expired: Vec<String> = rooms
+            .iter()
+            .filter(|(_, room)| room.is_expired(now))
+            .map(|(code, _)| code.clone())
+            .collect();
+
+        for code in &expired {
+            if let Some(room) = rooms.remove(code) {
+                let _ = room
+                    .host
+                    .sender
+                    .send(ServerMessage::error(format!("Room expired: {}", code)));
+            }
+        }
+
+        expired
+    }
+
+    /// Create a game from a match
+    pub async fn create_game_from_match(&self, mut m: Match) {
+        self.balance_match_colors(&mut m).await;
+
+        let mut game = ServerGame::new(
+            m.game_id.clone(),
+            m.white_player.player_id.clone(),
+            m.black_player.player_id.clone(),
+            m.white_player.sender.clone(),
+            m.black_player.sender.clone(),
+        );
+        game.started_at = self.now();
+        game.last_seen = HashMap::from([
+            (Color::White, game.started_at),
+            (Color::Black, game.started_at),
+        ]);
+        game.rated = m.is_rated();
+        game.result_log = Some(Arc::clone(&self.rated_results));
+        if let Some((white_seconds, black_seconds)) = m.time_odds() {
+            game.game
+                .reset_game_with_times(white_seconds, black_seconds, 0);
+        } else if let Some(tc) = m.time_control {
+            game.game
+                .reset_game_with_clock(tc.initial_seconds, tc.increment_seconds);
+        }
+
+        Self::announce_game(&mut game);
+
+        // Store game and player mappings
+        let mut games = self.active_games.write().await;
+        let mut player_map = self.player_to_game.write().await;
+
+        player_map.insert(m.white_player.player_id.clone(), m.game_id.clone());
+        player_map.insert(m.black_player.player_id.clone(), m.game_id.clone());
+        games.insert(m.game_id, game);
+    }
+
+    /// Tell both players a new game has started and send them its initial state
+    fn announce_game(game: &mut ServerGame) {
+        let _ = game.white_sender.send(ServerMessage::match_found(
+            game.game_id.clone(),
+            game.black_player_id.clone(),
+            Color::White,
+            game.rng_seed,
+        ));
+
+        let _ = game.black_sender.send(ServerMessage::match_found(
+            game.game_id.clone(),
+            game.white_player_id.clone(),
+            Color::Black,
+            game.rng_seed,
+        ));
+
+        // Send initial game state
+        game.broadcast_state();
+        game_span(&game.game_id).in_scope(|| {
+            tracing::info!(
+                "Game created: {} (White) vs {} (Black)",
+                game.white_player_id,
+                game.black_player_id
+            )
+        });
+    }
+
+    /// Swap the match's colors if that evens out the players' color history,
+    /// then record the final assignment
+    async fn balance_match_colors(&self, m: &mut Match) {
+        let mut history = self.color_history.write().await;
+
+        if self.balance_colors.load(Ordering::Relaxed) {
+            let white_balance = history
+                .get(&m.white_player.player_id)
+                .map_or(0, ColorHistory::balance);
+            let black_balance = history
+                .get(&m.black_player.player_id)
+                .map_or(0, ColorHistory::balance);
+
+            if white_balance > black_balance {
+                std::mem::swap(&mut m.white_player, &mut m.black_player);
             }
         }
+
+        Self::record_colors(
+            &mut history,
+            &m.white_player.player_id,
+            &m.black_player.player_id,
+        );
+    }
+
+    /// Count a game towards both players' color history
+    fn record_colors(history: &mut HashMap<String, ColorHistory>, white_id: &str, black_id: &str) {
+        history.entry(white_id.to_string()).or_default().white += 1;
+        history.entry(black_id.to_string()).or_default().black += 1;
+    }
+
+    /// Record a player's stored rating (e.g. loaded from the database), used for matchmaking
+    pub async fn set_player_rating(&self, player_id: &str, rating: u32) {
+        self.player_ratings
+            .write()
+            .await
+            .insert(player_id.to_string(), rating);
+    }
+
+    /// A player's stored rating, or the default rating if none has been recorded
+    pub async fn stored_rating(&self, player_id: &str) -> u32 {
+        self.player_ratings
+            .read()
+            .await
+            .get(player_id)
+            .copied()
+            .unwrap_or(DEFAULT_RATING.round() as u32)
+    }
+
+    /// Take the results of rated games that ended since the last call
+    /// Called periodically from a background task that updates the stored ratings
+    pub fn take_rated_results(&self) -> Vec<GameResult> {
+        std::mem::take(&mut *self.rated_results.lock().unwrap())
     }
 
-    /// Handle player joining matchmaking queue
-    async fn handle_join_matchmaking(&self, _player_id: String) -> Result<(), String> {
-        // Note: The actual adding to queue happens in the WebSocket handler
-        // This is just for validation
+    /// Put back results that couldn't be recorded, ahead of any that ended since, so the
+    /// next `take_rated_results` retries them in their original order
+    pub fn requeue_rated_results(&self, results: Vec<GameResult>) {
+        let mut log = self.rated_results.lock().unwrap();
+        log.splice(0..0, results);
+    }
+
+    /// Enable or disable color balancing across a session (enabled by default)
+    pub fn set_color_balancing(&self, enabled: bool) {
+        self.balance_colors.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Get how many times a player has been assigned each color
+    pub async fn color_history(&self, player_id: &str) -> ColorHistory {
+        self.color_history
+            .read()
+            .await
+            .get(player_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Add a spectator to a game; everyone is sent the state with the new spectator count
+    pub async fn add_spectator(
+        &self,
+        game_id: &str,
+        sender: mpsc::UnboundedSender<ServerMessage>,
+    ) -> Result<(), String> {
+        let mut games = self.active_games.write().await;
+        let game = games
+            .get_mut(game_id)
+            .ok_or_else(|| format!("Game not found: {}", game_id))?;
+
+        game.spectators.push(sender);
+        game.broadcast_state();
+        Ok(())
+    }
+
+    /// Swap a fresh connection in for a player whose connection dropped, within
+    /// DISCONNECT_GRACE, and replay the current state to them
+    pub async fn reconnect(
+        &self,
+        game_id: &str,
+        player_id: &str,
+        sender: mpsc::UnboundedSender<ServerMessage>,
+    ) -> Result<(), String> {
+        let mut games = self.active_games.write().await;
+        let game = games
+            .get_mut(game_id)
+            .ok_or_else(|| format!("Game not found: {}", game_id))?;
+        let color = game
+            .get_player_color(player_id)
+            .ok_or_else(|| format!("Not your game: {}", game_id))?;
+
+        if game.finalized {
+            return Err("Game is over".to_string());
+        }
+        if !game.is_disconnected(color) {
+            return Err("Player is still connected".to_string());
+        }
+
+        match color {
+            Color::White => game.white_sender = sender,
+            Color::Black => game.black_sender = sender,
+        }
+        game.last_seen.insert(color, self.now());
+
+        // The new connection has no baseline yet, so it starts from a full state
+        let state = game.to_serializable_state();
+        let sender = match color {
+            Color::White => game.white_sender.clone(),
+            Color::Black => game.black_sender.clone(),
+        };
+        game.remember_sent_board(&sender, state.board_state.clone());
+        game.send_to_player(player_id, ServerMessage::game_state_update(state));
+        game_span(game_id).in_scope(|| tracing::info!("Player {} reconnected", player_id));
+        Ok(())
+    }
+
+    /// Remove a spectator (matched by channel) from a game
+    pub async fn remove_spectator(
+        &self,
+        game_id: &str,
+        sender: &mpsc::UnboundedSender<ServerMessage>,
+    ) -> Result<(), String> {
+        let mut games = self.active_games.write().await;
+        let game = games
+            .get_mut(game_id)
+            .ok_or_else(|| format!("Game not found: {}", game_id))?;
+
+        game.spectators
+            .retain(|spectator| !spectator.same_channel(sender));
+        game.broadcast_state();
+        Ok(())
+    }
+
+    /// Set the move and duration limits for a game (None = unlimited)
+    pub async fn set_game_limits(
+        &self,
+        game_id: &str,
+        max_moves: Option<usize>,
+        max_duration: Option<Duration>,
+    ) -> Result<(), String> {
+        let mut games = self.active_games.write().await;
+        let game = games
+            .get_mut(game_id)
+            .ok_or_else(|| format!("Game not found: {}", game_id))?;
+
+        game.max_moves = max_moves;
+        game.max_duration = max_duration;
+        Ok(())
+    }
+
+    /// Give a player cards they may play during a game (e.g. the deck from a draft)
+    pub async fn deal_cards(
+        &self,
+        game_id: &str,
+        color: Color,
+        cards: Vec<Card>,
+    ) -> Result<(), String> {
+        let mut games = self.active_games.write().await;
+        let game = games
+            .get_mut(game_id)
+            .ok_or_else(|| format!("Game not found: {}", game_id))?;
+
+        game.hands.entry(color).or_default().extend(cards);
+        game.broadcast_state();
+        Ok(())
+    }
+
+    /// Choose whether a game sends state deltas after moves instead of full updates
+    pub async fn set_state_deltas(&self, game_id: &str, enabled: bool) -> Result<(), String> {
+        let mut games = self.active_games.write().await;
+        let game = games
+            .get_mut(game_id)
+            .ok_or_else(|| format!("Game not found: {}", game_id))?;
+
+        game.send_state_deltas = enabled;
         Ok(())
     }
 
-    /// Add a player to the matchmaking queue (called from WebSocket handler)
-    pub async fn add_to_matchmaking(&self, player: WaitingPlayer) -> Result<(), String> {
-        let mut queue = self.matchmaking.write().await;
-        queue.add_player(player);
+    /// Choose whether a game's state updates carry the legal moves map, for thin clients
+    /// that don't generate moves themselves
+    pub async fn set_legal_moves(&self, game_id: &str, enabled: bool) -> Result<(), String> {
+        let mut games = self.active_games.write().await;
+        let game = games
+            .get_mut(game_id)
+            .ok_or_else(|| format!("Game not found: {}", game_id))?;
+
+        game.include_legal_moves = enabled;
         Ok(())
     }
 
-    /// Try to create matches from the queue
-    pub async fn try_matchmaking(&self) -> Vec<Match> {
-        let mut queue = self.matchmaking.write().await;
-        queue.try_create_matches()
+    /// End games that ran past their move or duration limit as draws
+    /// Like any other ended game, they stay in place so the players can ask for a rematch
+    /// Called periodically from a background task; returns the IDs of the ended games
+    pub async fn sweep_game_limits(&self) -> Vec<String> {
+        let mut games = self.active_games.write().await;
+        let now = self.now();
+
+        let expired: Vec<(String, EndReasonCode, &'static str)> = games
+            .iter()
+            .filter(|(_, game)| !game.finalized)
+            .filter_map(|(game_id, game)| {
+                game.exceeded_limit(now)
+                    .map(|(code, reason)| (game_id.clone(), code, reason))
+            })
+            .collect();
+
+        for (game_id, reason_code, reason) in &expired {
+            if let Some(game) = games.get_mut(game_id) {
+                game_span(game_id).in_scope(|| game.end_game(None, *reason_code, reason));
+            }
+        }
+
+        expired.into_iter().map(|(game_id, _, _)| game_id).collect()
     }
 
-    /// Create a game from a match
-    pub async fn create_game_from_match(&self, m: Match) {
-        let game = ServerGame::new(
-            m.game_id.clone(),
-            m.white_player.player_id.clone(),
-            m.black_player.player_id.clone(),
-            m.white_player.sender.clone(),
-            m.black_player.sender.clone(),
-        );
+    /// End games a player has abandoned (disconnected and unseen for DISCONNECT_GRACE)
+    /// The opponent wins, or the game is drawn if they have disconnected too
+    /// Called periodically from a background task; returns the IDs of the ended games
+    pub async fn sweep_disconnected(&self) -> Vec<String> {
+        let mut games = self.active_games.write().await;
+        let mut player_map = self.player_to_game.write().await;
+        let now = self.now();
 
-        // Notify players that match was found
-        let _ = m.white_player.sender.send(ServerMessage::match_found(
-            m.game_id.clone(),
-            m.black_player.player_id.clone(),
-            Color::White,
-        ));
+        let abandoned: Vec<(String, Option<Color>, EndReasonCode, &'static str)> = games
+            .iter()
+            .filter_map(|(game_id, game)| {
+                game.abandonment(now)
+                    .map(|(winner, code, reason)| (game_id.clone(), winner, code, reason))
+            })
+            .collect();
 
-        let _ = m.black_player.sender.send(ServerMessage::match_found(
-            m.game_id.clone(),
-            m.white_player.player_id.clone(),
-            Color::Black,
-        ));
+        for (game_id, winner, reason_code, reason) in &abandoned {
+            if let Some(mut game) = games.remove(game_id) {
+                game_span(game_id).in_scope(|| game.end_game(*winner, *reason_code, reason));
+                if let Some(gone) = game.gone_player(now) {
+                    game.cancel_rematch(gone);
+                }
 
-        // Send initial game state
-        game.broadcast_state();
+                player_map.remove(&game.white_player_id);
+                player_map.remove(&game.black_player_id);
+            }
+        }
 
-        // Store game and player mappings
+        abandoned
+            .into_iter()
+            .map(|(game_id, _, _, _)| game_id)
+            .collect()
+    }
+
+    /// Tick the clocks of all timed games and end those where a player ran out of time
+    /// Like any other ended game, they stay in place so the players can ask for a rematch
+    /// Called once a second from a background task; returns the IDs of the ended games
+    pub async fn sweep_clocks(&self) -> Vec<String> {
+        let now = self.now();
         let mut games = self.active_games.write().await;
-        let mut player_map = self.player_to_game.write().await;
 
-        player_map.insert(m.white_player.player_id.clone(), m.game_id.clone());
-        player_map.insert(m.black_player.player_id.clone(), m.game_id.clone());
-        games.insert(m.game_id, game);
+        let flagged: Vec<(String, Option<Color>, EndReasonCode, &'static str)> = games
+            .iter_mut()
+            .filter_map(|(game_id, game)| {
+                game.tick_clock(now)
+                    .map(|(winner, code, reason)| (game_id.clone(), winner, code, reason))
+            })
+            .collect();
+
+        for (game_id, winner, reason_code, reason) in &flagged {
+            if let Some(game) = games.get_mut(game_id) {
+                game_span(game_id).in_scope(|| game.end_game(*winner, *reason_code, reason));
+            }
+        }
+
+        flagged
+            .into_iter()
+            .map(|(game_id, _, _, _)| game_id)
+            .collect()
     }
 
     /// Handle player submitting an action
@@ -216,8 +1457,30 @@ impl GameServer {
             format!("Game not found: {}", game_id)
         })?;
 
-        // Verify it's the player's turn
-        if !game.is_player_turn(player_id) {
+        // Spectators, and anyone else outside the game, can only watch
+        if !game.has_player(player_id) {
+            return Err(format!("Not your game: {}", game_id));
+        }
+
+        // A finished game only takes rematch requests, which don't come through here
+        if game.finalized {
+            let reason = "Game is over".to_string();
+            game.send_to_player(player_id, ServerMessage::invalid_action(reason.clone()));
+            return Err(reason);
+        }
+
+        // Verify it's the player's turn (draw offers, takeback requests and their answers may be
+        // made at any time)
+        let needs_turn = !matches!(
+            action,
+            GameAction::OfferDraw
+                | GameAction::AcceptDraw
+                | GameAction::DeclineDraw
+                | GameAction::RequestTakeback
+                | GameAction::AcceptTakeback
+                | GameAction::DeclineTakeback
+        );
+        if needs_turn && !game.is_player_turn(player_id) {
             let msg = ServerMessage::not_your_turn();
             if let Some(color) = game.get_player_color(player_id) {
                 let sender = if color == Color::White {
@@ -230,22 +1493,78 @@ impl GameServer {
             return Err("Not your turn".to_string());
         }
 
+        // The computer replies to moves once the games lock is released
+        let is_move = matches!(
+            action,
+            GameAction::MovePiece { .. } | GameAction::MoveSan { .. }
+        );
+
         // Process the action
-        match action {
+        let result = match action {
             GameAction::MovePiece {
                 from,
                 to,
                 promotion,
+                offer_draw,
             } => {
                 self.process_move(game, player_id, from, to, promotion)
-                    .await
+                    .await?;
+
+                // Register the offer under the same lock, unless the move ended the game
+                if offer_draw && !game.game.is_game_over() {
+                    self.process_offer_draw(game, player_id).await?;
+                }
+                Ok(())
             }
+            GameAction::MoveSan { san } => match moves::san_to_move(game.game.board(), &san) {
+                Ok(mv) => {
+                    self.process_move(game, player_id, mv.from, mv.to, mv.promotion)
+                        .await
+                }
+                Err(reason) => {
+                    if let Some(color) = game.get_player_color(player_id) {
+                        let sender = if color == Color::White {
+                            &game.white_sender
+                        } else {
+                            &game.black_sender
+                        };
+                        let _ = sender.send(ServerMessage::invalid_action(reason.clone()));
+                    }
+                    Err(reason)
+                }
+            },
             GameAction::Resign => self.process_resign(game, player_id).await,
-            GameAction::OfferDraw | GameAction::AcceptDraw | GameAction::DeclineDraw => {
-                // TODO: Implement draw offers
-                Ok(())
+            GameAction::ClaimDraw => self.process_claim_draw(game, player_id).await,
+            GameAction::ClaimDrawWithMove {
+                from,
+                to,
+                promotion,
+            } => {
+                let mv = Move {
+                    from,
+                    to,
+                    promotion,
+                };
+                self.process_claim_draw_with_move(game, player_id, mv).await
+            }
+            GameAction::OfferDraw => self.process_offer_draw(game, player_id).await,
+            GameAction::RequestTakeback => self.process_request_takeback(game, player_id).await,
+            GameAction::AcceptTakeback => self.process_accept_takeback(game, player_id).await,
+            GameAction::DeclineTakeback => self.process_decline_takeback(game, player_id).await,
+            GameAction::AcceptDraw => self.process_accept_draw(game, player_id).await,
+            GameAction::DeclineDraw => self.process_decline_draw(game, player_id).await,
+            GameAction::PlayCard { card_id, target } => {
+                self.process_play_card(game, player_id, card_id, target)
+                    .await
             }
+        };
+        drop(games);
+
+        result?;
+        if is_move {
+            self.play_computer_turn(game_id).await?;
         }
+        Ok(())
     }
 
     /// Process a move action
@@ -257,18 +1576,18 @@ impl GameServer {
         to: Position,
         promotion: Option<crate::game::piece::PieceType>,
     ) -> Result<(), String> {
-        // Select the piece first
-        game.game.select_piece(from.row, from.col);
-
-        // Try to move (with or without promotion)
-        let success = if let Some(promo) = promotion {
-            game.game
-                .try_move_selected_with_promotion(to.row, to.col, promo)
-        } else {
-            game.game.try_move_selected(to.row, to.col)
-        };
-
-        if !success {
+        // Clocks don't run before the first move, so White isn't charged for the wait
+        let now = self.now();
+        if game.game.board().move_count() == 0 {
+            game.game.restart_clock_at(now);
+        }
+        if game.game.apply_move_at(from, to, promotion, now).is_err() {
+            tracing::debug!(
+                "Rejected illegal move {}{} from {}",
+                from.to_algebraic(),
+                to.to_algebraic(),
+                player_id
+            );
             let msg = ServerMessage::invalid_move(from, to);
             if let Some(color) = game.get_player_color(player_id) {
                 let sender = if color == Color::White {
@@ -280,30 +1599,112 @@ impl GameServer {
             }
             return Err("Illegal move".to_string());
         }
+        tracing::debug!(
+            "{} played {}{}",
+            player_id,
+            from.to_algebraic(),
+            to.to_algebraic()
+        );
+
+        // Any move implicitly declines a pending offer or takeback request (an offer made with
+        // this move is registered afterwards), and the opponent's cooldown ends once we've moved
+        game.pending_draw_offer = None;
+        game.pending_takeback = None;
+        if let Some(color) = game.get_player_color(player_id) {
+            if game.draw_offer_cooldown == Some(color.opposite()) {
+                game.draw_offer_cooldown = None;
+            }
+        }
 
         // Notify opponent of the move
         let action = GameAction::move_piece(from, to, promotion);
         game.send_to_opponent(player_id, ServerMessage::opponent_action(action));
 
         // Broadcast updated game state
-        game.broadcast_state();
+        game.broadcast_update();
+
+        Self::end_game_if_decided(game);
+        Ok(())
+    }
 
-        // Check if game is over
+    /// End the game if the position on the board decides it (mate, draw rules, time, ...)
+    fn end_game_if_decided(game: &mut ServerGame) {
         let status = rules::get_game_status(game.game.board());
         if !matches!(status, GameStatus::Ongoing | GameStatus::Check) {
-            let (winner, reason) = match status {
-                GameStatus::Checkmate(color) => (Some(color), "Checkmate".to_string()),
-                GameStatus::Stalemate => (None, "Stalemate".to_string()),
-                GameStatus::DrawInsufficientMaterial => (None, "Insufficient material".to_string()),
-                GameStatus::TimeLoss(color) => (Some(color.opposite()), "Time out".to_string()),
-                _ => (None, "Game over".to_string()),
+            let (winner, reason_code, reason) = match status {
+                GameStatus::Checkmate(color) => {
+                    (Some(color), EndReasonCode::Checkmate, "Checkmate")
+                }
+                GameStatus::Stalemate => (None, EndReasonCode::Stalemate, "Stalemate"),
+                GameStatus::DrawInsufficientMaterial => (
+                    None,
+                    EndReasonCode::InsufficientMaterial,
+                    "Insufficient material",
+                ),
+                GameStatus::TimeLoss(color) => {
+                    (Some(color.opposite()), EndReasonCode::TimeOut, "Time out")
+                }
+                GameStatus::DrawRepetition => {
+                    (None, EndReasonCode::Repetition, "Fivefold repetition")
+                }
+                GameStatus::DrawFiftyMove => {
+                    (None, EndReasonCode::FiftyMoveRule, "Fifty-move rule")
+                }
+                GameStatus::DrawTimeout => {
+                    (None, EndReasonCode::TimeOut, "Both players out of time")
+                }
+                GameStatus::KingCaptured(color) => {
+                    (Some(color), EndReasonCode::KingCaptured, "King captured")
+                }
+                _ => (None, EndReasonCode::Other, "Game over"),
             };
 
-            let msg = ServerMessage::game_over(winner, reason);
-            let _ = game.white_sender.send(msg.clone());
-            let _ = game.black_sender.send(msg);
+            game.end_game(winner, reason_code, reason);
+        }
+    }
+
+    /// Process a card play: the card must be in the player's hand and its effect must apply
+    /// Only a successful play uses up the card
+    async fn process_play_card(
+        &self,
+        game: &mut ServerGame,
+        player_id: &str,
+        card_id: String,
+        target: Option<Position>,
+    ) -> Result<(), String> {
+        let color = game
+            .get_player_color(player_id)
+            .ok_or_else(|| format!("Not your game: {}", game.game_id))?;
+        let card = game
+            .hands
+            .get(&color)
+            .and_then(|hand| hand.iter().find(|card| card.id == card_id));
+        let result = match card {
+            Some(card) => card
+                .effect
+                .played_by(color)
+                .with_target(target)
+                .and_then(|effect| game.game.apply_card_effect(&effect))
+                .map_err(|e| e.to_string()),
+            None => Err(format!("Card not in hand: {}", card_id)),
+        };
+        if let Err(reason) = result {
+            game.send_to_player(player_id, ServerMessage::invalid_action(reason.clone()));
+            return Err(reason);
+        }
+
+        if let Some(hand) = game.hands.get_mut(&color) {
+            if let Some(index) = hand.iter().position(|card| card.id == card_id) {
+                hand.remove(index);
+            }
         }
+        tracing::debug!("{} played card {}", player_id, card_id);
+
+        let action = GameAction::play_card(card_id, target);
+        game.send_to_opponent(player_id, ServerMessage::opponent_action(action));
+        game.broadcast_state();
 
+        Self::end_game_if_decided(game);
         Ok(())
     }
 
@@ -315,20 +1716,241 @@ impl GameServer {
             Some(Color::White)
         };
 
-        let msg = ServerMessage::game_over(winner, "Resignation".to_string());
-        let _ = game.white_sender.send(msg.clone());
-        let _ = game.black_sender.send(msg);
+        game.end_game(winner, EndReasonCode::Resignation, "Resignation");
+        Ok(())
+    }
+
+    /// Process a draw offer, rejecting spam
+    /// A player may have one pending offer and must wait for the opponent to move before offering again
+    async fn process_offer_draw(
+        &self,
+        game: &mut ServerGame,
+        player_id: &str,
+    ) -> Result<(), String> {
+        let color = game
+            .get_player_color(player_id)
+            .ok_or_else(|| format!("Not your game: {}", game.game_id))?;
+        let count = game.draw_offer_counts.get(player_id).copied().unwrap_or(0);
+
+        let rejection = if game.draw_offer_cooldown == Some(color) {
+            Some("Draw offer on cooldown")
+        } else if game.pending_draw_offer.is_some() {
+            Some("Draw offer already pending")
+        } else if game.max_draw_offers.is_some_and(|max| count >= max) {
+            Some("Draw offer limit reached")
+        } else {
+            None
+        };
+
+        if let Some(reason) = rejection {
+            game.send_to_player(player_id, ServerMessage::invalid_action(reason.to_string()));
+            return Err(reason.to_string());
+        }
+
+        game.pending_draw_offer = Some(color);
+        game.draw_offer_cooldown = Some(color);
+        game.draw_offer_counts
+            .insert(player_id.to_string(), count + 1);
+
+        game.send_to_opponent(player_id, ServerMessage::draw_offered());
+
+        Ok(())
+    }
+
+    /// The color whose draw offer `player_id` may answer, or an error if there is none
+    fn answerable_draw_offer(game: &ServerGame, player_id: &str) -> Result<Color, String> {
+        let color = game
+            .get_player_color(player_id)
+            .ok_or_else(|| format!("Not your game: {}", game.game_id))?;
+
+        match game.pending_draw_offer {
+            Some(offerer) if offerer == color.opposite() => Ok(offerer),
+            _ => {
+                let reason = "No draw offer to answer".to_string();
+                game.send_to_player(player_id, ServerMessage::invalid_action(reason.clone()));
+                Err(reason)
+            }
+        }
+    }
+
+    /// Process the acceptance of the opponent's draw offer
+    async fn process_accept_draw(
+        &self,
+        game: &mut ServerGame,
+        player_id: &str,
+    ) -> Result<(), String> {
+        Self::answerable_draw_offer(game, player_id)?;
+
+        game.pending_draw_offer = None;
+        game.end_game(None, EndReasonCode::DrawAgreement, "Draw by agreement");
+        Ok(())
+    }
+
+    /// Process the refusal of the opponent's draw offer, letting the offerer know
+    async fn process_decline_draw(
+        &self,
+        game: &mut ServerGame,
+        player_id: &str,
+    ) -> Result<(), String> {
+        Self::answerable_draw_offer(game, player_id)?;
+
+        game.pending_draw_offer = None;
+        game.send_to_opponent(player_id, ServerMessage::draw_declined());
+        Ok(())
+    }
+
+    /// Process a takeback request by forwarding it to the opponent
+    /// Rated and computer games refuse takebacks outright, and a player may have one pending
+    /// request for a move of their own
+    async fn process_request_takeback(
+        &self,
+        game: &mut ServerGame,
+        player_id: &str,
+    ) -> Result<(), String> {
+        let color = game
+            .get_player_color(player_id)
+            .ok_or_else(|| format!("Not your game: {}", game.game_id))?;
+        let rejection = if game.rated {
+            Some("Takebacks not allowed in rated games")
+        } else if game.computer.is_some() {
+            Some("No takebacks against the computer")
+        } else if !game.game.board().has_moved(color) {
+            Some("No move to take back")
+        } else if game.pending_takeback.is_some() {
+            Some("Takeback already pending")
+        } else {
+            None
+        };
+
+        if let Some(reason) = rejection {
+            game.send_to_player(player_id, ServerMessage::invalid_action(reason.to_string()));
+            return Err(reason.to_string());
+        }
+
+        game.pending_takeback = Some(color);
+        game.send_to_opponent(
+            player_id,
+            ServerMessage::opponent_action(GameAction::RequestTakeback),
+        );
+        Ok(())
+    }
+
+    /// The color whose takeback request `player_id` may answer, or an error if there is none
+    fn answerable_takeback(game: &ServerGame, player_id: &str) -> Result<Color, String> {
+        let color = game
+            .get_player_color(player_id)
+            .ok_or_else(|| format!("Not your game: {}", game.game_id))?;
+
+        match game.pending_takeback {
+            Some(requester) if requester == color.opposite() => Ok(requester),
+            _ => {
+                let reason = "No takeback request to answer".to_string();
+                game.send_to_player(player_id, ServerMessage::invalid_action(reason.clone()));
+                Err(reason)
+            }
+        }
+    }
+
+    /// Process the acceptance of the opponent's takeback request
+    /// Undoes the requester's last move and anything played after it
+    async fn process_accept_takeback(
+        &self,
+        game: &mut ServerGame,
+        player_id: &str,
+    ) -> Result<(), String> {
+        let requester = Self::answerable_takeback(game, player_id)?;
+
+        game.pending_takeback = None;
+        while game.game.board().has_moved(requester) {
+            let requesters_move = game.game.board().last_mover() == Some(requester);
+            game.game.undo_move();
+            if requesters_move {
+                break;
+            }
+        }
+
+        game.send_to_opponent(
+            player_id,
+            ServerMessage::opponent_action(GameAction::AcceptTakeback),
+        );
+        game.broadcast_state();
+        Ok(())
+    }
+
+    /// Process the refusal of the opponent's takeback request, letting the requester know
+    async fn process_decline_takeback(
+        &self,
+        game: &mut ServerGame,
+        player_id: &str,
+    ) -> Result<(), String> {
+        Self::answerable_takeback(game, player_id)?;
+
+        game.pending_takeback = None;
+        game.send_to_opponent(
+            player_id,
+            ServerMessage::opponent_action(GameAction::DeclineTakeback),
+        );
+        Ok(())
+    }
+
+    /// Process a draw claim (threefold repetition or fifty-move rule)
+    async fn process_claim_draw(
+        &self,
+        game: &mut ServerGame,
+        player_id: &str,
+    ) -> Result<(), String> {
+        let board = game.game.board();
+        if !board.can_claim_draw() {
+            let reason = "No draw to claim".to_string();
+            game.send_to_player(player_id, ServerMessage::invalid_action(reason.clone()));
+            return Err(reason);
+        }
+
+        let by_repetition = board.repetition_count() >= 3;
+        Self::end_in_claimed_draw(game, by_repetition);
+        Ok(())
+    }
+
+    /// Process a draw claim declaring the move that would create the draw
+    /// The move is tried on a copy of the board and never played
+    async fn process_claim_draw_with_move(
+        &self,
+        game: &mut ServerGame,
+        player_id: &str,
+        mv: Move,
+    ) -> Result<(), String> {
+        let mut board = game.game.board().clone();
+        let claimable = rules::check_move_legality(&board, mv).is_ok()
+            && board.make_move(mv)
+            && board.can_claim_draw();
+        if !claimable {
+            let reason = "Declared move does not create a draw to claim".to_string();
+            game.send_to_player(player_id, ServerMessage::invalid_action(reason.clone()));
+            return Err(reason);
+        }
 
+        Self::end_in_claimed_draw(game, board.repetition_count() >= 3);
         Ok(())
     }
 
+    /// Announce a claimed draw by repetition or, failing that, the fifty-move rule
+    fn end_in_claimed_draw(game: &mut ServerGame, by_repetition: bool) {
+        let (reason_code, reason) = if by_repetition {
+            (EndReasonCode::Repetition, "Threefold repetition")
+        } else {
+            (EndReasonCode::FiftyMoveRule, "Fifty-move rule")
+        };
+
+        game.end_game(None, reason_code, reason);
+    }
+
     /// Handle player leaving a game
     async fn handle_leave_game(&self, player_id: &str, game_id: &str) -> Result<(), String> {
         let mut games = self.active_games.write().await;
         let mut player_map = self.player_to_game.write().await;
 
         // Extract player IDs before removing the game
-        if let Some(game) = games.get(game_id) {
+        if let Some(game) = games.get_mut(game_id) {
             let white_id = game.white_player_id.clone();
             let black_id = game.black_player_id.clone();
 
@@ -339,8 +1961,19 @@ impl GameServer {
                 Some(Color::White)
             };
 
-            let msg = ServerMessage::game_over(winner, "Opponent left".to_string());
-            game.send_to_opponent(player_id, msg);
+            // Leaving a game that has already ended doesn't change its result
+            if game.finalize() {
+                game.record_result(winner);
+                let msg = ServerMessage::game_over(
+                    winner,
+                    EndReasonCode::OpponentLeft,
+                    "Opponent left".to_string(),
+                );
+                game.send_to_opponent(player_id, msg);
+            }
+            if let Some(color) = game.get_player_color(player_id) {
+                game.cancel_rematch(color);
+            }
 
             // Now remove game (after we're done with references to it)
             games.remove(game_id);
@@ -351,6 +1984,135 @@ impl GameServer {
         Ok(())
     }
 
+    /// Handle a rematch request for a finished game
+    /// The first request is forwarded to the opponent; once both have asked, the game is
+    /// replaced by a new one with colors swapped and the same clock settings
+    async fn handle_request_rematch(&self, player_id: &str, game_id: &str) -> Result<(), String> {
+        let mut games = self.active_games.write().await;
+        let mut player_map = self.player_to_game.write().await;
+
+        let game = games
+            .get_mut(game_id)
+            .ok_or_else(|| format!("Game not found: {}", game_id))?;
+        let color = game
+            .get_player_color(player_id)
+            .ok_or_else(|| format!("Not your game: {}", game_id))?;
+
+        let rejection = if !game.finalized {
+            Some("Game is still in progress")
+        } else if game.computer.is_some() {
+            Some("No rematches against the computer")
+        } else if game.is_disconnected(color.opposite()) {
+            Some("Opponent has left")
+        } else if game.rematch_requested == Some(color) {
+            Some("Rematch already requested")
+        } else {
+            None
+        };
+
+        if let Some(reason) = rejection {
+            game.send_to_player(player_id, ServerMessage::invalid_action(reason.to_string()));
+            return Err(reason.to_string());
+        }
+
+        if game.rematch_requested.is_none() {
+            game.rematch_requested = Some(color);
+            game.send_to_opponent(
+                player_id,
+                ServerMessage::rematch_offered(game_id.to_string()),
+            );
+            return Ok(());
+        }
+
+        // Both players agreed: the old game makes way for the new one
+        let Some(old) = games.remove(game_id) else {
+            return Err(format!("Game not found: {}", game_id));
+        };
+        let mut rematch = ServerGame::new(
+            Uuid::new_v4().to_string(),
+            old.black_player_id.clone(),
+            old.white_player_id.clone(),
+            old.black_sender.clone(),
+            old.white_sender.clone(),
+        );
+        rematch.started_at = self.now();
+        rematch.last_seen = HashMap::from([
+            (Color::White, rematch.started_at),
+            (Color::Black, rematch.started_at),
+        ]);
+        rematch.rated = old.rated;
+        rematch.result_log = Some(Arc::clone(&self.rated_results));
+        rematch.include_legal_moves = old.include_legal_moves;
+        rematch.send_state_deltas = old.send_state_deltas;
+        rematch.max_draw_offers = old.max_draw_offers;
+        rematch.max_moves = old.max_moves;
+        rematch.max_duration = old.max_duration;
+        if let Some(clock_settings) = old.rematch_clock() {
+            rematch.game.reset_game_with_clock_settings(clock_settings);
+        }
+
+        Self::record_colors(
+            &mut *self.color_history.write().await,
+            &rematch.white_player_id,
+            &rematch.black_player_id,
+        );
+
+        let started = ServerMessage::rematch_started(game_id.to_string(), rematch.game_id.clone());
+        let _ = rematch.white_sender.send(started.clone());
+        let _ = rematch.black_sender.send(started);
+        Self::announce_game(&mut rematch);
+
+        player_map.insert(rematch.white_player_id.clone(), rematch.game_id.clone());
+        player_map.insert(rematch.black_player_id.clone(), rematch.game_id.clone());
+        games.insert(rematch.game_id.clone(), rematch);
+
+        Ok(())
+    }
+
+    /// Forward a player's chat message to their opponent and any spectators
+    /// Overlong messages and players over CHAT_RATE_LIMIT are refused
+    async fn handle_send_chat(
+        &self,
+        player_id: &str,
+        game_id: &str,
+        text: String,
+    ) -> Result<(), String> {
+        let mut games = self.active_games.write().await;
+        let game = games
+            .get_mut(game_id)
+            .ok_or_else(|| format!("Game not found: {}", game_id))?;
+        let color = game
+            .get_player_color(player_id)
+            .ok_or_else(|| format!("Not your game: {}", game_id))?;
+
+        let now = self.now();
+        let recent = game.recent_chat.entry(color).or_default();
+        recent.retain(|&sent| now.saturating_duration_since(sent) < CHAT_RATE_WINDOW);
+
+        let text = text.trim().to_string();
+        let rejection = if text.is_empty() {
+            Some("Chat message is empty")
+        } else if text.chars().count() > MAX_CHAT_LENGTH {
+            Some("Chat message too long")
+        } else if recent.len() >= CHAT_RATE_LIMIT {
+            Some("Sending chat messages too quickly")
+        } else {
+            None
+        };
+
+        if let Some(reason) = rejection {
+            game.send_to_player(player_id, ServerMessage::invalid_action(reason.to_string()));
+            return Err(reason.to_string());
+        }
+        recent.push(now);
+
+        let msg = ServerMessage::chat_message(player_id.to_string(), text);
+        game.spectators
+            .retain(|spectator| spectator.send(msg.clone()).is_ok());
+        game.send_to_opponent(player_id, msg);
+        Ok(())
+    }
+
     /// Handle request for game state
     async fn handle_request_state(&self, player_id: &str, game_id: &str) -> Result<(), String> {
         let games = self.active_games.read().await;
@@ -390,6 +2152,31 @@ impl GameServer {
         Ok(())
     }
 
+    /// Find the game a player is currently in, if any
+    pub async fn find_player_game(&self, player_id: &str) -> Option<String> {
+        self.player_to_game.read().await.get(player_id).cloned()
+    }
+
+    /// Summaries of all active games, sorted by game ID
+    pub async fn list_games(&self) -> Vec<GameSummary> {
+        let games = self.active_games.read().await;
+        let mut summaries: Vec<GameSummary> = games.values().map(ServerGame::summary).collect();
+        summaries.sort_by(|a, b| a.game_id.cmp(&b.game_id));
+        summaries
+    }
+
+    /// Summaries of the active games a player is part of, sorted by game ID
+    pub async fn games_with_player(&self, player_id: &str) -> Vec<GameSummary> {
+        let games = self.active_games.read().await;
+        let mut summaries: Vec<GameSummary> = games
+            .values()
+            .filter(|game| game.has_player(player_id))
+            .map(ServerGame::summary)
+            .collect();
+        summaries.sort_by(|a, b| a.game_id.cmp(&b.game_id));
+        summaries
+    }
+
     /// Get the number of active games
     pub async fn active_game_count(&self) -> usize {
         self.active_games.read().await.len()
@@ -1,24 +1,57 @@
 // Network-compatible types for serialization
 use crate::game::board::GameStatus;
-use crate::game::piece::{Color, GameAction, Piece, PieceType, Position};
+use crate::game::chess_clock::{ChessClockSettings, TriggerType};
+use crate::game::piece::{Color, GameAction, Move, Piece, PieceType, Position};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// Represents a single piece's state on the board
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PieceState {
     pub id: u8,
     pub position: String, // algebraic notation (e.g., "e4")
     pub piece_type: String, // "pawn", "knight", "bishop", "rook", "queen", "king"
+    pub color: Color,
 }
 
 /// ID-based board representation: list of all pieces with their IDs and positions
-/// Color can be inferred from ID: 0-15 = White, 16-31 = Black
+/// Each piece carries its color: IDs 0-15 start White and 16-31 Black, but promoted pieces get IDs from 32 up
 pub type BoardState = Vec<PieceState>;
 
+/// Difference between two board states: pieces that are new or changed, and IDs that are gone
+pub fn board_delta(previous: &BoardState, current: &BoardState) -> (Vec<PieceState>, Vec<u8>) {
+    let moved = current
+        .iter()
+        .filter(|piece| !previous.contains(piece))
+        .cloned()
+        .collect();
+    let removed = previous
+        .iter()
+        .filter(|old| !current.iter().any(|piece| piece.id == old.id))
+        .map(|old| old.id)
+        .collect();
+
+    (moved, removed)
+}
+
 /// Time representation: player_id -> seconds_remaining
 pub type TimeState = HashMap<String, i32>;
 
+/// Clock for a game set up by the players themselves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeControl {
+    pub initial_seconds: i32,
+    #[serde(default)]
+    pub increment_seconds: i32,
+}
+
+/// Material points each side has on the board, for the scoreboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaterialPoints {
+    pub white: i32,
+    pub black: i32,
+}
+
 /// Serializable version of game state for network transmission
 /// Uses ID-based piece tracking for client-side animation and reconciliation
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +62,78 @@ pub struct SerializableGameState {
     pub status: GameStatus,
     pub game_id: String,
     pub last_action: Option<GameAction>, // The action that led to this state (for animation)
+    /// From and to squares (algebraic) of the last move, for simple highlighting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_move: Option<(String, String)>,
+    /// Legal destinations per movable piece (algebraic), only sent to thin clients that need it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub legal_moves: Option<HashMap<String, Vec<String>>>,
+    /// Squares (algebraic) of the pieces giving check to the side to move
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checking_squares: Vec<String>,
+    /// Number of spectators watching the game
+    #[serde(default)]
+    pub spectator_count: usize,
+    /// Material points of each side's surviving pieces
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub material: Option<MaterialPoints>,
+    /// Clock configuration (increments and triggers), so reconnecting clients can show future bonuses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clock_settings: Option<Box<ChessClockSettings>>,
+    /// Clock triggers that haven't fired yet: (type, threshold, increment in seconds)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pending_triggers: Vec<(TriggerType, f32, i32)>,
+    /// Number of cards each player still holds: player_id -> count (empty without cards)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub cards_remaining: HashMap<String, usize>,
+}
+
+/// Changes to a game state since the last one sent to a client: the pieces that moved or
+/// left the board, and every other field that can change from one update to the next
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateDelta {
+    pub moved: Vec<PieceState>, // Pieces that appeared or changed square
+    pub removed: Vec<u8>,       // IDs of pieces that left the board
+    pub next_player_id: String,
+    pub status: GameStatus,
+    pub time: TimeState,
+    pub last_action: Option<GameAction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_move: Option<(String, String)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub legal_moves: Option<HashMap<String, Vec<String>>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checking_squares: Vec<String>,
+    #[serde(default)]
+    pub spectator_count: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub material: Option<MaterialPoints>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pending_triggers: Vec<(TriggerType, f32, i32)>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub cards_remaining: HashMap<String, usize>,
+}
+
+impl StateDelta {
+    /// Delta that takes a client from `previous`, the board it was last sent, to `state`
+    pub fn new(previous: &BoardState, state: &SerializableGameState) -> Self {
+        let (moved, removed) = board_delta(previous, &state.board_state);
+        Self {
+            moved,
+            removed,
+            next_player_id: state.next_player_id.clone(),
+            status: state.status,
+            time: state.time.clone(),
+            last_action: state.last_action.clone(),
+            last_move: state.last_move.clone(),
+            legal_moves: state.legal_moves.clone(),
+            checking_squares: state.checking_squares.clone(),
+            spectator_count: state.spectator_count,
+            material: state.material,
+            pending_triggers: state.pending_triggers.clone(),
+            cards_remaining: state.cards_remaining.clone(),
+        }
+    }
 }
 
 impl SerializableGameState {
@@ -66,9 +171,97 @@ impl SerializableGameState {
             status,
             game_id,
             last_action,
+            last_move: None,
+            legal_moves: None,
+            checking_squares: Vec::new(),
+            spectator_count: 0,
+            material: None,
+            clock_settings: None,
+            pending_triggers: Vec::new(),
+            cards_remaining: HashMap::new(),
         }
     }
 
+    /// Attach the legal moves map (from -> destinations) in algebraic notation
+    pub fn with_legal_moves(mut self, legal_moves: &HashMap<Position, Vec<Position>>) -> Self {
+        let legal_moves = legal_moves
+            .iter()
+            .map(|(from, destinations)| {
+                (
+                    from.to_algebraic(),
+                    destinations.iter().map(|to| to.to_algebraic()).collect(),
+                )
+            })
+            .collect();
+
+        self.legal_moves = Some(legal_moves);
+        self
+    }
+
+    /// Attach the squares of the pieces giving check in algebraic notation
+    pub fn with_checking_squares(mut self, checking_squares: &[Position]) -> Self {
+        self.checking_squares = checking_squares
+            .iter()
+            .map(|pos| pos.to_algebraic())
+            .collect();
+        self
+    }
+
+    /// Attach the number of spectators watching
+    pub fn with_spectator_count(mut self, spectator_count: usize) -> Self {
+        self.spectator_count = spectator_count;
+        self
+    }
+
+    /// Attach both sides' material points
+    pub fn with_material(mut self, white: i32, black: i32) -> Self {
+        self.material = Some(MaterialPoints { white, black });
+        self
+    }
+
+    /// Attach the last move's squares in algebraic notation
+    pub fn with_last_move(mut self, last_move: Option<&Move>) -> Self {
+        self.last_move = last_move.map(|mv| (mv.from.to_algebraic(), mv.to.to_algebraic()));
+        self
+    }
+
+    /// Attach the clock configuration
+    pub fn with_clock_settings(mut self, clock_settings: Option<&ChessClockSettings>) -> Self {
+        self.clock_settings = clock_settings.cloned().map(Box::new);
+        self
+    }
+
+    /// Attach the clock triggers that are still to fire
+    pub fn with_pending_triggers(mut self, pending_triggers: Vec<(TriggerType, f32, i32)>) -> Self {
+        self.pending_triggers = pending_triggers;
+        self
+    }
+
+    /// Attach how many cards each player still holds
+    pub fn with_cards_remaining(mut self, cards_remaining: HashMap<String, usize>) -> Self {
+        self.cards_remaining = cards_remaining;
+        self
+    }
+
+    /// Apply a state delta received from the server on top of this state
+    pub fn apply_delta(&mut self, delta: &StateDelta) {
+        self.board_state.retain(|piece| {
+            !delta.removed.contains(&piece.id) && !delta.moved.iter().any(|m| m.id == piece.id)
+        });
+        self.board_state.extend(delta.moved.iter().cloned());
+        self.next_player_id = delta.next_player_id.clone();
+        self.status = delta.status;
+        self.time = delta.time.clone();
+        self.last_action = delta.last_action.clone();
+        self.last_move = delta.last_move.clone();
+        self.legal_moves = delta.legal_moves.clone();
+        self.checking_squares = delta.checking_squares.clone();
+        self.spectator_count = delta.spectator_count;
+        self.material = delta.material;
+        self.pending_triggers = delta.pending_triggers.clone();
+        self.cards_remaining = delta.cards_remaining.clone();
+    }
+
     /// Convert board squares to ID-based format
     /// Returns a list of all pieces with their IDs, positions, and types
     fn squares_to_id_based(squares: &[[Option<Piece>; 8]; 8]) -> BoardState {
@@ -82,6 +275,7 @@ impl SerializableGameState {
                         id: piece.id,
                         position: pos.to_algebraic(),
                         piece_type: Self::piece_type_to_string(piece.piece_type),
+                        color: piece.color,
                     });
                 }
             }
@@ -102,3 +296,23 @@ impl SerializableGameState {
         }
     }
 }
+
+/// Outcome of a finished rated game, queued for rating updates
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameResult {
+    pub game_id: String,
+    pub white_player_id: String,
+    pub black_player_id: String,
+    pub winner: Option<Color>, // None = draw
+}
+
+/// Short description of an active game, for game lists and spectating
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameSummary {
+    pub game_id: String,
+    pub white_player_id: String,
+    pub black_player_id: String,
+    pub current_turn: Color,
+    pub move_count: usize,
+    pub spectator_count: usize,
+}
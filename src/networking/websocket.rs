@@ -41,7 +41,7 @@ pub async fn handle_websocket(ws: WebSocket, server: GameServer) {
                             // Extract player_id from the message if we don't have it yet
                             if player_id.is_none() {
                                 if let ClientMessage::JoinMatchmaking {
-                                    player_id: ref pid,
+                                    player_id: ref pid, ..
                                 } = client_msg
                                 {
                                     player_id = Some(pid.clone());
@@ -0,0 +1,94 @@
+// Elo rating updates
+// Pure functions, independent of the AI and networking modules
+
+use crate::game::piece::Color;
+
+/// Rating of a player with no rated games yet (matches the users table default)
+pub const DEFAULT_RATING: f64 = 1000.0;
+
+/// Largest rating change a single game can cause
+pub const DEFAULT_K_FACTOR: f64 = 32.0;
+
+/// Expected score (0 to 1) of a player rated `rating` against one rated `opponent`
+pub fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+/// New ratings of a game's winner and loser, in that order
+pub fn update_elo(winner: f64, loser: f64, k: f64) -> (f64, f64) {
+    adjust(winner, loser, 1.0, k)
+}
+
+/// New ratings of two players who drew, each scoring half a point
+pub fn update_elo_draw(a: f64, b: f64, k: f64) -> (f64, f64) {
+    adjust(a, b, 0.5, k)
+}
+
+/// New (white, black) ratings after a game won by `winner`, or drawn if None
+pub fn rate_game(white: f64, black: f64, winner: Option<Color>, k: f64) -> (f64, f64) {
+    match winner {
+        Some(Color::White) => update_elo(white, black, k),
+        Some(Color::Black) => {
+            let (black, white) = update_elo(black, white, k);
+            (white, black)
+        }
+        None => update_elo_draw(white, black, k),
+    }
+}
+
+/// Move both ratings by what `a` scored beyond expectation; Elo is zero-sum
+fn adjust(a: f64, b: f64, score: f64, k: f64) -> (f64, f64) {
+    let delta = k * (score - expected_score(a, b));
+    (a + delta, b - delta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: (f64, f64), expected: (f64, f64)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 0.01 && (actual.1 - expected.1).abs() < 0.01,
+            "expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn test_expected_score() {
+        assert!((expected_score(1500.0, 1500.0) - 0.5).abs() < 1e-9);
+        assert!((expected_score(1800.0, 1400.0) - 0.9091).abs() < 1e-4);
+        assert!((expected_score(1400.0, 1800.0) - 0.0909).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_win_between_equals_moves_half_k() {
+        assert_close(update_elo(1000.0, 1000.0, 32.0), (1016.0, 984.0));
+    }
+
+    #[test]
+    fn test_upset_moves_ratings_further() {
+        assert_close(update_elo(1200.0, 1000.0, 32.0), (1207.69, 992.31));
+        assert_close(update_elo(1000.0, 1200.0, 32.0), (1024.31, 1175.69));
+    }
+
+    #[test]
+    fn test_draw_pulls_ratings_together() {
+        assert_close(update_elo_draw(1200.0, 1000.0, 32.0), (1191.69, 1008.31));
+        assert_close(update_elo_draw(1500.0, 1500.0, 32.0), (1500.0, 1500.0));
+    }
+
+    #[test]
+    fn test_rate_game_by_winner_color() {
+        assert_close(
+            rate_game(1000.0, 1200.0, Some(Color::Black), 32.0),
+            (992.31, 1207.69),
+        );
+        assert_close(
+            rate_game(1000.0, 1200.0, Some(Color::White), 32.0),
+            (1024.31, 1175.69),
+        );
+        assert_close(rate_game(1000.0, 1200.0, None, 32.0), (1008.31, 1191.69));
+    }
+}
@@ -1,11 +1,26 @@
 // Integration tests for network multiplayer functionality
 
+use chessmate::cards::deck::Card;
+use chessmate::cards::effects::Effect;
 use chessmate::game::piece::{Color, PieceType, Position};
-use chessmate::networking::matchmaking::{MatchmakingQueue, WaitingPlayer};
-use chessmate::networking::protocol::{ClientMessage, GameAction, ServerMessage};
-use chessmate::networking::server::GameServer;
+use chessmate::networking::auth::TokenVerifier;
+use chessmate::networking::connection::handle_websocket;
+use chessmate::networking::matchmaking::{Match, MatchmakingQueue, WaitingPlayer};
+use chessmate::networking::protocol::{
+    ClientMessage, CloseReason, EndReasonCode, GameAction, ServerMessage, PROTOCOL_VERSION,
+};
+use chessmate::networking::server::{
+    GameServer, ServerGame, CHAT_RATE_LIMIT, CHAT_RATE_WINDOW, COMPUTER_PLAYER_ID,
+    DISCONNECT_GRACE, MAX_CHAT_LENGTH, ROOM_CODE_LENGTH, ROOM_TIMEOUT,
+};
+use chessmate::networking::types::{GameResult, TimeControl};
+use futures_util::{SinkExt, StreamExt};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use tokio::sync::mpsc;
 use tokio::time::{sleep, timeout, Duration};
+use tokio_tungstenite::tungstenite::Message;
 
 #[test]
 fn test_matchmaking_queue_pairs_players() {
@@ -18,12 +33,16 @@ fn test_matchmaking_queue_pairs_players() {
         player_id: "alice".to_string(),
         joined_at: std::time::Instant::now(),
         sender: tx1,
+        rating: None,
+        wants_time_odds: false,
     };
 
     let player2 = WaitingPlayer {
         player_id: "bob".to_string(),
         joined_at: std::time::Instant::now(),
         sender: tx2,
+        rating: None,
+        wants_time_odds: false,
     };
 
     queue.add_player(player1);
@@ -53,18 +72,24 @@ fn test_matchmaking_queue_odd_number() {
         player_id: "p1".to_string(),
         joined_at: std::time::Instant::now(),
         sender: tx1,
+        rating: None,
+        wants_time_odds: false,
     });
 
     queue.add_player(WaitingPlayer {
         player_id: "p2".to_string(),
         joined_at: std::time::Instant::now(),
         sender: tx2,
+        rating: None,
+        wants_time_odds: false,
     });
 
     queue.add_player(WaitingPlayer {
         player_id: "p3".to_string(),
         joined_at: std::time::Instant::now(),
         sender: tx3,
+        rating: None,
+        wants_time_odds: false,
     });
 
     let matches = queue.try_create_matches();
@@ -73,6 +98,43 @@ fn test_matchmaking_queue_odd_number() {
     assert_eq!(queue.player_count(), 1); // One player left waiting
 }
 
+#[tokio::test]
+async fn test_joining_again_updates_rating_and_time_odds() {
+    let server = GameServer::new();
+    server.set_player_rating("alice", 1700).await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    server
+        .add_to_matchmaking(WaitingPlayer::new("alice".to_string(), tx))
+        .await
+        .unwrap();
+
+    let rejoin = ClientMessage::JoinMatchmaking {
+        player_id: "alice".to_string(),
+        rating: Some(100),
+        time_odds: true,
+    };
+    server.handle_message("alice", rejoin).await.unwrap();
+
+    // The claimed rating is replaced by the stored one
+    match rx.try_recv().unwrap() {
+        ServerMessage::MatchmakingStatus {
+            rating,
+            queue_position,
+            ..
+        } => {
+            assert_eq!(rating, Some(1700));
+            assert_eq!(queue_position, 1);
+        }
+        other => panic!("Expected MatchmakingStatus, got {:?}", other),
+    }
+
+    // Nobody can join on someone else's behalf, or update a queue entry they don't have
+    let as_bob = ClientMessage::join_matchmaking("bob".to_string());
+    assert!(server.handle_message("alice", as_bob).await.is_err());
+    let unqueued = ClientMessage::join_matchmaking("carol".to_string());
+    assert!(server.handle_message("carol", unqueued).await.is_err());
+}
+
 #[tokio::test]
 async fn test_game_server_matchmaking() {
     let server = GameServer::new();
@@ -110,6 +172,7 @@ async fn test_game_server_matchmaking() {
             game_id: _,
             opponent_id,
             your_color: _,
+            ..
         } => {
             assert_eq!(opponent_id, "bob");
         }
@@ -121,6 +184,7 @@ async fn test_game_server_matchmaking() {
             game_id: _,
             opponent_id,
             your_color: _,
+            ..
         } => {
             assert_eq!(opponent_id, "alice");
         }
@@ -182,6 +246,7 @@ async fn test_game_server_move_processing() {
         from: Position::new(6, 4),
         to: Position::new(4, 4),
         promotion: None,
+        offer_draw: false,
     };
 
     let msg = ClientMessage::SubmitAction {
@@ -234,6 +299,7 @@ async fn test_invalid_move_rejected() {
         from: Position::new(6, 4),
         to: Position::new(3, 4),
         promotion: None,
+        offer_draw: false,
     };
 
     let msg = ClientMessage::SubmitAction {
@@ -279,6 +345,7 @@ async fn test_wrong_turn_rejected() {
         from: Position::new(1, 4),
         to: Position::new(3, 4),
         promotion: None,
+        offer_draw: false,
     };
 
     let msg = ClientMessage::SubmitAction {
@@ -460,6 +527,7 @@ async fn test_full_game_flow() {
                 from,
                 to,
                 promotion: None,
+                offer_draw: false,
             },
         };
 
@@ -476,20 +544,107 @@ async fn test_full_game_flow() {
     assert_eq!(active_games, 1);
 }
 
+#[test]
+fn test_close_reason_uses_application_close_code() {
+    let reason = CloseReason::AuthenticationFailed;
+    // 4000-4999 is reserved for applications, which is what clients key on
+    assert_eq!(reason.code(), 4001);
+    assert_eq!(reason.reason(), "Authentication failed");
+    assert_eq!(CloseReason::VersionMismatch.code(), 4002);
+}
+
+/// Serve the server's WebSocket handler on a free local port, returning its URL
+async fn serve_websocket(server: GameServer) -> String {
+    let app = axum::Router::new().route(
+        "/ws",
+        axum::routing::get(move |ws: axum::extract::WebSocketUpgrade| {
+            let server = server.clone_refs();
+            async move { ws.on_upgrade(move |socket| handle_websocket(socket, server)) }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("ws://{}/ws", addr)
+}
+
+/// Encode a client message as a WebSocket text frame
+fn text_frame(msg: &ClientMessage) -> Message {
+    Message::Text(serde_json::to_string(msg).unwrap())
+}
+
+#[tokio::test]
+async fn test_version_mismatch_gets_close_frame_and_socket_ends() {
+    let url = serve_websocket(GameServer::new()).await;
+    let (mut ws, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+
+    let hello = ClientMessage::Hello {
+        protocol_version: PROTOCOL_VERSION + 1,
+    };
+    ws.send(text_frame(&hello)).await.unwrap();
+
+    let frame = timeout(Duration::from_secs(5), async {
+        loop {
+            match ws.next().await {
+                Some(Ok(Message::Close(frame))) => break frame,
+                Some(Ok(_)) => continue,
+                other => panic!("Expected a close frame, got {:?}", other),
+            }
+        }
+    })
+    .await
+    .expect("Server should close the connection")
+    .expect("Close frame should carry a code");
+    let reason = CloseReason::VersionMismatch;
+    assert_eq!(u16::from(frame.code), reason.code());
+    assert_eq!(frame.reason, reason.reason());
+
+    // Nothing lingers after the close
+    let rest = timeout(Duration::from_secs(5), ws.next())
+        .await
+        .expect("Socket should end after the close frame");
+    assert!(!matches!(rest, Some(Ok(_))), "Got {:?} after close", rest);
+}
+
+#[tokio::test]
+async fn test_matching_version_keeps_connection_open() {
+    let url = serve_websocket(GameServer::new()).await;
+    let (mut ws, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+
+    let join = ClientMessage::join_matchmaking("alice".to_string());
+    ws.send(text_frame(&ClientMessage::hello())).await.unwrap();
+    ws.send(text_frame(&join)).await.unwrap();
+
+    let reply = timeout(Duration::from_secs(5), ws.next())
+        .await
+        .expect("Server should answer");
+    match reply {
+        Some(Ok(Message::Text(text))) => assert!(matches!(
+            serde_json::from_str(&text).unwrap(),
+            ServerMessage::MatchmakingJoined
+        )),
+        other => panic!("Expected MatchmakingJoined, got {:?}", other),
+    }
+}
+
 #[test]
 fn test_protocol_serialization() {
     // Test ClientMessage serialization
-    let join_msg = ClientMessage::JoinMatchmaking {
-        player_id: "test_player".to_string(),
-    };
+    let join_msg = ClientMessage::join_matchmaking("test_player".to_string());
     let json = serde_json::to_string(&join_msg).unwrap();
     assert!(json.contains("JoinMatchmaking"));
     assert!(json.contains("test_player"));
 
     let parsed: ClientMessage = serde_json::from_str(&json).unwrap();
     match parsed {
-        ClientMessage::JoinMatchmaking { player_id } => {
+        ClientMessage::JoinMatchmaking {
+            player_id,
+            rating,
+            time_odds,
+        } => {
             assert_eq!(player_id, "test_player");
+            assert_eq!(rating, None);
+            assert!(!time_odds);
         }
         _ => panic!("Failed to deserialize JoinMatchmaking"),
     }
@@ -499,6 +654,7 @@ fn test_protocol_serialization() {
         game_id: "game123".to_string(),
         opponent_id: "opponent".to_string(),
         your_color: Color::White,
+        seed: 42,
     };
     let json = serde_json::to_string(&match_found).unwrap();
     assert!(json.contains("MatchFound"));
@@ -510,10 +666,12 @@ fn test_protocol_serialization() {
             game_id,
             opponent_id,
             your_color,
+            seed,
         } => {
             assert_eq!(game_id, "game123");
             assert_eq!(opponent_id, "opponent");
             assert_eq!(your_color, Color::White);
+            assert_eq!(seed, 42);
         }
         _ => panic!("Failed to deserialize MatchFound"),
     }
@@ -523,6 +681,7 @@ fn test_protocol_serialization() {
         from: Position::new(6, 4),
         to: Position::new(4, 4),
         promotion: Some(PieceType::Queen),
+        offer_draw: false,
     };
     let json = serde_json::to_string(&move_action).unwrap();
     assert!(json.contains("MovePiece"));
@@ -533,6 +692,7 @@ fn test_protocol_serialization() {
             from,
             to,
             promotion,
+            ..
         } => {
             assert_eq!(from, Position::new(6, 4));
             assert_eq!(to, Position::new(4, 4));
@@ -541,3 +701,2299 @@ fn test_protocol_serialization() {
         _ => panic!("Failed to deserialize MovePiece"),
     }
 }
+
+/// Parse an algebraic square (e.g. "e4") into a Position
+fn sq(square: &str) -> Position {
+    Position::from_algebraic(square).unwrap()
+}
+
+/// Collect all messages currently queued for a player
+fn drain(rx: &mut mpsc::UnboundedReceiver<ServerMessage>) -> Vec<ServerMessage> {
+    let mut messages = Vec::new();
+    while let Ok(msg) = rx.try_recv() {
+        messages.push(msg);
+    }
+    messages
+}
+
+/// A started two-player game with receivers indexed by color
+struct TestGame {
+    game_id: String,
+    white_id: String,
+    black_id: String,
+    white_rx: mpsc::UnboundedReceiver<ServerMessage>,
+    black_rx: mpsc::UnboundedReceiver<ServerMessage>,
+}
+
+/// Match "alice" and "bob", start their game and drain the initial messages
+async fn start_game(server: &GameServer) -> TestGame {
+    start_game_with_rating(server, None).await
+}
+
+/// Like `start_game`, with both players stored at the given rating and asking for rated play
+async fn start_game_with_rating(server: &GameServer, rating: Option<u32>) -> TestGame {
+    start_game_with(server, rating, None).await
+}
+
+/// Like `start_game`, with a clock of `initial_seconds` per side and no increment
+async fn start_timed_game(server: &GameServer, initial_seconds: i32) -> TestGame {
+    let time_control = TimeControl {
+        initial_seconds,
+        increment_seconds: 0,
+    };
+    start_game_with(server, None, Some(time_control)).await
+}
+
+async fn start_game_with(
+    server: &GameServer,
+    rating: Option<u32>,
+    time_control: Option<TimeControl>,
+) -> TestGame {
+    let (tx1, mut rx1) = mpsc::unbounded_channel();
+    let (tx2, mut rx2) = mpsc::unbounded_channel();
+
+    if let Some(rating) = rating {
+        server.set_player_rating("alice", rating).await;
+        server.set_player_rating("bob", rating).await;
+    }
+    server
+        .add_to_matchmaking(WaitingPlayer::new("alice".to_string(), tx1).with_rating(rating))
+        .await
+        .unwrap();
+    server
+        .add_to_matchmaking(WaitingPlayer::new("bob".to_string(), tx2).with_rating(rating))
+        .await
+        .unwrap();
+
+    let matches = server.try_matchmaking().await;
+    let m = matches[0].clone().with_time_control(time_control);
+    server.create_game_from_match(m.clone()).await;
+
+    drain(&mut rx1);
+    drain(&mut rx2);
+
+    let white_id = m.white_player.player_id.clone();
+    let black_id = m.black_player.player_id.clone();
+    let (white_rx, black_rx) = if white_id == "alice" {
+        (rx1, rx2)
+    } else {
+        (rx2, rx1)
+    };
+
+    TestGame {
+        game_id: m.game_id,
+        white_id,
+        black_id,
+        white_rx,
+        black_rx,
+    }
+}
+
+/// Submit a move for a player, ignoring the result
+async fn play(server: &GameServer, game: &TestGame, player_id: &str, from: &str, to: &str) {
+    let msg = ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action: GameAction::move_piece(sq(from), sq(to), None),
+    };
+    let _ = server.handle_message(player_id, msg).await;
+}
+
+/// Find the GameOver message among received messages
+fn find_game_over(messages: &[ServerMessage]) -> Option<(Option<Color>, EndReasonCode)> {
+    messages.iter().find_map(|msg| match msg {
+        ServerMessage::GameOver {
+            winner,
+            reason_code,
+            ..
+        } => Some((*winner, *reason_code)),
+        _ => None,
+    })
+}
+
+#[tokio::test]
+async fn test_checkmate_game_over_reason_code() {
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+    let (white, black) = (game.white_id.clone(), game.black_id.clone());
+
+    // Fool's mate
+    play(&server, &game, &white, "f2", "f3").await;
+    play(&server, &game, &black, "e7", "e5").await;
+    play(&server, &game, &white, "g2", "g4").await;
+    play(&server, &game, &black, "d8", "h4").await;
+
+    let messages = drain(&mut game.white_rx);
+    assert_eq!(
+        find_game_over(&messages),
+        Some((Some(Color::Black), EndReasonCode::Checkmate))
+    );
+}
+
+#[tokio::test]
+async fn test_resignation_game_over_reason_code() {
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+    let white = game.white_id.clone();
+
+    let resign_msg = ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action: GameAction::Resign,
+    };
+    let _ = server.handle_message(&white, resign_msg).await;
+
+    let messages = drain(&mut game.black_rx);
+    assert_eq!(
+        find_game_over(&messages),
+        Some((Some(Color::Black), EndReasonCode::Resignation))
+    );
+}
+
+#[tokio::test]
+async fn test_san_move_submission() {
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+    let (white, black) = (game.white_id.clone(), game.black_id.clone());
+
+    let san_msg = ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action: GameAction::move_san("Nf3".to_string()),
+    };
+    server.handle_message(&white, san_msg).await.unwrap();
+
+    // Black sees the knight move as a coordinate move
+    let messages = drain(&mut game.black_rx);
+    assert!(messages.iter().any(|msg| matches!(
+        msg,
+        ServerMessage::OpponentAction {
+            action: GameAction::MovePiece { from, to, .. }
+        } if *from == sq("g1") && *to == sq("f3")
+    )));
+
+    // Invalid SAN is rejected with a reason
+    let bad_msg = ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action: GameAction::move_san("Qh5".to_string()),
+    };
+    assert!(server.handle_message(&black, bad_msg).await.is_err());
+    let messages = drain(&mut game.black_rx);
+    assert!(messages
+        .iter()
+        .any(|msg| matches!(msg, ServerMessage::InvalidAction { .. })));
+}
+
+#[tokio::test]
+async fn test_claim_draw_after_threefold_repetition() {
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+    let (white, black) = (game.white_id.clone(), game.black_id.clone());
+
+    let claim = || ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action: GameAction::claim_draw(),
+    };
+
+    // Nothing to claim from the start position
+    assert!(server.handle_message(&white, claim()).await.is_err());
+
+    // Shuffle knights twice to reach the start position a third time
+    for _ in 0..2 {
+        play(&server, &game, &white, "g1", "f3").await;
+        play(&server, &game, &black, "g8", "f6").await;
+        play(&server, &game, &white, "f3", "g1").await;
+        play(&server, &game, &black, "f6", "g8").await;
+    }
+
+    drain(&mut game.black_rx);
+    server.handle_message(&white, claim()).await.unwrap();
+
+    let messages = drain(&mut game.black_rx);
+    assert_eq!(
+        find_game_over(&messages),
+        Some((None, EndReasonCode::Repetition))
+    );
+}
+
+#[tokio::test]
+async fn test_draw_offer_cooldown() {
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+    let (white, black) = (game.white_id.clone(), game.black_id.clone());
+
+    let offer = || ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action: GameAction::OfferDraw,
+    };
+
+    // First offer is forwarded to the opponent
+    server.handle_message(&white, offer()).await.unwrap();
+    let messages = drain(&mut game.black_rx);
+    assert!(messages
+        .iter()
+        .any(|msg| matches!(msg, ServerMessage::DrawOffered)));
+
+    // Second offer before black moves is rejected
+    play(&server, &game, &white, "e2", "e4").await;
+    drain(&mut game.white_rx);
+    assert!(server.handle_message(&white, offer()).await.is_err());
+    let messages = drain(&mut game.white_rx);
+    assert!(messages.iter().any(|msg| matches!(
+        msg,
+        ServerMessage::InvalidAction { reason } if reason == "Draw offer on cooldown"
+    )));
+
+    // Allowed again once black has moved
+    play(&server, &game, &black, "e7", "e5").await;
+    server.handle_message(&white, offer()).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_player_who_left_matchmaking_is_not_matched() {
+    let server = GameServer::new();
+    let (alice_tx, mut alice_rx) = mpsc::unbounded_channel();
+    let (bob_tx, _bob_rx) = mpsc::unbounded_channel();
+    let (carol_tx, _carol_rx) = mpsc::unbounded_channel();
+    server
+        .add_to_matchmaking(WaitingPlayer::new("alice".to_string(), alice_tx))
+        .await
+        .unwrap();
+    server
+        .add_to_matchmaking(WaitingPlayer::new("bob".to_string(), bob_tx))
+        .await
+        .unwrap();
+
+    // Only the connection's own player may be taken out of the queue
+    let leave = ClientMessage::LeaveMatchmaking {
+        player_id: "alice".to_string(),
+    };
+    assert!(server.handle_message("bob", leave.clone()).await.is_err());
+    server.handle_message("alice", leave.clone()).await.unwrap();
+    assert!(drain(&mut alice_rx)
+        .iter()
+        .any(|msg| matches!(msg, ServerMessage::MatchmakingLeft)));
+    assert!(server.handle_message("alice", leave).await.is_err());
+
+    assert_eq!(server.matchmaking_count().await, 1);
+    assert!(server.try_matchmaking().await.is_empty());
+
+    server
+        .add_to_matchmaking(WaitingPlayer::new("carol".to_string(), carol_tx))
+        .await
+        .unwrap();
+    let matches = server.try_matchmaking().await;
+    assert_eq!(matches.len(), 1);
+    let mut matched = [
+        matches[0].white_player.player_id.as_str(),
+        matches[0].black_player.player_id.as_str(),
+    ];
+    matched.sort();
+    assert_eq!(matched, ["bob", "carol"]);
+}
+
+#[tokio::test]
+async fn test_stale_players_pruned_with_mock_clock() {
+    let queue_timeout = Duration::from_secs(300);
+    let now = Arc::new(Mutex::new(Instant::now()));
+    let clock_now = Arc::clone(&now);
+    let server = GameServer::new_for_test(Arc::new(move || *clock_now.lock().unwrap()), 7)
+        .with_queue_timeout(queue_timeout);
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    server
+        .add_to_matchmaking(WaitingPlayer::new("idle".to_string(), tx))
+        .await
+        .unwrap();
+
+    // Just under the threshold nothing is pruned
+    *now.lock().unwrap() += queue_timeout - Duration::from_secs(1);
+    assert!(server.prune_stale_players().await.is_empty());
+    assert_eq!(server.matchmaking_count().await, 1);
+
+    // Advancing past the threshold prunes the player and notifies them
+    *now.lock().unwrap() += Duration::from_secs(2);
+    assert_eq!(server.prune_stale_players().await, vec!["idle".to_string()]);
+    assert_eq!(server.matchmaking_count().await, 0);
+    assert!(matches!(rx.try_recv(), Ok(ServerMessage::Error { .. })));
+}
+
+#[tokio::test]
+async fn test_connected_players_wait_without_queue_timeout() {
+    let now = Arc::new(Mutex::new(Instant::now()));
+    let clock_now = Arc::clone(&now);
+    let server = GameServer::new_for_test(Arc::new(move || *clock_now.lock().unwrap()), 7);
+
+    let (tx, _rx) = mpsc::unbounded_channel();
+    server
+        .add_to_matchmaking(WaitingPlayer::new("patient".to_string(), tx))
+        .await
+        .unwrap();
+    let (tx, rx) = mpsc::unbounded_channel();
+    server
+        .add_to_matchmaking(WaitingPlayer::new("gone".to_string(), tx))
+        .await
+        .unwrap();
+    drop(rx);
+
+    *now.lock().unwrap() += Duration::from_secs(3600);
+    assert_eq!(server.prune_stale_players().await, vec!["gone".to_string()]);
+    assert_eq!(server.matchmaking_count().await, 1);
+}
+
+#[tokio::test]
+async fn test_white_checkmate_winner_is_white() {
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+    let (white, black) = (game.white_id.clone(), game.black_id.clone());
+
+    // Scholar's mate
+    play(&server, &game, &white, "e2", "e4").await;
+    play(&server, &game, &black, "e7", "e5").await;
+    play(&server, &game, &white, "f1", "c4").await;
+    play(&server, &game, &black, "b8", "c6").await;
+    play(&server, &game, &white, "d1", "h5").await;
+    play(&server, &game, &black, "g8", "f6").await;
+    play(&server, &game, &white, "h5", "f7").await;
+
+    let messages = drain(&mut game.black_rx);
+    assert_eq!(
+        find_game_over(&messages),
+        Some((Some(Color::White), EndReasonCode::Checkmate))
+    );
+}
+
+#[tokio::test]
+async fn test_move_limit_ends_game_as_draw() {
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+    let (white, black) = (game.white_id.clone(), game.black_id.clone());
+
+    server
+        .set_game_limits(&game.game_id, Some(4), None)
+        .await
+        .unwrap();
+
+    play(&server, &game, &white, "e2", "e4").await;
+    play(&server, &game, &black, "e7", "e5").await;
+    play(&server, &game, &white, "g1", "f3").await;
+    assert!(server.sweep_game_limits().await.is_empty());
+
+    play(&server, &game, &black, "b8", "c6").await;
+    assert_eq!(server.sweep_game_limits().await, vec![game.game_id.clone()]);
+    // The ended game stays for a rematch, but isn't ended again
+    assert_eq!(server.active_game_count().await, 1);
+    assert!(server.sweep_game_limits().await.is_empty());
+
+    let messages = drain(&mut game.white_rx);
+    assert_eq!(
+        find_game_over(&messages),
+        Some((None, EndReasonCode::MoveLimit))
+    );
+}
+
+#[tokio::test]
+async fn test_find_player_game() {
+    let server = GameServer::new();
+    let game = start_game(&server).await;
+
+    assert_eq!(
+        server.find_player_game(&game.white_id).await,
+        Some(game.game_id.clone())
+    );
+    assert_eq!(
+        server.find_player_game(&game.black_id).await,
+        Some(game.game_id.clone())
+    );
+    assert_eq!(server.find_player_game("idle").await, None);
+}
+
+#[tokio::test]
+async fn test_color_balancing_evens_out_history() {
+    let server = GameServer::new();
+    let player = |id: &str| {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (WaitingPlayer::new(id.to_string(), tx), rx)
+    };
+
+    // Alice is White three times in a row; Carol has played each color once
+    server.set_color_balancing(false);
+    for opponent in ["bob1", "bob2", "bob3"] {
+        let (alice, _) = player("alice");
+        let (bob, _) = player(opponent);
+        server.create_game_from_match(Match::new(alice, bob)).await;
+    }
+    let (carol, _) = player("carol");
+    let (dave, _) = player("dave");
+    server.create_game_from_match(Match::new(carol, dave)).await;
+    let (erin, _) = player("erin");
+    let (carol, _) = player("carol");
+    server.create_game_from_match(Match::new(erin, carol)).await;
+
+    assert_eq!(server.color_history("alice").await.white, 3);
+    assert_eq!(server.color_history("carol").await.balance(), 0);
+
+    // Even when the match proposes White for Alice, she gets Black
+    server.set_color_balancing(true);
+    let (alice, mut alice_rx) = player("alice");
+    let (carol, _) = player("carol");
+    server
+        .create_game_from_match(Match::new(alice, carol))
+        .await;
+
+    let color = drain(&mut alice_rx).into_iter().find_map(|msg| match msg {
+        ServerMessage::MatchFound { your_color, .. } => Some(your_color),
+        _ => None,
+    });
+    assert_eq!(color, Some(Color::Black));
+
+    let history = server.color_history("alice").await;
+    assert_eq!((history.white, history.black), (3, 1));
+}
+
+#[tokio::test]
+async fn test_games_with_player_filter() {
+    let server = GameServer::new();
+    let game = start_game(&server).await;
+
+    let (tx1, _rx1) = mpsc::unbounded_channel();
+    let (tx2, _rx2) = mpsc::unbounded_channel();
+    let other = Match::new(
+        WaitingPlayer::new("carol".to_string(), tx1),
+        WaitingPlayer::new("dave".to_string(), tx2),
+    );
+    server.create_game_from_match(other).await;
+
+    assert_eq!(server.list_games().await.len(), 2);
+
+    let games = server.games_with_player("alice").await;
+    assert_eq!(games.len(), 1);
+    assert_eq!(games[0].game_id, game.game_id);
+    assert!(games[0].white_player_id == "alice" || games[0].black_player_id == "alice");
+
+    assert!(server.games_with_player("nobody").await.is_empty());
+}
+
+#[tokio::test]
+async fn test_second_connection_for_in_game_player_is_refused() {
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let result = server
+        .add_to_matchmaking(WaitingPlayer::new(game.white_id.clone(), tx))
+        .await;
+    assert_eq!(result, Err("Already connected elsewhere".to_string()));
+    assert_eq!(server.matchmaking_count().await, 0);
+
+    // The original connection still controls the game
+    assert_eq!(
+        server.find_player_game(&game.white_id).await,
+        Some(game.game_id.clone())
+    );
+    play(&server, &game, &game.white_id, "e2", "e4").await;
+    assert!(drain(&mut game.white_rx)
+        .iter()
+        .any(|msg| matches!(msg, ServerMessage::GameStateUpdate { .. })));
+}
+
+#[tokio::test]
+async fn test_matchmaking_status_reports_queue_position() {
+    let server = GameServer::new();
+    let (tx1, _rx1) = mpsc::unbounded_channel();
+    let (tx2, _rx2) = mpsc::unbounded_channel();
+
+    server
+        .add_to_matchmaking(WaitingPlayer::new("alice".to_string(), tx1))
+        .await
+        .unwrap();
+    server
+        .add_to_matchmaking(WaitingPlayer::new("bob".to_string(), tx2))
+        .await
+        .unwrap();
+
+    let position = |status: Option<ServerMessage>| match status {
+        Some(ServerMessage::MatchmakingStatus { queue_position, .. }) => Some(queue_position),
+        _ => None,
+    };
+    assert_eq!(position(server.matchmaking_status("alice").await), Some(1));
+    assert_eq!(position(server.matchmaking_status("bob").await), Some(2));
+    assert_eq!(position(server.matchmaking_status("carol").await), None);
+}
+
+#[tokio::test]
+async fn test_state_reports_last_move_squares() {
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+
+    play(&server, &game, &game.white_id, "e2", "e4").await;
+
+    let last_move = drain(&mut game.black_rx)
+        .into_iter()
+        .find_map(|msg| match msg {
+            ServerMessage::GameStateUpdate { state } => Some(state.last_move),
+            _ => None,
+        });
+    assert_eq!(last_move, Some(Some(("e2".to_string(), "e4".to_string()))));
+}
+
+#[tokio::test]
+async fn test_move_with_draw_offer() {
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+
+    let msg = ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action: GameAction::move_piece_offering_draw(sq("e2"), sq("e4"), None),
+    };
+    server.handle_message(&game.white_id, msg).await.unwrap();
+
+    let received = drain(&mut game.black_rx);
+    assert!(received.iter().any(|msg| matches!(
+        msg,
+        ServerMessage::GameStateUpdate { state } if state.last_move.is_some()
+    )));
+    assert!(received
+        .iter()
+        .any(|msg| matches!(msg, ServerMessage::DrawOffered)));
+
+    // The offer is pending: a second one is refused
+    let again = ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action: GameAction::OfferDraw,
+    };
+    assert!(server.handle_message(&game.white_id, again).await.is_err());
+}
+
+#[tokio::test]
+async fn test_spectator_count_in_state_and_summary() {
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+
+    let (tx1, mut spectator_rx) = mpsc::unbounded_channel();
+    let (tx2, _rx2) = mpsc::unbounded_channel();
+    server.add_spectator(&game.game_id, tx1).await.unwrap();
+    server
+        .add_spectator(&game.game_id, tx2.clone())
+        .await
+        .unwrap();
+
+    let last_count = |messages: Vec<ServerMessage>| {
+        messages.into_iter().rev().find_map(|msg| match msg {
+            ServerMessage::GameStateUpdate { state } => Some(state.spectator_count),
+            _ => None,
+        })
+    };
+    assert_eq!(last_count(drain(&mut game.white_rx)), Some(2));
+    assert_eq!(last_count(drain(&mut spectator_rx)), Some(2));
+    assert_eq!(server.list_games().await[0].spectator_count, 2);
+
+    server.remove_spectator(&game.game_id, &tx2).await.unwrap();
+    assert_eq!(last_count(drain(&mut game.black_rx)), Some(1));
+    assert_eq!(server.list_games().await[0].spectator_count, 1);
+}
+
+#[tokio::test]
+async fn test_closed_spectators_are_pruned_on_broadcast() {
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+
+    let (tx1, _rx1) = mpsc::unbounded_channel();
+    let (tx2, rx2) = mpsc::unbounded_channel();
+    server.add_spectator(&game.game_id, tx1).await.unwrap();
+    server.add_spectator(&game.game_id, tx2).await.unwrap();
+    assert_eq!(server.list_games().await[0].spectator_count, 2);
+
+    // The second spectator disconnects without leaving
+    drop(rx2);
+    drain(&mut game.black_rx);
+    play(&server, &game, &game.white_id, "e2", "e4").await;
+
+    let count = drain(&mut game.black_rx)
+        .into_iter()
+        .find_map(|msg| match msg {
+            ServerMessage::GameStateUpdate { state } => Some(state.spectator_count),
+            _ => None,
+        });
+    assert_eq!(count, Some(1));
+    assert_eq!(server.list_games().await[0].spectator_count, 1);
+}
+
+#[tokio::test]
+async fn test_spectator_watches_but_cannot_act() {
+    let server = GameServer::new();
+    let game = start_game(&server).await;
+
+    let (tx, mut spectator_rx) = mpsc::unbounded_channel();
+    server.add_spectator(&game.game_id, tx).await.unwrap();
+    assert!(drain(&mut spectator_rx)
+        .iter()
+        .any(|msg| matches!(msg, ServerMessage::GameStateUpdate { .. })));
+
+    // Spectators see the players' moves but can't make their own
+    play(&server, &game, &game.white_id, "e2", "e4").await;
+    assert!(drain(&mut spectator_rx)
+        .iter()
+        .any(|msg| matches!(msg, ServerMessage::GameStateUpdate { .. })));
+    for action in [
+        GameAction::move_piece(sq("e7"), sq("e5"), None),
+        GameAction::resign(),
+    ] {
+        let msg = ClientMessage::SubmitAction {
+            game_id: game.game_id.clone(),
+            action,
+        };
+        assert!(server.handle_message("carol", msg).await.is_err());
+    }
+    assert_eq!(server.list_games().await[0].move_count, 1);
+
+    // The final result reaches spectators, who are then let go
+    let resign = ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action: GameAction::resign(),
+    };
+    server.handle_message(&game.black_id, resign).await.unwrap();
+    assert_eq!(
+        find_game_over(&drain(&mut spectator_rx)),
+        Some((Some(Color::White), EndReasonCode::Resignation))
+    );
+    assert_eq!(server.list_games().await[0].spectator_count, 0);
+}
+
+#[tokio::test]
+async fn test_legal_moves_included_in_state_when_enabled() {
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+    let (white, black) = (game.white_id.clone(), game.black_id.clone());
+
+    let last_state = |rx: &mut mpsc::UnboundedReceiver<ServerMessage>| {
+        drain(rx)
+            .into_iter()
+            .filter_map(|msg| match msg {
+                ServerMessage::GameStateUpdate { state } => Some(state),
+                _ => None,
+            })
+            .next_back()
+            .expect("no state update")
+    };
+
+    play(&server, &game, &white, "e2", "e4").await;
+    assert!(last_state(&mut game.black_rx).legal_moves.is_none());
+
+    server.set_legal_moves(&game.game_id, true).await.unwrap();
+    play(&server, &game, &black, "e7", "e5").await;
+    let legal_moves = last_state(&mut game.white_rx)
+        .legal_moves
+        .expect("legal moves not included");
+    assert_eq!(
+        legal_moves.get("g1").map(Vec::len),
+        Some(3),
+        "Knight on g1 should reach e2, f3 and h3"
+    );
+    assert!(!legal_moves.contains_key("e4"));
+}
+
+#[tokio::test]
+async fn test_non_capture_move_sends_single_piece_delta() {
+    let server = GameServer::new();
+    let mut game = start_timed_game(&server, 300).await;
+    server.set_state_deltas(&game.game_id, true).await.unwrap();
+
+    play(&server, &game, &game.white_id, "e2", "e4").await;
+
+    let delta = drain(&mut game.black_rx)
+        .into_iter()
+        .find_map(|msg| match msg {
+            ServerMessage::StateDelta { delta } => Some(delta),
+            _ => None,
+        })
+        .expect("expected a StateDelta");
+    assert_eq!(delta.moved.len(), 1);
+    assert_eq!(delta.moved[0].position, "e4");
+    assert!(delta.removed.is_empty());
+    assert_eq!(delta.next_player_id, game.black_id);
+    // Everything besides the board that changes with a move comes along
+    assert!(delta.last_action.is_some());
+    assert_eq!(delta.last_move, Some(("e2".to_string(), "e4".to_string())));
+    assert!(delta.material.is_some());
+    assert_eq!(delta.time.len(), 2);
+}
+
+#[test]
+fn test_delta_baselines_are_kept_per_recipient() {
+    let (white_tx, _white_rx) = mpsc::unbounded_channel();
+    let (black_tx, mut black_rx) = mpsc::unbounded_channel();
+    let mut game = ServerGame::new(
+        "game123".to_string(),
+        "alice".to_string(),
+        "bob".to_string(),
+        white_tx,
+        black_tx,
+    );
+    game.send_state_deltas = true;
+    game.broadcast_state();
+    drain(&mut black_rx);
+
+    // A spectator that was never sent a state has nothing to apply a delta to
+    let (spectator_tx, mut spectator_rx) = mpsc::unbounded_channel();
+    game.spectators.push(spectator_tx);
+    game.game.apply_move(sq("e2"), sq("e4"), None).unwrap();
+    game.broadcast_update();
+    assert!(matches!(
+        drain(&mut black_rx).as_slice(),
+        [ServerMessage::StateDelta { .. }]
+    ));
+    let mut spectator_state = match drain(&mut spectator_rx).pop() {
+        Some(ServerMessage::GameStateUpdate { state }) => *state,
+        other => panic!("Expected GameStateUpdate, got {:?}", other),
+    };
+
+    // From then on it gets deltas that bring its copy up to date
+    game.game.apply_move(sq("e7"), sq("e5"), None).unwrap();
+    game.broadcast_update();
+    match drain(&mut spectator_rx).pop() {
+        Some(ServerMessage::StateDelta { delta }) => spectator_state.apply_delta(&delta),
+        other => panic!("Expected StateDelta, got {:?}", other),
+    }
+    let mut expected = game.to_serializable_state().board_state;
+    let mut actual = spectator_state.board_state;
+    expected.sort_by_key(|piece| piece.id);
+    actual.sort_by_key(|piece| piece.id);
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_same_game_id_gives_same_effect_rng() {
+    use rand::Rng;
+
+    let new_game = |game_id: &str| {
+        let (white_tx, _) = mpsc::unbounded_channel();
+        let (black_tx, _) = mpsc::unbounded_channel();
+        ServerGame::new(
+            game_id.to_string(),
+            "alice".to_string(),
+            "bob".to_string(),
+            white_tx,
+            black_tx,
+        )
+    };
+
+    let mut a = new_game("game123");
+    let mut b = new_game("game123");
+    let mut c = new_game("game456");
+    assert_eq!(a.rng_seed, b.rng_seed);
+    assert_ne!(a.rng_seed, c.rng_seed);
+
+    let roll = |game: &mut ServerGame| -> Vec<u32> {
+        (0..8)
+            .map(|_| game.effect_rng().gen_range(0..1000))
+            .collect()
+    };
+    assert_eq!(roll(&mut a), roll(&mut b));
+    assert_ne!(roll(&mut a), roll(&mut c));
+}
+
+#[tokio::test]
+async fn test_time_odds_give_stronger_player_less_time() {
+    let server = GameServer::new();
+    let (tx1, mut rx1) = mpsc::unbounded_channel();
+    let (tx2, _rx2) = mpsc::unbounded_channel();
+
+    // The stored ratings count, not the ones the clients claim
+    server.set_player_rating("alice", 2000).await;
+    server.set_player_rating("bob", 1600).await;
+    server
+        .add_to_matchmaking(
+            WaitingPlayer::new("alice".to_string(), tx1)
+                .with_rating(Some(100))
+                .with_time_odds(true),
+        )
+        .await
+        .unwrap();
+    server
+        .add_to_matchmaking(
+            WaitingPlayer::new("bob".to_string(), tx2)
+                .with_rating(Some(1600))
+                .with_time_odds(true),
+        )
+        .await
+        .unwrap();
+
+    let matches = server.try_matchmaking().await;
+    server.create_game_from_match(matches[0].clone()).await;
+
+    let time = drain(&mut rx1)
+        .into_iter()
+        .find_map(|msg| match msg {
+            ServerMessage::GameStateUpdate { state } => Some(state.time),
+            _ => None,
+        })
+        .unwrap();
+    assert!(time["alice"] < time["bob"]);
+}
+
+#[tokio::test]
+async fn test_claim_draw_by_declaring_repeating_move() {
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+    let (white, black) = (game.white_id.clone(), game.black_id.clone());
+
+    let claim_with = |from: &str, to: &str| ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action: GameAction::claim_draw_with_move(sq(from), sq(to), None),
+    };
+
+    // The start position has occurred twice; Ng1 would bring it back a third time
+    play(&server, &game, &white, "g1", "f3").await;
+    play(&server, &game, &black, "g8", "f6").await;
+    play(&server, &game, &white, "f3", "g1").await;
+    play(&server, &game, &black, "f6", "g8").await;
+    play(&server, &game, &white, "g1", "f3").await;
+    play(&server, &game, &black, "g8", "f6").await;
+    play(&server, &game, &white, "f3", "g1").await;
+
+    // A move that doesn't repeat anything can't be used to claim
+    assert!(server
+        .handle_message(&black, claim_with("e7", "e5"))
+        .await
+        .is_err());
+
+    drain(&mut game.white_rx);
+    server
+        .handle_message(&black, claim_with("f6", "g8"))
+        .await
+        .unwrap();
+
+    let messages = drain(&mut game.white_rx);
+    assert_eq!(
+        find_game_over(&messages),
+        Some((None, EndReasonCode::Repetition))
+    );
+    // The declared move is not played
+    assert!(messages.iter().all(|msg| !matches!(
+        msg,
+        ServerMessage::OpponentAction { .. } | ServerMessage::GameStateUpdate { .. }
+    )));
+}
+
+#[tokio::test]
+async fn test_handling_action_updates_last_seen() {
+    let now = Arc::new(Mutex::new(Instant::now()));
+    let clock_now = Arc::clone(&now);
+    let server = GameServer::new_for_test(Arc::new(move || *clock_now.lock().unwrap()), 7);
+    let game = start_game(&server).await;
+    let started = *now.lock().unwrap();
+
+    *now.lock().unwrap() += Duration::from_secs(30);
+    play(&server, &game, &game.white_id, "e2", "e4").await;
+
+    assert_eq!(
+        server.player_last_seen(&game.game_id, Color::White).await,
+        Some(started + Duration::from_secs(30))
+    );
+    assert_eq!(
+        server.player_last_seen(&game.game_id, Color::Black).await,
+        Some(started)
+    );
+}
+
+#[tokio::test]
+async fn test_disconnected_player_forfeits_after_grace() {
+    let now = Arc::new(Mutex::new(Instant::now()));
+    let clock_now = Arc::clone(&now);
+    let server = GameServer::new_for_test(Arc::new(move || *clock_now.lock().unwrap()), 7);
+    let mut game = start_game(&server).await;
+
+    drop(game.black_rx);
+    *now.lock().unwrap() += DISCONNECT_GRACE - Duration::from_secs(1);
+    assert!(server.sweep_disconnected().await.is_empty());
+
+    *now.lock().unwrap() += Duration::from_secs(1);
+    assert_eq!(
+        server.sweep_disconnected().await,
+        vec![game.game_id.clone()]
+    );
+    assert_eq!(
+        find_game_over(&drain(&mut game.white_rx)),
+        Some((Some(Color::White), EndReasonCode::OpponentLeft))
+    );
+}
+
+#[tokio::test]
+async fn test_reconnect_within_grace_resumes_game() {
+    let now = Arc::new(Mutex::new(Instant::now()));
+    let clock_now = Arc::clone(&now);
+    let server = GameServer::new_for_test(Arc::new(move || *clock_now.lock().unwrap()), 7);
+    let game = start_game(&server).await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    // A connected player's seat can't be taken, and outsiders have no seat
+    assert!(server
+        .reconnect(&game.game_id, &game.white_id, tx.clone())
+        .await
+        .is_err());
+    assert!(server
+        .reconnect(&game.game_id, "carol", tx.clone())
+        .await
+        .is_err());
+
+    drop(game.black_rx);
+    *now.lock().unwrap() += DISCONNECT_GRACE - Duration::from_secs(1);
+    server
+        .reconnect(&game.game_id, &game.black_id, tx)
+        .await
+        .unwrap();
+    assert!(drain(&mut rx)
+        .iter()
+        .any(|msg| matches!(msg, ServerMessage::GameStateUpdate { .. })));
+
+    // The grace period no longer runs out, and the game goes on over the new connection
+    *now.lock().unwrap() += Duration::from_secs(1);
+    assert!(server.sweep_disconnected().await.is_empty());
+    let first = ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action: GameAction::move_piece(sq("e2"), sq("e4"), None),
+    };
+    server.handle_message(&game.white_id, first).await.unwrap();
+    let reply = ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action: GameAction::move_piece(sq("e7"), sq("e5"), None),
+    };
+    server.handle_message(&game.black_id, reply).await.unwrap();
+    assert!(drain(&mut rx)
+        .iter()
+        .any(|msg| matches!(msg, ServerMessage::GameStateUpdate { .. })));
+}
+
+#[test]
+fn test_both_players_disconnected_is_draw() {
+    let (white_tx, white_rx) = mpsc::unbounded_channel();
+    let (black_tx, black_rx) = mpsc::unbounded_channel();
+    let game = ServerGame::new(
+        "game123".to_string(),
+        "alice".to_string(),
+        "bob".to_string(),
+        white_tx,
+        black_tx,
+    );
+    let later = game.started_at + DISCONNECT_GRACE;
+
+    drop(white_rx);
+    assert_eq!(
+        game.abandonment(later),
+        Some((
+            Some(Color::Black),
+            EndReasonCode::OpponentLeft,
+            "Opponent disconnected"
+        ))
+    );
+
+    drop(black_rx);
+    assert_eq!(
+        game.abandonment(later),
+        Some((None, EndReasonCode::BothAbandoned, "Both abandoned"))
+    );
+}
+
+#[test]
+fn test_authenticate_accepts_signed_token_and_rejects_tampered() {
+    let server = GameServer::new().with_token_verifier(TokenVerifier::new("test-secret"));
+    assert!(server.requires_auth());
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    let token = TokenVerifier::new("test-secret").sign("alice");
+    assert_eq!(server.authenticate(&token, &tx), Some("alice".to_string()));
+    match rx.try_recv().unwrap() {
+        ServerMessage::Authenticated { player_id } => assert_eq!(player_id, "alice"),
+        other => panic!("Expected Authenticated, got {:?}", other),
+    }
+
+    // Claiming another player's identity with alice's signature
+    let tampered = token.replacen("alice", "bob", 1);
+    assert_eq!(server.authenticate(&tampered, &tx), None);
+    assert!(matches!(
+        rx.try_recv().unwrap(),
+        ServerMessage::Error { .. }
+    ));
+}
+
+#[test]
+fn test_acting_as_a_player_requires_their_token() {
+    let open = GameServer::new();
+    assert!(open.check_acting_as(None, "alice").is_ok());
+
+    let server = GameServer::new().with_token_verifier(TokenVerifier::new("test-secret"));
+    assert!(server.check_acting_as(Some("alice"), "alice").is_ok());
+    assert!(server.check_acting_as(Some("alice"), "bob").is_err());
+    assert!(server.check_acting_as(None, "alice").is_err());
+
+    let token = TokenVerifier::new("test-secret").sign("alice");
+    assert_eq!(server.verify_token(&token), Ok("alice".to_string()));
+    assert!(open.verify_token(&token).is_err());
+
+    // Every message that names a player is checked against the verified one
+    let join = ClientMessage::join_matchmaking("bob".to_string());
+    assert_eq!(join.claimed_player_id(), Some("bob"));
+    let reconnect = ClientMessage::reconnect("game".to_string(), "bob".to_string());
+    assert_eq!(reconnect.claimed_player_id(), Some("bob"));
+    let spectate = ClientMessage::spectate_game("game".to_string());
+    assert_eq!(spectate.claimed_player_id(), None);
+}
+
+/// Fields as "name" -> formatted value
+type Fields = Vec<(String, String)>;
+
+#[derive(Default)]
+struct FieldCollector(Fields);
+
+impl tracing::field::Visit for FieldCollector {
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .push((field.name().to_string(), format!("{:?}", value)));
+    }
+}
+
+/// Test subscriber recording each event with the fields of the spans it happened in
+#[derive(Clone, Default)]
+struct SpanRecorder {
+    spans: Arc<Mutex<HashMap<u64, Fields>>>,
+    entered: Arc<Mutex<Vec<u64>>>,
+    events: Arc<Mutex<Vec<(Fields, Fields)>>>, // (event, spans)
+}
+
+impl tracing::Subscriber for SpanRecorder {
+    fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+        let mut fields = FieldCollector::default();
+        span.record(&mut fields);
+        let mut spans = self.spans.lock().unwrap();
+        let id = spans.len() as u64 + 1;
+        spans.insert(id, fields.0);
+        tracing::span::Id::from_u64(id)
+    }
+
+    fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+
+    fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+    fn event(&self, event: &tracing::Event<'_>) {
+        let mut fields = FieldCollector::default();
+        event.record(&mut fields);
+        let spans = self.spans.lock().unwrap();
+        let span_fields = self
+            .entered
+            .lock()
+            .unwrap()
+            .iter()
+            .flat_map(|id| spans[id].clone())
+            .collect();
+        self.events.lock().unwrap().push((fields.0, span_fields));
+    }
+
+    fn enter(&self, span: &tracing::span::Id) {
+        self.entered.lock().unwrap().push(span.into_u64());
+    }
+
+    fn exit(&self, _: &tracing::span::Id) {
+        self.entered.lock().unwrap().pop();
+    }
+}
+
+#[tokio::test]
+async fn test_processed_move_is_logged_in_game_span() {
+    let recorder = SpanRecorder::default();
+    let _guard = tracing::subscriber::set_default(recorder.clone());
+
+    let server = GameServer::new();
+    let game = start_game(&server).await;
+    let white = game.white_id.clone();
+    play(&server, &game, &white, "e2", "e4").await;
+
+    let message = ("message".to_string(), format!("{} played e2e4", white));
+    let game_field = ("game_id".to_string(), game.game_id.clone());
+    let events = recorder.events.lock().unwrap();
+    let (_, spans) = events
+        .iter()
+        .find(|(fields, _)| fields.contains(&message))
+        .expect("move event was not emitted");
+    assert!(spans.contains(&game_field));
+    assert!(spans.contains(&("player_id".to_string(), white)));
+}
+
+#[tokio::test]
+async fn test_play_vs_computer_replies_to_moves() {
+    let server = GameServer::new();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    let game_id = server.start_computer_game("alice", 1, tx).await.unwrap();
+    let messages = drain(&mut rx);
+    let your_color = messages
+        .iter()
+        .find_map(|msg| match msg {
+            ServerMessage::MatchFound {
+                opponent_id,
+                your_color,
+                ..
+            } => {
+                assert_eq!(opponent_id, COMPUTER_PLAYER_ID);
+                Some(*your_color)
+            }
+            _ => None,
+        })
+        .expect("no MatchFound");
+
+    let opponent_moves = |messages: &[ServerMessage]| {
+        messages
+            .iter()
+            .filter(|msg| matches!(msg, ServerMessage::OpponentAction { .. }))
+            .count()
+    };
+    // With White the computer opens right away
+    let expected_opening = usize::from(your_color == Color::Black);
+    assert_eq!(opponent_moves(&messages), expected_opening);
+
+    let (from, to) = match your_color {
+        Color::White => ("e2", "e4"),
+        Color::Black => ("e7", "e5"),
+    };
+    let msg = ClientMessage::SubmitAction {
+        game_id: game_id.clone(),
+        action: GameAction::move_piece(sq(from), sq(to), None),
+    };
+    server.handle_message("alice", msg).await.unwrap();
+
+    let messages = drain(&mut rx);
+    assert_eq!(opponent_moves(&messages), 1);
+    assert_eq!(server.find_player_game("alice").await, Some(game_id));
+}
+
+#[tokio::test]
+async fn test_create_room_replies_with_join_code() {
+    let server = GameServer::new();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    let code = server.create_room("alice", None, tx).await.unwrap();
+    assert_eq!(code.len(), ROOM_CODE_LENGTH);
+
+    let messages = drain(&mut rx);
+    assert!(matches!(
+        messages.as_slice(),
+        [ServerMessage::RoomCreated { code: sent }] if *sent == code
+    ));
+    // Nobody is in a game until a friend joins
+    assert_eq!(server.find_player_game("alice").await, None);
+}
+
+#[tokio::test]
+async fn test_joining_room_starts_game_between_host_and_guest() {
+    let server = GameServer::new();
+    let (host_tx, mut host_rx) = mpsc::unbounded_channel();
+    let (guest_tx, mut guest_rx) = mpsc::unbounded_channel();
+
+    let time_control = TimeControl {
+        initial_seconds: 180,
+        increment_seconds: 2,
+    };
+    let code = server
+        .create_room("alice", Some(time_control), host_tx)
+        .await
+        .unwrap();
+    drain(&mut host_rx);
+
+    // Codes are matched case-insensitively
+    let game_id = server
+        .join_room("bob", &code.to_lowercase(), guest_tx)
+        .await
+        .unwrap();
+    assert_eq!(
+        server.find_player_game("alice").await,
+        Some(game_id.clone())
+    );
+    assert_eq!(server.find_player_game("bob").await, Some(game_id.clone()));
+
+    let match_found = |messages: &[ServerMessage]| {
+        messages.iter().find_map(|msg| match msg {
+            ServerMessage::MatchFound {
+                game_id,
+                opponent_id,
+                your_color,
+                ..
+            } => Some((game_id.clone(), opponent_id.clone(), *your_color)),
+            _ => None,
+        })
+    };
+    let host_messages = drain(&mut host_rx);
+    let (host_game, host_opponent, host_color) = match_found(&host_messages).unwrap();
+    let (guest_game, guest_opponent, guest_color) = match_found(&drain(&mut guest_rx)).unwrap();
+    assert_eq!(host_game, game_id);
+    assert_eq!(guest_game, game_id);
+    assert_eq!(host_opponent, "bob");
+    assert_eq!(guest_opponent, "alice");
+    assert_eq!(guest_color, host_color.opposite());
+
+    // The room's clock is applied to the game
+    let state = host_messages
+        .iter()
+        .find_map(|msg| match msg {
+            ServerMessage::GameStateUpdate { state } => Some(state),
+            _ => None,
+        })
+        .expect("no initial state");
+    assert_eq!(state.time.get("alice"), Some(&180));
+    assert_eq!(state.time.get("bob"), Some(&180));
+
+    // The room is used up
+    let (tx, _rx) = mpsc::unbounded_channel();
+    assert!(server.join_room("carol", &code, tx).await.is_err());
+}
+
+#[tokio::test]
+async fn test_join_room_rejects_unknown_code_and_own_room() {
+    let server = GameServer::new();
+    let (host_tx, _host_rx) = mpsc::unbounded_channel();
+    let (guest_tx, _guest_rx) = mpsc::unbounded_channel();
+
+    let code = server
+        .create_room("alice", None, host_tx.clone())
+        .await
+        .unwrap();
+
+    let err = server
+        .join_room("bob", "NOPE42", guest_tx)
+        .await
+        .unwrap_err();
+    assert!(err.contains("Room not found"));
+    assert!(server.join_room("alice", &code, host_tx).await.is_err());
+    assert_eq!(server.find_player_game("bob").await, None);
+}
+
+#[tokio::test]
+async fn test_room_codes_expire() {
+    let now = Arc::new(Mutex::new(Instant::now()));
+    let clock_now = Arc::clone(&now);
+    let server = GameServer::new_for_test(Arc::new(move || *clock_now.lock().unwrap()), 7);
+    let (host_tx, mut host_rx) = mpsc::unbounded_channel();
+    let (guest_tx, _guest_rx) = mpsc::unbounded_channel();
+
+    // A code nobody used in time can't be joined
+    let code = server
+        .create_room("alice", None, host_tx.clone())
+        .await
+        .unwrap();
+    *now.lock().unwrap() += ROOM_TIMEOUT;
+    let err = server
+        .join_room("bob", &code, guest_tx.clone())
+        .await
+        .unwrap_err();
+    assert_eq!(err, format!("Room expired: {}", code));
+    assert_eq!(server.find_player_game("bob").await, None);
+
+    // The sweep closes expired rooms and tells the host
+    let code = server.create_room("alice", None, host_tx).await.unwrap();
+    assert!(server.prune_expired_rooms().await.is_empty());
+    *now.lock().unwrap() += ROOM_TIMEOUT;
+    assert_eq!(server.prune_expired_rooms().await, vec![code.clone()]);
+    assert!(drain(&mut host_rx).iter().any(|msg| matches!(
+        msg,
+        ServerMessage::Error { message } if *message == format!("Room expired: {}", code)
+    )));
+
+    // A room whose host disconnected expires right away
+    let (gone_tx, gone_rx) = mpsc::unbounded_channel();
+    let code = server.create_room("carol", None, gone_tx).await.unwrap();
+    drop(gone_rx);
+    assert!(server.join_room("bob", &code, guest_tx).await.is_err());
+}
+
+#[tokio::test]
+async fn test_game_is_finalized_only_once() {
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+
+    let resign = ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action: GameAction::Resign,
+    };
+    server
+        .handle_message(&game.white_id, resign.clone())
+        .await
+        .unwrap();
+    // Racing terminal events after the resignation: a second resign (refused, as the game is
+    // over) and the loser leaving
+    assert!(server.handle_message(&game.white_id, resign).await.is_err());
+    let leave = ClientMessage::LeaveGame {
+        game_id: game.game_id.clone(),
+    };
+    server.handle_message(&game.white_id, leave).await.unwrap();
+
+    let game_overs = |messages: Vec<ServerMessage>| {
+        messages
+            .into_iter()
+            .filter(|msg| matches!(msg, ServerMessage::GameOver { .. }))
+            .count()
+    };
+    let white_messages = drain(&mut game.white_rx);
+    let black_messages = drain(&mut game.black_rx);
+    assert_eq!(
+        find_game_over(&black_messages),
+        Some((Some(Color::Black), EndReasonCode::Resignation))
+    );
+    assert_eq!(game_overs(white_messages), 1);
+    assert_eq!(game_overs(black_messages), 1);
+
+    // Leaving still cleans up the finished game
+    assert_eq!(server.find_player_game(&game.black_id).await, None);
+}
+
+#[tokio::test]
+async fn test_rated_results_are_queued_once() {
+    let resign = |game: &TestGame| ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action: GameAction::resign(),
+    };
+
+    let server = GameServer::new();
+    let rated = start_game_with_rating(&server, Some(1500)).await;
+    server
+        .handle_message(&rated.white_id, resign(&rated))
+        .await
+        .unwrap();
+    // Leaving the finished game doesn't record it again
+    server
+        .handle_message(
+            &rated.black_id,
+            ClientMessage::LeaveGame {
+                game_id: rated.game_id.clone(),
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        server.take_rated_results(),
+        vec![GameResult {
+            game_id: rated.game_id.clone(),
+            white_player_id: rated.white_id.clone(),
+            black_player_id: rated.black_id.clone(),
+            winner: Some(Color::Black),
+        }]
+    );
+    assert!(server.take_rated_results().is_empty());
+
+    // A result that couldn't be recorded is handed out again
+    let unrecorded = GameResult {
+        game_id: rated.game_id.clone(),
+        white_player_id: rated.white_id.clone(),
+        black_player_id: rated.black_id.clone(),
+        winner: Some(Color::Black),
+    };
+    server.requeue_rated_results(vec![unrecorded.clone()]);
+    assert_eq!(server.take_rated_results(), vec![unrecorded]);
+
+    // Casual games don't count
+    let server = GameServer::new();
+    let casual = start_game(&server).await;
+    server
+        .handle_message(&casual.white_id, resign(&casual))
+        .await
+        .unwrap();
+    assert!(server.take_rated_results().is_empty());
+}
+
+#[tokio::test]
+async fn test_takeback_rejected_in_rated_games_only() {
+    let request_takeback = |game: &TestGame| ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action: GameAction::request_takeback(),
+    };
+
+    let server = GameServer::new();
+    let mut rated = start_game_with_rating(&server, Some(1500)).await;
+    play(&server, &rated, &rated.white_id, "e2", "e4").await;
+    drain(&mut rated.white_rx);
+    drain(&mut rated.black_rx);
+
+    let result = server
+        .handle_message(&rated.white_id, request_takeback(&rated))
+        .await;
+    assert_eq!(
+        result,
+        Err("Takebacks not allowed in rated games".to_string())
+    );
+    assert!(drain(&mut rated.white_rx).iter().any(|msg| matches!(
+        msg,
+        ServerMessage::InvalidAction { reason } if reason == "Takebacks not allowed in rated games"
+    )));
+    assert!(drain(&mut rated.black_rx).is_empty());
+
+    let server = GameServer::new();
+    let mut casual = start_game(&server).await;
+    play(&server, &casual, &casual.white_id, "e2", "e4").await;
+    drain(&mut casual.white_rx);
+    drain(&mut casual.black_rx);
+
+    // Asked for after moving, while it's the opponent's turn
+    server
+        .handle_message(&casual.white_id, request_takeback(&casual))
+        .await
+        .unwrap();
+    assert!(drain(&mut casual.black_rx).iter().any(|msg| matches!(
+        msg,
+        ServerMessage::OpponentAction {
+            action: GameAction::RequestTakeback
+        }
+    )));
+}
+
+#[tokio::test]
+async fn test_promoted_piece_keeps_its_color_in_board_state() {
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+    for (player, from, to) in [
+        (&game.white_id, "a2", "a4"),
+        (&game.black_id, "b7", "b5"),
+        (&game.white_id, "a4", "b5"),
+        (&game.black_id, "a7", "a6"),
+        (&game.white_id, "b5", "a6"),
+        (&game.black_id, "c8", "b7"),
+        (&game.white_id, "a6", "b7"),
+        (&game.black_id, "b8", "c6"),
+    ] {
+        play(&server, &game, player, from, to).await;
+    }
+    drain(&mut game.black_rx);
+
+    let promote = ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action: GameAction::move_piece(sq("b7"), sq("a8"), Some(PieceType::Queen)),
+    };
+    server
+        .handle_message(&game.white_id, promote)
+        .await
+        .unwrap();
+
+    let state = drain(&mut game.black_rx)
+        .into_iter()
+        .filter_map(|msg| match msg {
+            ServerMessage::GameStateUpdate { state } => Some(state),
+            _ => None,
+        })
+        .next_back()
+        .unwrap();
+    let queen = state
+        .board_state
+        .iter()
+        .find(|piece| piece.position == "a8")
+        .unwrap();
+    assert_eq!(queen.piece_type, "queen");
+    assert!(queen.id >= 32);
+    assert_eq!(queen.color, Color::White);
+}
+
+#[tokio::test]
+async fn test_takeback_rejected_before_requesters_first_move() {
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+    play(&server, &game, &game.white_id, "e2", "e4").await;
+    drain(&mut game.white_rx);
+    drain(&mut game.black_rx);
+
+    // Black hasn't moved, so there's nothing of theirs to take back (e4 is White's)
+    let result = server
+        .handle_message(
+            &game.black_id,
+            ClientMessage::SubmitAction {
+                game_id: game.game_id.clone(),
+                action: GameAction::request_takeback(),
+            },
+        )
+        .await;
+    assert_eq!(result, Err("No move to take back".to_string()));
+    assert!(drain(&mut game.black_rx).iter().any(|msg| matches!(
+        msg,
+        ServerMessage::InvalidAction { reason } if reason == "No move to take back"
+    )));
+    assert!(drain(&mut game.white_rx).is_empty());
+
+    // Nothing is pending for White to accept
+    let accept = ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action: GameAction::accept_takeback(),
+    };
+    assert_eq!(
+        server.handle_message(&game.white_id, accept).await,
+        Err("No takeback request to answer".to_string())
+    );
+}
+
+#[tokio::test]
+async fn test_takeback_rejected_against_computer() {
+    let server = GameServer::new();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let game_id = server.start_computer_game("alice", 1, tx).await.unwrap();
+    let your_color = drain(&mut rx)
+        .iter()
+        .find_map(|msg| match msg {
+            ServerMessage::MatchFound { your_color, .. } => Some(*your_color),
+            _ => None,
+        })
+        .expect("no MatchFound");
+
+    let (from, to) = match your_color {
+        Color::White => ("e2", "e4"),
+        Color::Black => ("e7", "e5"),
+    };
+    let msg = ClientMessage::SubmitAction {
+        game_id: game_id.clone(),
+        action: GameAction::move_piece(sq(from), sq(to), None),
+    };
+    server.handle_message("alice", msg).await.unwrap();
+    drain(&mut rx);
+
+    let msg = ClientMessage::SubmitAction {
+        game_id,
+        action: GameAction::request_takeback(),
+    };
+    assert_eq!(
+        server.handle_message("alice", msg).await,
+        Err("No takebacks against the computer".to_string())
+    );
+    assert!(drain(&mut rx).iter().any(|msg| matches!(
+        msg,
+        ServerMessage::InvalidAction { reason } if reason == "No takebacks against the computer"
+    )));
+}
+
+#[tokio::test]
+async fn test_accepted_takeback_undoes_the_move() {
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+    play(&server, &game, &game.white_id, "e2", "e4").await;
+    play(&server, &game, &game.black_id, "e7", "e5").await;
+    drain(&mut game.white_rx);
+    drain(&mut game.black_rx);
+
+    // Nothing to answer yet
+    let accept = ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action: GameAction::accept_takeback(),
+    };
+    assert_eq!(
+        server.handle_message(&game.white_id, accept.clone()).await,
+        Err("No takeback request to answer".to_string())
+    );
+    drain(&mut game.white_rx);
+
+    // Black takes back e5 while it's White's turn
+    server
+        .handle_message(
+            &game.black_id,
+            ClientMessage::SubmitAction {
+                game_id: game.game_id.clone(),
+                action: GameAction::request_takeback(),
+            },
+        )
+        .await
+        .unwrap();
+    server.handle_message(&game.white_id, accept).await.unwrap();
+
+    assert!(drain(&mut game.black_rx).iter().any(|msg| matches!(
+        msg,
+        ServerMessage::OpponentAction {
+            action: GameAction::AcceptTakeback
+        }
+    )));
+    let state = drain(&mut game.white_rx)
+        .into_iter()
+        .filter_map(|msg| match msg {
+            ServerMessage::GameStateUpdate { state } => Some(state),
+            _ => None,
+        })
+        .next_back()
+        .unwrap();
+    assert_eq!(state.next_player_id, game.black_id);
+    assert_eq!(state.last_move, Some(("e2".to_string(), "e4".to_string())));
+
+    // Black can play a different reply
+    let reply = ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action: GameAction::move_piece(sq("c7"), sq("c5"), None),
+    };
+    server.handle_message(&game.black_id, reply).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_takeback_request_undoes_the_reply_too() {
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+    play(&server, &game, &game.white_id, "e2", "e4").await;
+    play(&server, &game, &game.black_id, "e7", "e5").await;
+    drain(&mut game.white_rx);
+    drain(&mut game.black_rx);
+
+    // White asks to take back e4 after Black has replied: both moves are undone
+    server
+        .handle_message(
+            &game.white_id,
+            ClientMessage::SubmitAction {
+                game_id: game.game_id.clone(),
+                action: GameAction::request_takeback(),
+            },
+        )
+        .await
+        .unwrap();
+    let accept = ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action: GameAction::accept_takeback(),
+    };
+    server.handle_message(&game.black_id, accept).await.unwrap();
+
+    let state = drain(&mut game.black_rx)
+        .into_iter()
+        .filter_map(|msg| match msg {
+            ServerMessage::GameStateUpdate { state } => Some(state),
+            _ => None,
+        })
+        .next_back()
+        .unwrap();
+    assert_eq!(state.next_player_id, game.white_id);
+    assert_eq!(state.last_move, None);
+}
+
+#[tokio::test]
+async fn test_declined_takeback_notifies_requester() {
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+    play(&server, &game, &game.white_id, "e2", "e4").await;
+    drain(&mut game.white_rx);
+    drain(&mut game.black_rx);
+
+    server
+        .handle_message(
+            &game.white_id,
+            ClientMessage::SubmitAction {
+                game_id: game.game_id.clone(),
+                action: GameAction::request_takeback(),
+            },
+        )
+        .await
+        .unwrap();
+    server
+        .handle_message(
+            &game.black_id,
+            ClientMessage::SubmitAction {
+                game_id: game.game_id.clone(),
+                action: GameAction::decline_takeback(),
+            },
+        )
+        .await
+        .unwrap();
+
+    assert!(drain(&mut game.white_rx).iter().any(|msg| matches!(
+        msg,
+        ServerMessage::OpponentAction {
+            action: GameAction::DeclineTakeback
+        }
+    )));
+    // The move stands and the request is used up
+    assert!(!drain(&mut game.black_rx)
+        .iter()
+        .any(|msg| matches!(msg, ServerMessage::GameStateUpdate { .. })));
+    let reply = ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action: GameAction::move_piece(sq("e7"), sq("e5"), None),
+    };
+    server.handle_message(&game.black_id, reply).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_clock_sweep_flags_player_out_of_time() {
+    let now = Arc::new(Mutex::new(Instant::now()));
+    let clock_now = Arc::clone(&now);
+    let server = GameServer::new_for_test(Arc::new(move || *clock_now.lock().unwrap()), 7);
+    let mut game = start_timed_game(&server, 2).await;
+
+    // Clocks don't run before the first move
+    for _ in 0..3 {
+        *now.lock().unwrap() += Duration::from_secs(1);
+        assert!(server.sweep_clocks().await.is_empty());
+    }
+
+    play(&server, &game, &game.white_id, "e2", "e4").await;
+    drain(&mut game.white_rx);
+    drain(&mut game.black_rx);
+
+    // Black's clock is running: two seconds, two one-second sweeps
+    *now.lock().unwrap() += Duration::from_secs(1);
+    assert!(server.sweep_clocks().await.is_empty());
+    *now.lock().unwrap() += Duration::from_secs(1);
+    assert_eq!(server.sweep_clocks().await, vec![game.game_id.clone()]);
+
+    let expected = Some((Some(Color::White), EndReasonCode::TimeOut));
+    assert_eq!(find_game_over(&drain(&mut game.white_rx)), expected);
+    assert_eq!(find_game_over(&drain(&mut game.black_rx)), expected);
+    // The ended game stays for a rematch
+    assert_eq!(server.active_game_count().await, 1);
+
+    // The ended game is no longer ticked
+    assert!(server.sweep_clocks().await.is_empty());
+    assert!(drain(&mut game.white_rx).is_empty());
+}
+
+#[tokio::test]
+async fn test_clock_sweep_charges_elapsed_time_with_mock_clock() {
+    let now = Arc::new(Mutex::new(Instant::now()));
+    let clock_now = Arc::clone(&now);
+    let server = GameServer::new_for_test(Arc::new(move || *clock_now.lock().unwrap()), 7);
+    let mut game = start_timed_game(&server, 60).await;
+
+    // White's wait before the first move isn't charged
+    *now.lock().unwrap() += Duration::from_secs(5);
+    play(&server, &game, &game.white_id, "e2", "e4").await;
+
+    // A sweep charges the 1500 ms since the last one, and the move 1500 ms later is charged
+    // as well rather than being free
+    *now.lock().unwrap() += Duration::from_millis(1500);
+    assert!(server.sweep_clocks().await.is_empty());
+    *now.lock().unwrap() += Duration::from_millis(1500);
+    play(&server, &game, &game.black_id, "e7", "e5").await;
+
+    let time = drain(&mut game.white_rx)
+        .into_iter()
+        .filter_map(|msg| match msg {
+            ServerMessage::GameStateUpdate { state } => Some(state.time),
+            _ => None,
+        })
+        .next_back()
+        .unwrap();
+    assert_eq!(time[&game.white_id], 60);
+    assert_eq!(time[&game.black_id], 57);
+    drain(&mut game.black_rx);
+}
+
+#[tokio::test]
+async fn test_actions_rejected_once_game_is_over() {
+    let now = Arc::new(Mutex::new(Instant::now()));
+    let clock_now = Arc::clone(&now);
+    let server = GameServer::new_for_test(Arc::new(move || *clock_now.lock().unwrap()), 7);
+    let mut game = start_timed_game(&server, 60).await;
+    play(&server, &game, &game.white_id, "e2", "e4").await;
+
+    let resign = ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action: GameAction::resign(),
+    };
+    server
+        .handle_message(&game.black_id, resign.clone())
+        .await
+        .unwrap();
+    drain(&mut game.white_rx);
+    drain(&mut game.black_rx);
+
+    // Neither moves nor a second resignation reach the finished game
+    for (player, msg) in [
+        (&game.black_id, resign),
+        (
+            &game.black_id,
+            ClientMessage::SubmitAction {
+                game_id: game.game_id.clone(),
+                action: GameAction::move_piece(sq("e7"), sq("e5"), None),
+            },
+        ),
+    ] {
+        assert_eq!(
+            server.handle_message(player, msg).await,
+            Err("Game is over".to_string())
+        );
+    }
+    assert!(drain(&mut game.black_rx).iter().all(|msg| matches!(
+        msg,
+        ServerMessage::InvalidAction { reason } if reason == "Game is over"
+    )));
+    assert!(drain(&mut game.white_rx).is_empty());
+
+    // Its clock has stopped too
+    *now.lock().unwrap() += Duration::from_secs(120);
+    assert!(server.sweep_clocks().await.is_empty());
+    assert!(drain(&mut game.white_rx).is_empty());
+}
+
+#[tokio::test]
+async fn test_draw_offer_accepted_ends_game() {
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+    let (white, black) = (game.white_id.clone(), game.black_id.clone());
+    let action = |action: GameAction| ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action,
+    };
+
+    // Nothing to accept yet, and a player can't accept their own offer
+    assert!(server
+        .handle_message(&black, action(GameAction::accept_draw()))
+        .await
+        .is_err());
+    play(&server, &game, &white, "e2", "e4").await;
+    server
+        .handle_message(&black, action(GameAction::offer_draw()))
+        .await
+        .unwrap();
+    assert!(server
+        .handle_message(&black, action(GameAction::accept_draw()))
+        .await
+        .is_err());
+    drain(&mut game.white_rx);
+    drain(&mut game.black_rx);
+
+    // Accepting works even though it's not the accepting player's turn
+    server
+        .handle_message(&white, action(GameAction::accept_draw()))
+        .await
+        .unwrap();
+    let expected = Some((None, EndReasonCode::DrawAgreement));
+    assert_eq!(find_game_over(&drain(&mut game.white_rx)), expected);
+    assert_eq!(find_game_over(&drain(&mut game.black_rx)), expected);
+}
+
+#[tokio::test]
+async fn test_draw_offer_declined_or_lapsed() {
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+    let (white, black) = (game.white_id.clone(), game.black_id.clone());
+    let action = |action: GameAction| ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action,
+    };
+
+    // Declining clears the offer and tells the offerer
+    server
+        .handle_message(&white, action(GameAction::offer_draw()))
+        .await
+        .unwrap();
+    server
+        .handle_message(&black, action(GameAction::decline_draw()))
+        .await
+        .unwrap();
+    assert!(drain(&mut game.white_rx)
+        .iter()
+        .any(|msg| matches!(msg, ServerMessage::DrawDeclined)));
+    assert!(server
+        .handle_message(&black, action(GameAction::accept_draw()))
+        .await
+        .is_err());
+
+    // An offer lapses once a move is played
+    play(&server, &game, &white, "e2", "e4").await;
+    server
+        .handle_message(&black, action(GameAction::offer_draw()))
+        .await
+        .unwrap();
+    play(&server, &game, &black, "e7", "e5").await;
+    assert!(server
+        .handle_message(&white, action(GameAction::accept_draw()))
+        .await
+        .is_err());
+    assert_eq!(find_game_over(&drain(&mut game.black_rx)), None);
+}
+
+#[tokio::test]
+async fn test_rematch_swaps_colors_and_keeps_clock() {
+    let server = GameServer::new();
+    let mut game = start_timed_game(&server, 300).await;
+    let (white, black) = (game.white_id.clone(), game.black_id.clone());
+    let rematch = || ClientMessage::RequestRematch {
+        game_id: game.game_id.clone(),
+    };
+
+    // No rematch while the game is still being played
+    assert!(server.handle_message(&white, rematch()).await.is_err());
+
+    server
+        .handle_message(
+            &white,
+            ClientMessage::SubmitAction {
+                game_id: game.game_id.clone(),
+                action: GameAction::resign(),
+            },
+        )
+        .await
+        .unwrap();
+    drain(&mut game.white_rx);
+    drain(&mut game.black_rx);
+
+    // The first request is offered to the opponent, and can't be repeated
+    server.handle_message(&white, rematch()).await.unwrap();
+    assert!(drain(&mut game.black_rx).iter().any(|msg| matches!(
+        msg,
+        ServerMessage::RematchOffered { game_id } if *game_id == game.game_id
+    )));
+    assert!(server.handle_message(&white, rematch()).await.is_err());
+
+    // The opponent's request starts the new game with colors swapped
+    server.handle_message(&black, rematch()).await.unwrap();
+    let new_game_id = server.find_player_game(&white).await.unwrap();
+    assert_ne!(new_game_id, game.game_id);
+    assert_eq!(
+        server.find_player_game(&black).await,
+        Some(new_game_id.clone())
+    );
+    assert_eq!(server.active_game_count().await, 1);
+
+    for (rx, color) in [
+        (&mut game.white_rx, Color::Black),
+        (&mut game.black_rx, Color::White),
+    ] {
+        let messages = drain(rx);
+        assert!(messages.iter().any(|msg| matches!(
+            msg,
+            ServerMessage::RematchStarted { previous_game_id, game_id }
+                if *previous_game_id == game.game_id && *game_id == new_game_id
+        )));
+        assert!(messages.iter().any(|msg| matches!(
+            msg,
+            ServerMessage::MatchFound { game_id, your_color, .. }
+                if *game_id == new_game_id && *your_color == color
+        )));
+        let state = messages
+            .iter()
+            .find_map(|msg| match msg {
+                ServerMessage::GameStateUpdate { state } => Some(state),
+                _ => None,
+            })
+            .expect("no initial state");
+        assert_eq!(state.time.get(&white), Some(&300));
+        assert_eq!(state.time.get(&black), Some(&300));
+    }
+
+    // The former black player now moves first
+    let first_move = ClientMessage::SubmitAction {
+        game_id: new_game_id,
+        action: GameAction::move_piece(sq("e2"), sq("e4"), None),
+    };
+    server.handle_message(&black, first_move).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_rematch_cancelled_when_opponent_leaves() {
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+    let (white, black) = (game.white_id.clone(), game.black_id.clone());
+    let rematch = || ClientMessage::RequestRematch {
+        game_id: game.game_id.clone(),
+    };
+
+    server
+        .handle_message(
+            &white,
+            ClientMessage::SubmitAction {
+                game_id: game.game_id.clone(),
+                action: GameAction::resign(),
+            },
+        )
+        .await
+        .unwrap();
+    server.handle_message(&white, rematch()).await.unwrap();
+    drain(&mut game.white_rx);
+
+    // Leaving instead of accepting tells the waiting player the rematch is off
+    server
+        .handle_message(
+            &black,
+            ClientMessage::LeaveGame {
+                game_id: game.game_id.clone(),
+            },
+        )
+        .await
+        .unwrap();
+    assert!(drain(&mut game.white_rx)
+        .iter()
+        .any(|msg| matches!(msg, ServerMessage::Error { .. })));
+    assert!(server.handle_message(&black, rematch()).await.is_err());
+    assert_eq!(server.active_game_count().await, 0);
+
+    // A disconnected opponent can't be asked
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+    server
+        .handle_message(
+            &game.white_id,
+            ClientMessage::SubmitAction {
+                game_id: game.game_id.clone(),
+                action: GameAction::resign(),
+            },
+        )
+        .await
+        .unwrap();
+    drop(game.black_rx);
+    let rematch = ClientMessage::RequestRematch {
+        game_id: game.game_id.clone(),
+    };
+    assert!(server
+        .handle_message(&game.white_id, rematch)
+        .await
+        .is_err());
+    assert!(drain(&mut game.white_rx).iter().any(|msg| matches!(
+        msg,
+        ServerMessage::InvalidAction { reason } if reason == "Opponent has left"
+    )));
+}
+
+#[tokio::test]
+async fn test_rematch_after_time_out() {
+    let now = Arc::new(Mutex::new(Instant::now()));
+    let clock_now = Arc::clone(&now);
+    let server = GameServer::new_for_test(Arc::new(move || *clock_now.lock().unwrap()), 7);
+    let mut game = start_timed_game(&server, 1).await;
+    let (white, black) = (game.white_id.clone(), game.black_id.clone());
+    let rematch = || ClientMessage::RequestRematch {
+        game_id: game.game_id.clone(),
+    };
+
+    play(&server, &game, &white, "e2", "e4").await;
+    *now.lock().unwrap() += Duration::from_secs(1);
+    assert_eq!(server.sweep_clocks().await, vec![game.game_id.clone()]);
+    drain(&mut game.white_rx);
+    drain(&mut game.black_rx);
+
+    server.handle_message(&black, rematch()).await.unwrap();
+    assert!(drain(&mut game.white_rx)
+        .iter()
+        .any(|msg| matches!(msg, ServerMessage::RematchOffered { .. })));
+    server.handle_message(&white, rematch()).await.unwrap();
+
+    let new_game_id = server.find_player_game(&white).await.unwrap();
+    assert_ne!(new_game_id, game.game_id);
+    assert!(drain(&mut game.black_rx).iter().any(|msg| matches!(
+        msg,
+        ServerMessage::MatchFound { game_id, your_color, .. }
+            if *game_id == new_game_id && *your_color == Color::White
+    )));
+    assert_eq!(server.active_game_count().await, 1);
+}
+
+#[tokio::test]
+async fn test_chat_reaches_opponent_and_spectators_only() {
+    let server = GameServer::new();
+    let mut game = start_game(&server).await;
+    let (tx, mut spectator_rx) = mpsc::unbounded_channel();
+    server.add_spectator(&game.game_id, tx).await.unwrap();
+    drain(&mut spectator_rx);
+
+    let chat = |text: &str| ClientMessage::SendChat {
+        game_id: game.game_id.clone(),
+        text: text.to_string(),
+    };
+    server
+        .handle_message(&game.white_id, chat("  good luck! "))
+        .await
+        .unwrap();
+
+    let is_greeting = |msg: &ServerMessage| {
+        matches!(
+            msg,
+            ServerMessage::ChatMessage { from, text }
+                if *from == game.white_id && text == "good luck!"
+        )
+    };
+    assert!(drain(&mut game.black_rx).iter().any(is_greeting));
+    assert!(drain(&mut spectator_rx).iter().any(is_greeting));
+    assert!(!drain(&mut game.white_rx).iter().any(is_greeting));
+
+    // Only the game's players may chat in it
+    assert!(server.handle_message("carol", chat("hi")).await.is_err());
+    assert!(drain(&mut game.black_rx).is_empty());
+}
+
+#[tokio::test]
+async fn test_chat_length_and_rate_limits() {
+    let now = Arc::new(Mutex::new(Instant::now()));
+    let clock_now = Arc::clone(&now);
+    let server = GameServer::new_for_test(Arc::new(move || *clock_now.lock().unwrap()), 7);
+    let mut game = start_game(&server).await;
+    let chat = |text: String| ClientMessage::SendChat {
+        game_id: game.game_id.clone(),
+        text,
+    };
+
+    let longest = "x".repeat(MAX_CHAT_LENGTH);
+    server
+        .handle_message(&game.white_id, chat(longest.clone()))
+        .await
+        .unwrap();
+    assert!(server
+        .handle_message(&game.white_id, chat(longest + "x"))
+        .await
+        .is_err());
+    assert!(server
+        .handle_message(&game.white_id, chat("   ".to_string()))
+        .await
+        .is_err());
+
+    // One message already counts towards the limit
+    for _ in 1..CHAT_RATE_LIMIT {
+        server
+            .handle_message(&game.white_id, chat("gg".to_string()))
+            .await
+            .unwrap();
+    }
+    assert!(server
+        .handle_message(&game.white_id, chat("gg".to_string()))
+        .await
+        .is_err());
+    assert!(drain(&mut game.white_rx).iter().any(|msg| matches!(
+        msg,
+        ServerMessage::InvalidAction { reason } if reason == "Sending chat messages too quickly"
+    )));
+
+    // The opponent has their own allowance, and the window moves on
+    server
+        .handle_message(&game.black_id, chat("gg".to_string()))
+        .await
+        .unwrap();
+    *now.lock().unwrap() += CHAT_RATE_WINDOW;
+    server
+        .handle_message(&game.white_id, chat("gg".to_string()))
+        .await
+        .unwrap();
+    let received = drain(&mut game.black_rx)
+        .iter()
+        .filter(|msg| matches!(msg, ServerMessage::ChatMessage { .. }))
+        .count();
+    assert_eq!(received, CHAT_RATE_LIMIT + 1);
+}
+
+#[tokio::test]
+async fn test_teleport_card_is_not_counted_as_a_move() {
+    let now = Arc::new(Mutex::new(Instant::now()));
+    let clock_now = Arc::clone(&now);
+    let server = GameServer::new_for_test(Arc::new(move || *clock_now.lock().unwrap()), 7);
+    let mut game = start_timed_game(&server, 60).await;
+    let white = game.white_id.clone();
+    server
+        .set_game_limits(&game.game_id, Some(1), None)
+        .await
+        .unwrap();
+
+    let jump = Card::new(
+        "jump",
+        "Jump",
+        "Teleport your knight",
+        Effect::TeleportPiece {
+            from: sq("g1"),
+            to: sq("f3"),
+        },
+    );
+    server
+        .deal_cards(&game.game_id, Color::White, vec![jump])
+        .await
+        .unwrap();
+    server
+        .handle_message(
+            &white,
+            ClientMessage::SubmitAction {
+                game_id: game.game_id.clone(),
+                action: GameAction::play_card("jump".to_string(), None),
+            },
+        )
+        .await
+        .unwrap();
+
+    // Clients aren't shown the teleport as a move
+    let state = drain(&mut game.black_rx)
+        .into_iter()
+        .filter_map(|msg| match msg {
+            ServerMessage::GameStateUpdate { state } => Some(state),
+            _ => None,
+        })
+        .next_back()
+        .unwrap();
+    assert_eq!(state.last_move, None);
+
+    // Neither the move limit nor the clock take it for the first move
+    assert!(server.sweep_game_limits().await.is_empty());
+    *now.lock().unwrap() += Duration::from_secs(5);
+    assert!(server.sweep_clocks().await.is_empty());
+    play(&server, &game, &white, "e2", "e4").await;
+    let state = drain(&mut game.black_rx)
+        .into_iter()
+        .filter_map(|msg| match msg {
+            ServerMessage::GameStateUpdate { state } => Some(state),
+            _ => None,
+        })
+        .next_back()
+        .unwrap();
+    assert_eq!(state.time[&white], 60);
+    assert_eq!(state.last_move, Some(("e2".to_string(), "e4".to_string())));
+}
+
+#[tokio::test]
+async fn test_time_grant_card_updates_broadcast_clock() {
+    let server = GameServer::new();
+    let mut game = start_timed_game(&server, 60).await;
+
+    let bonus = Card::new(
+        "bonus",
+        "Time Bonus",
+        "Add 30 seconds to your clock",
+        // The time goes to whoever plays the card, whatever color it was made for
+        Effect::GrantTime {
+            player: Color::Black,
+            seconds: 30,
+        },
+    );
+    server
+        .deal_cards(&game.game_id, Color::White, vec![bonus])
+        .await
+        .unwrap();
+    let dealt = drain(&mut game.black_rx)
+        .into_iter()
+        .find_map(|msg| match msg {
+            ServerMessage::GameStateUpdate { state } => Some(state.cards_remaining),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(dealt[&game.white_id], 1);
+    drain(&mut game.white_rx);
+
+    let play_card = |card_id: &str| ClientMessage::SubmitAction {
+        game_id: game.game_id.clone(),
+        action: GameAction::play_card(card_id.to_string(), None),
+    };
+
+    // Only cards in the player's own hand can be played
+    assert!(server
+        .handle_message(&game.white_id, play_card("missing"))
+        .await
+        .is_err());
+    assert!(drain(&mut game.white_rx).iter().any(|msg| matches!(
+        msg,
+        ServerMessage::InvalidAction { reason } if reason == "Card not in hand: missing"
+    )));
+
+    server
+        .handle_message(&game.white_id, play_card("bonus"))
+        .await
+        .unwrap();
+    let messages = drain(&mut game.black_rx);
+    assert!(messages.iter().any(|msg| matches!(
+        msg,
+        ServerMessage::OpponentAction {
+            action: GameAction::PlayCard { card_id, .. }
+        } if card_id == "bonus"
+    )));
+    let state = messages
+        .into_iter()
+        .find_map(|msg| match msg {
+            ServerMessage::GameStateUpdate { state } => Some(state),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(state.time[&game.white_id], 90);
+    assert_eq!(state.time[&game.black_id], 60);
+    assert_eq!(state.cards_remaining[&game.white_id], 0);
+
+    // The card is used up
+    assert!(server
+        .handle_message(&game.white_id, play_card("bonus"))
+        .await
+        .is_err());
+}